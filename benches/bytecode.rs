@@ -0,0 +1,130 @@
+//! Runs the same programs with the tree walker and the bytecode VM and
+//! prints how long each took, and how much of the VM's time went into
+//! compiling the top-level statements: `cargo bench --bench bytecode`.
+//!
+//! Compiling costs about as much as walking the tree once, so a program
+//! without calls, like `arithmetic`, runs slower on the VM and is better
+//! left to the tree walker. Programs spending their time in calls run
+//! faster on the VM.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use mlor::{
+    evaluator::{context::Context, environment::Environment, error::EvalError},
+    parser::syntax_tree::StatementNode,
+    vm::{compile, Vm},
+    Lexer, Parser, Value,
+};
+
+const ROUNDS: u32 = 20;
+
+/// The tree walker recurses on the native stack for every call.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// A long chain of mixed arithmetic, `1 + 2 * 3 - 4 // 5 + ...`.
+fn arithmetic_chain(length: usize) -> String {
+    let operators = ["+", "*", "-", "//", "%"];
+    let mut source = String::from("1");
+    for i in 0..length {
+        source.push_str(&format!(" {} {}", operators[i % operators.len()], i % 97 + 1));
+    }
+    source
+}
+
+fn programs() -> Vec<(&'static str, String)> {
+    vec![
+        ("arithmetic", arithmetic_chain(1000)),
+        (
+            "fib",
+            "fn fib(n) = if n < 2 then n else fib(n - 1) + fib(n - 2)\nfib(20)".to_string(),
+        ),
+        (
+            "closures",
+            "let compose = fun f -> fun g -> fun x -> f(g(x))\n\
+             fn sum(n, acc) = if n == 0 then acc else sum(n - 1, compose(fun x -> x + 1)(fun x -> x * 2)(acc) % 1000)\n\
+             sum(900, 0)"
+                .to_string(),
+        ),
+        (
+            "logic",
+            "fn count(n, acc) = if n == 0 then acc else count(n - 1, if n % 3 == 0 && n % 5 != 0 || n % 7 == 0 then acc + 1 else acc)\n\
+             count(900, 0)"
+                .to_string(),
+        ),
+    ]
+}
+
+/// Runs every statement, returning the value of the last one and the time it took.
+fn run(
+    statements: &[StatementNode],
+    mut execute: impl FnMut(&StatementNode, &mut Environment) -> Result<Value, EvalError>,
+) -> (Result<Value, EvalError>, Duration) {
+    let start = Instant::now();
+    let mut environment = Environment::default();
+    let mut last = Ok(Value::Int(0));
+    for statement in statements {
+        last = execute(statement, &mut environment);
+    }
+    (last, start.elapsed())
+}
+
+/// Time it takes to compile the expressions of the statements, which the
+/// VM does before running each of them.
+fn compile_time(statements: &[StatementNode], context: &Context) -> Duration {
+    let start = Instant::now();
+    for statement in statements {
+        match statement {
+            StatementNode::LetNode { value: expression, .. } | StatementNode::ExpressionStatementNode(expression) => {
+                std::hint::black_box(compile(expression, context));
+            }
+            StatementNode::FunctionNode { .. } | StatementNode::ErrorNode { .. } => (),
+        }
+    }
+    start.elapsed()
+}
+
+fn main() {
+    thread::Builder::new().stack_size(STACK_SIZE).spawn(benchmark).unwrap().join().unwrap();
+}
+
+fn benchmark() {
+    let mut context = Context::default();
+    context.stack_size = STACK_SIZE / 2;
+    println!(
+        "{:<12} {:>12} {:>12} {:>12} {:>8}",
+        "program", "tree walker", "bytecode", "compiling", "speedup"
+    );
+    for (name, source) in programs() {
+        let (program, errors) = Parser::from_tokens(Lexer::from_str(&source).into_tokens()).parse_program();
+        assert!(errors.is_empty(), "{}: {:?}", name, errors);
+
+        let mut tree_time = Duration::ZERO;
+        let mut bytecode_time = Duration::ZERO;
+        let mut compiling_time = Duration::ZERO;
+        for _ in 0..ROUNDS {
+            let (tree, elapsed) = run(&program.statements, |statement, environment| {
+                statement.execute(&context, environment)
+            });
+            tree_time += elapsed;
+            // a fresh VM each round, so compiling the function bodies is part of the time
+            let mut vm = Vm::default();
+            let (bytecode, elapsed) = run(&program.statements, |statement, environment| {
+                vm.execute(statement, &context, environment)
+            });
+            bytecode_time += elapsed;
+            assert_eq!(tree, bytecode, "{} gives different results", name);
+            compiling_time += compile_time(&program.statements, &context);
+        }
+        println!(
+            "{:<12} {:>12.2?} {:>12.2?} {:>12.2?} {:>7.2}x",
+            name,
+            tree_time / ROUNDS,
+            bytecode_time / ROUNDS,
+            compiling_time / ROUNDS,
+            tree_time.as_secs_f64() / bytecode_time.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    evaluator::error::EvalError,
+    lexer::{
+        source_map::SourceMap,
+        token::{Position, Span},
+    },
+    parser::syntax_tree::InvalidExpressionNode,
+    typechecker::error::TypeError,
+};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Style {
+    Plain,
+    Colored,
+}
+
+/// An error ready to be shown to the user: a stable code, a message and
+/// the part of the source it is about.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub message: String,
+    /// `None` for errors found at the very end of the input.
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    /// Renders the diagnostic with the offending source line, looked up in
+    /// the source map by the offset of the span, and the span underlined by carets:
+    ///
+    /// ```text
+    /// error[E0001]: Expected a number, found `*`
+    ///  --> main.mlor:1:5
+    ///   |
+    /// 1 | 2 * * 3
+    ///   |     ^
+    /// ```
+    ///
+    /// Errors at the end of the input point just past the last character of
+    /// the last file, trailing whitespace aside.
+    pub fn render(&self, sources: &SourceMap, style: Style) -> String {
+        let paint = |color: &str, text: &str| match style {
+            Style::Plain => text.to_string(),
+            Style::Colored => format!("{}{}{}", color, text, RESET),
+        };
+
+        let (file, span) = match self.span {
+            Some(span) => (sources.file(span.start.offset), span),
+            None => {
+                let file = sources.last();
+                let end = file.map_or(Position { column: 1, row: 1, offset: 0 }, |file| {
+                    file.position(file.start + file.text.trim_end().len())
+                });
+                (file, Span { start: end, end })
+            }
+        };
+        let line = file.map_or("", |file| file.line(span.start.row));
+        let name = file.map_or("", |file| file.name.as_str());
+        let start = span.start.column as usize - 1;
+        let width = match span.end.row == span.start.row {
+            true => (span.end.column as usize).saturating_sub(span.start.column as usize),
+            false => line.chars().count().saturating_sub(start),
+        };
+
+        // keep tabs in the padding so the carets line up with the source
+        let padding = line
+            .chars()
+            .chain(std::iter::repeat(' '))
+            .take(start)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let row = span.start.row.to_string();
+        let gutter = " ".repeat(row.len());
+
+        format!(
+            "{}: {}\n{}{} {}:{}:{}\n{} {}\n{} {} {}\n{} {} {}{}",
+            paint(RED, &format!("error[{}]", self.code)),
+            paint(BOLD, &self.message),
+            gutter,
+            paint(BLUE, "-->"),
+            name,
+            span.start.row,
+            span.start.column,
+            gutter,
+            paint(BLUE, "|"),
+            paint(BLUE, &row),
+            paint(BLUE, "|"),
+            line,
+            gutter,
+            paint(BLUE, "|"),
+            padding,
+            paint(RED, &"^".repeat(width.max(1))),
+        )
+    }
+}
+
+impl From<&InvalidExpressionNode> for Diagnostic {
+    fn from(error: &InvalidExpressionNode) -> Self {
+        Self {
+            code: error.code(),
+            message: error.message(),
+            span: error.span(),
+        }
+    }
+}
+
+impl From<&TypeError> for Diagnostic {
+    fn from(error: &TypeError) -> Self {
+        Self {
+            code: error.code(),
+            message: error.message(),
+            span: Some(error.span()),
+        }
+    }
+}
+
+impl From<&EvalError> for Diagnostic {
+    fn from(error: &EvalError) -> Self {
+        Self {
+            code: error.code(),
+            message: error.message(),
+            span: Some(error.span()),
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse_errors(sources: &mut SourceMap, name: &str, source: &str) -> Vec<Diagnostic> {
+    let (_, errors) = crate::parser::parse_program_file(sources.add(name, source));
+    errors.iter().map(Diagnostic::from).collect()
+}
+
+#[test]
+fn render_parse_errors() {
+    let mut sources = SourceMap::default();
+    let diagnostics = parse_errors(&mut sources, "a.mlor", "2 * * 3; (1 + 2");
+    assert_eq!(
+        diagnostics[0].render(&sources, Style::Plain),
+        "error[E0001]: Expected a number, found `*`\n --> a.mlor:1:5\n  |\n1 | 2 * * 3; (1 + 2\n  |     ^"
+    );
+    assert_eq!(
+        diagnostics[1].render(&sources, Style::Plain),
+        "error[E0002]: Unexpected end of input, expected `)`\n --> a.mlor:1:16\n  |\n1 | 2 * * 3; (1 + 2\n  |                ^"
+    );
+
+    let mut sources = SourceMap::default();
+    let diagnostics = parse_errors(&mut sources, "b.mlor", "let x = $12 + 1");
+    assert_eq!(
+        diagnostics[0].render(&sources, Style::Plain),
+        "error[E0001]: Expected a number, found the unrecognised `$12`\n --> b.mlor:1:9\n  |\n1 | let x = $12 + 1\n  |         ^^^"
+    );
+}
+
+#[test]
+fn render_multiline_source() {
+    let mut sources = SourceMap::default();
+    let diagnostics = parse_errors(&mut sources, "a.mlor", "1 +\n\tfoo 2 )");
+    assert_eq!(
+        diagnostics[0].render(&sources, Style::Plain),
+        "error[E0001]: Expected `;`, found `)`\n --> a.mlor:2:8\n  |\n2 | \tfoo 2 )\n  | \t      ^"
+    );
+}
+
+#[test]
+fn render_from_earlier_files() {
+    let mut sources = SourceMap::default();
+    let first = parse_errors(&mut sources, "first.mlor", "let x = 1\nlet y = ) 2");
+    let second = parse_errors(&mut sources, "second.mlor", "x +\n\n");
+    assert_eq!(
+        first[0].render(&sources, Style::Plain),
+        "error[E0001]: Expected a number, found `)`\n --> first.mlor:2:9\n  |\n2 | let y = ) 2\n  |         ^"
+    );
+    assert_eq!(
+        second[0].render(&sources, Style::Plain),
+        "error[E0002]: Unexpected end of input, expected a number\n --> second.mlor:1:4\n  |\n1 | x +\n  |    ^"
+    );
+}
+
+#[test]
+fn render_colored() {
+    let mut sources = SourceMap::default();
+    sources.add("a.mlor", "1 / 0");
+    let diagnostic = Diagnostic {
+        code: "E0201",
+        message: "Division by zero".to_string(),
+        span: Some(Span {
+            start: Position { column: 3, row: 1, offset: 2 },
+            end: Position { column: 4, row: 1, offset: 3 },
+        }),
+    };
+    assert_eq!(
+        diagnostic.render(&sources, Style::Colored),
+        "\x1b[1;31merror[E0201]\x1b[0m: \x1b[1mDivision by zero\x1b[0m\n \x1b[1;34m-->\x1b[0m a.mlor:1:3\n  \x1b[1;34m|\x1b[0m\n\
+         \x1b[1;34m1\x1b[0m \x1b[1;34m|\x1b[0m 1 / 0\n  \x1b[1;34m|\x1b[0m   \x1b[1;31m^\x1b[0m"
+    );
+}
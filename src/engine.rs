@@ -0,0 +1,357 @@
+use std::{fmt, io::Read};
+
+use crate::{
+    diagnostics::{Diagnostic, Style},
+    evaluator::{context::Context, environment::Environment, error::EvalError, value::Value},
+    lexer::{source::ReaderSource, source_map::SourceMap, Lexer},
+    optimizer::Optimizer,
+    parser::{
+        syntax_tree::{InvalidExpressionNode, ProgramNode, StatementNode},
+        Parser,
+    },
+    typechecker::{environment::TypeEnvironment, error::TypeError, types::TypeScheme},
+    vm::Vm,
+};
+
+/// Anything that can stop a source from running.
+#[derive(Debug)]
+pub enum Error {
+    /// Every syntax error found in the source.
+    Syntax(Vec<InvalidExpressionNode>),
+    Type(TypeError),
+    Eval(EvalError),
+    /// The source has no statement to give a value.
+    Empty,
+}
+
+impl Error {
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            Self::Syntax(errors) => errors.iter().map(Diagnostic::from).collect(),
+            Self::Type(error) => vec![Diagnostic::from(error)],
+            Self::Eval(error) => vec![Diagnostic::from(error)],
+            Self::Empty => vec![Diagnostic {
+                code: "E0004",
+                message: "Nothing to evaluate, the source has no statements".to_string(),
+                span: None,
+            }],
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages = self
+            .diagnostics()
+            .into_iter()
+            .map(|diagnostic| format!("error[{}]: {}", diagnostic.code, diagnostic.message))
+            .collect::<Vec<_>>();
+        write!(f, "{}", messages.join("\n"))
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<TypeError> for Error {
+    fn from(error: TypeError) -> Self {
+        Self::Type(error)
+    }
+}
+
+impl From<EvalError> for Error {
+    fn from(error: EvalError) -> Self {
+        Self::Eval(error)
+    }
+}
+
+/// How statements are run, both give the same results and errors.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Backend {
+    /// Evaluates the syntax tree directly.
+    #[default]
+    TreeWalker,
+    /// Compiles expressions to bytecode and runs it on the [`Vm`]. Slower
+    /// than the tree walker on expressions that run once, faster on
+    /// functions called many times.
+    Bytecode,
+}
+
+impl Backend {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tree" => Some(Self::TreeWalker),
+            "bytecode" => Some(Self::Bytecode),
+            _ => None,
+        }
+    }
+}
+
+/// Runs sources one after another, keeping the bindings they make:
+///
+/// ```
+/// let mut engine = mlor::Engine::default();
+/// engine.eval("fn square(x) = x * x").unwrap();
+/// assert_eq!(engine.eval("square(7)").unwrap(), mlor::Value::Int(49));
+/// ```
+///
+/// Every statement is type checked before it runs, and its bindings are
+/// only kept if it runs successfully.
+#[derive(Default)]
+pub struct Engine {
+    context: Context,
+    environment: Environment,
+    types: TypeEnvironment,
+    // earlier sources are kept while functions defined in them are bound
+    sources: SourceMap,
+    inputs: usize,
+    backend: Backend,
+    vm: Vm,
+}
+
+impl Engine {
+    pub fn with_context(context: Context) -> Self {
+        Self {
+            context,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_backend(self, backend: Backend) -> Self {
+        Self { backend, ..self }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Sources errors can point into, for rendering diagnostics: the last
+    /// one parsed and those bound functions were defined in.
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+
+    /// Runs all statements of the source, returning the value of the last one.
+    /// Sources without statements give [`Error::Empty`].
+    pub fn eval(&mut self, source: &str) -> Result<Value, Error> {
+        let name = self.next_input_name();
+        let program = self.parse(name, source)?;
+        let mut last = None;
+        for statement in &program.statements {
+            last = Some(self.execute(statement)?.0);
+        }
+        last.ok_or(Error::Empty)
+    }
+
+    /// Parses a source under the given name, it is kept for diagnostics.
+    pub fn parse(&mut self, name: impl Into<String>, source: impl Into<String>) -> Result<ProgramNode, Error> {
+        self.prune_sources();
+        let file = self.sources.add(name, source);
+        let (program, errors) = Parser::from_tokens(Lexer::from_source_file(file).into_tokens()).parse_program();
+        match errors.is_empty() {
+            true => Ok(program),
+            false => Err(Error::Syntax(errors)),
+        }
+    }
+
+    /// Parses a source as it is read, without reading it whole first. The
+    /// text read is kept for diagnostics, a read error is a syntax error.
+    pub fn parse_reader(&mut self, name: impl Into<String>, reader: impl Read) -> Result<ProgramNode, Error> {
+        self.prune_sources();
+        let mut characters = ReaderSource::new(reader).recording();
+        let lexer = Lexer::from_characters_at(&mut characters, self.sources.next_start());
+        let (program, errors) = Parser::from_tokens(lexer.into_tokens()).parse_program();
+        self.sources.add(name, characters.into_text());
+        match errors.is_empty() {
+            true => Ok(program),
+            false => Err(Error::Syntax(errors)),
+        }
+    }
+
+    /// Type checks and runs a statement, returning its value and type.
+    pub fn execute(&mut self, statement: &StatementNode) -> Result<(Value, TypeScheme), Error> {
+        // the checked bindings are only kept once the statement has run
+        let mut checked_types = self.types.clone();
+        let scheme = statement.check(&self.context, &mut checked_types)?;
+        let value = match self.backend {
+            Backend::TreeWalker => statement.execute(&self.context, &mut self.environment)?,
+            Backend::Bytecode => self.vm.execute(statement, &self.context, &mut self.environment)?,
+        };
+        self.types = checked_types;
+        Ok((value, scheme))
+    }
+
+    /// Types of statements, without running them or keeping their bindings.
+    /// Later statements see the bindings of earlier ones.
+    pub fn check(&self, statements: &[StatementNode]) -> Result<Vec<TypeScheme>, Error> {
+        let mut types = self.types.clone();
+        let schemes = statements.iter().map(|statement| statement.check(&self.context, &mut types));
+        Ok(schemes.collect::<Result<_, _>>()?)
+    }
+
+    /// Simplified statements, optimized with the types of the current
+    /// bindings. They have to be type checked first.
+    pub fn optimize(&self, statements: Vec<StatementNode>) -> Vec<StatementNode> {
+        let mut optimizer = Optimizer::new(&self.context, &self.types);
+        statements.into_iter().map(|statement| optimizer.statement(statement)).collect()
+    }
+
+    /// Binds a value of the given type, shadowing earlier bindings of the name.
+    pub fn bind(&mut self, name: &str, value: Value, scheme: TypeScheme) {
+        self.environment = self.environment.extend(name.to_string(), value);
+        self.types = self.types.extend(name.to_string(), scheme);
+    }
+
+    /// Bound names, each listed once.
+    pub fn names(&self) -> Vec<&str> {
+        self.types.names()
+    }
+
+    /// Forgets all bindings, the sources are kept.
+    pub fn reset(&mut self) {
+        self.environment = Environment::default();
+        self.types = TypeEnvironment::default();
+    }
+
+    /// Renders the error with the sources it points into.
+    pub fn render(&self, error: &Error, style: Style) -> String {
+        let diagnostics = error.diagnostics();
+        let rendered = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.render(&self.sources, style))
+            .collect::<Vec<_>>();
+        rendered.join("\n")
+    }
+
+    /// Drops the sources no bound function was defined in, errors of
+    /// statements that ran earlier have been reported already.
+    fn prune_sources(&mut self) {
+        let functions = self.environment.functions();
+        let bodies = functions.iter().map(|function| function.body.span().start.offset).collect::<Vec<_>>();
+        self.sources.retain(|file| bodies.iter().any(|offset| (file.start..=file.end()).contains(offset)));
+    }
+
+    /// Name the next unnamed source is added to the source map under.
+    pub fn next_input_name(&mut self) -> String {
+        self.inputs += 1;
+        format!("<input {}>", self.inputs)
+    }
+}
+
+#[test]
+fn eval_keeps_bindings_between_sources() {
+    let mut engine = Engine::default();
+    assert_eq!(engine.eval("let x = 2; fn add(a, b) = a + b").unwrap().to_string(), "<fn add(a, b)>");
+    assert_eq!(engine.eval("add(x, 3)\nadd(0.5, 2.0)").unwrap(), Value::Float(2.5));
+    assert!(matches!(engine.eval("let y = x / 0"), Err(Error::Eval(EvalError::DivisionByZero { .. }))));
+    assert!(matches!(engine.eval("y"), Err(Error::Type(TypeError::UndefinedVariable { .. }))));
+
+    engine.bind("z", Value::Bool(true), TypeScheme::monomorphic(crate::typechecker::types::Type::Bool));
+    assert_eq!(engine.eval("if z then x else 0").unwrap(), Value::Int(2));
+    assert_eq!(engine.names(), ["z", "add", "x"]);
+
+    engine.reset();
+    assert!(engine.eval("x").is_err());
+}
+
+#[test]
+fn eval_errors() {
+    let mut engine = Engine::default();
+    let error = engine.eval("1 + ; 2 *").unwrap_err();
+    assert!(matches!(&error, Error::Syntax(errors) if errors.len() == 2));
+    assert_eq!(
+        error.to_string(),
+        "error[E0001]: Expected a number, found `;`\nerror[E0002]: Unexpected end of input, expected a number"
+    );
+    assert_eq!(
+        engine.render(&error, Style::Plain).lines().nth(1),
+        Some(" --> <input 1>:1:5")
+    );
+
+    let error = engine.eval("true + 1").unwrap_err();
+    assert_eq!(error.to_string(), "error[E0103]: Expected a number, found bool");
+    assert!(matches!(engine.eval(" "), Err(Error::Empty)));
+    assert!(matches!(engine.eval("# nothing\n"), Err(Error::Empty)));
+    let rendered = engine.render(&Error::Empty, Style::Plain);
+    assert_eq!(rendered.lines().next(), Some("error[E0004]: Nothing to evaluate, the source has no statements"));
+}
+
+#[test]
+fn keep_the_sources_of_bound_functions() {
+    let mut engine = Engine::default();
+    engine.eval("fn f(x) = 1 / x; let g = fun x -> f(x)").unwrap();
+    for _ in 0..10 {
+        engine.eval("let y = 1; y + 1").unwrap();
+    }
+    engine.eval("let f = 2").unwrap();
+    let names = |engine: &Engine| engine.sources().files().iter().map(|file| file.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&engine), ["<input 1>", "<input 12>"]);
+
+    // the shadowed f is still called by g and its errors point at its body
+    let error = engine.eval("g(0)").unwrap_err();
+    assert_eq!(engine.render(&error, Style::Plain).lines().nth(1), Some(" --> <input 1>:1:13"));
+    assert_eq!(names(&engine), ["<input 1>", "<input 13>"]);
+
+    engine.reset();
+    engine.eval("1").unwrap();
+    assert_eq!(names(&engine), ["<input 14>"]);
+}
+
+#[test]
+fn parse_from_a_reader() {
+    let mut engine = Engine::default();
+    engine.eval("let x = 1").unwrap();
+    let program = engine.parse_reader("a.mlor", "fn f(y) = y / 0;\nf(x)".as_bytes()).unwrap();
+    engine.execute(&program.statements[0]).unwrap();
+    let error = engine.execute(&program.statements[1]).unwrap_err();
+    let rendered = engine.render(&error, Style::Plain);
+    assert_eq!(rendered.lines().nth(1), Some(" --> a.mlor:1:13"));
+    assert_eq!(rendered.lines().nth(3), Some("1 | fn f(y) = y / 0;"));
+
+    let error = engine.parse_reader("b.mlor", "1 +\n 2 *".as_bytes()).unwrap_err();
+    assert_eq!(engine.render(&error, Style::Plain).lines().nth(1), Some(" --> b.mlor:2:5"));
+}
+
+#[test]
+fn promote_integers_meeting_floats() {
+    for backend in [Backend::TreeWalker, Backend::Bytecode] {
+        let mut engine = Engine::default().with_backend(backend);
+        assert_eq!(engine.eval("1.5 * 2").unwrap(), Value::Float(3.0));
+        assert_eq!(engine.eval("let x = 1; x + 2.0 ** 0.5 * 0").unwrap(), Value::Float(1.0));
+        // the integer branch is a float too, so the division is not truncated
+        assert_eq!(engine.eval("(if x > 0 then 7 else 2.0) / 2").unwrap(), Value::Float(3.5));
+        let program = engine.parse("<optimized>", "if true then 7 else 2.0").unwrap();
+        engine.check(&program.statements).unwrap();
+        let optimized = engine.optimize(program.statements);
+        assert_eq!(engine.execute(&optimized[0]).unwrap().0, Value::Float(7.0));
+    }
+}
+
+#[test]
+fn default_call_depth_fits_a_thread() {
+    use crate::evaluator::context::DEFAULT_MAX_CALL_DEPTH;
+
+    std::thread::spawn(|| {
+        let mut engine = Engine::default();
+        engine.eval("fn f(n) = if n == 0 then 0 else 1 + f(n - 1)").unwrap();
+        let deepest = engine.eval(&format!("f({})", DEFAULT_MAX_CALL_DEPTH - 1));
+        assert_eq!(deepest.unwrap(), Value::Int(DEFAULT_MAX_CALL_DEPTH as i32 - 1));
+        assert!(matches!(
+            engine.eval(&format!("f({})", DEFAULT_MAX_CALL_DEPTH)),
+            Err(Error::Eval(EvalError::RecursionLimit { limit: DEFAULT_MAX_CALL_DEPTH, .. }))
+        ));
+
+        let mut unlimited = Engine::with_context(Context { max_call_depth: usize::MAX, ..Context::default() });
+        let result = unlimited.eval("fn f(n) = if n == 0 then 0 else 1 + f(n - 1); f(1000000)");
+        assert!(matches!(result, Err(Error::Eval(EvalError::StackOverflow { .. }))));
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn bytecode_backend() {
+    let mut engine = Engine::default().with_backend(Backend::Bytecode);
+    engine.eval("let k = 3; fn scale(x) = k * x").unwrap();
+    assert_eq!(engine.eval("scale(2) + (fun y -> y - 1)(k)").unwrap(), Value::Int(8));
+    assert!(matches!(engine.eval("scale(1) / 0"), Err(Error::Eval(EvalError::DivisionByZero { .. }))));
+}
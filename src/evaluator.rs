@@ -0,0 +1,1068 @@
+use std::{cell::OnceCell, cmp::Ordering, rc::Rc};
+
+use crate::lexer::token::Span;
+use crate::numeric::{bigint::BigInt, rational::Rational};
+use crate::parser::syntax_tree::{ComparisonOperator, ExpressionNode, FactorNode, StatementNode, TermNode};
+#[cfg(test)]
+use crate::parser::{evaluate_str, evaluate_str_in_mode, parse_valid_statement, span};
+
+use self::{
+    context::{Context, NumericMode},
+    environment::Environment,
+    error::{EvalError, NumberKind, OperandKind},
+    value::{Function, Value},
+};
+
+pub mod context;
+pub mod environment;
+pub mod error;
+pub mod value;
+
+/// `Div` truncates integers towards zero, while `IntDiv` rounds towards
+/// negative infinity for every kind of number and `Mod` is the matching
+/// remainder: it takes the sign of the divisor, so `a == (a // b) * b + a % b`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ArithmeticOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    IntDiv,
+    Mod,
+    Pow,
+}
+
+impl ArithmeticOperator {
+    fn is_division(self) -> bool {
+        matches!(self, Self::Div | Self::IntDiv | Self::Mod)
+    }
+}
+
+/// Applies an arithmetic operator to two numbers. Two ints give an int,
+/// otherwise the narrower side is promoted along int -> big int -> rational
+/// -> float, so as soon as either side is a float the result is a float.
+pub(crate) fn apply_arithmetic(operator: ArithmeticOperator, left: Value, right: Value, span: Span) -> Result<Value, EvalError> {
+    let invalid_operand = || EvalError::InvalidOperand {
+        expected: OperandKind::Number,
+        span,
+    };
+    match (left, right) {
+        (Value::Int(left), Value::Int(right)) => apply_int_arithmetic(operator, left, right, span),
+        (left @ Value::Float(_), right) | (left, right @ Value::Float(_)) => {
+            let (left, right) = left.to_float().zip(right.to_float()).ok_or_else(invalid_operand)?;
+            apply_float_arithmetic(operator, left, right, span)
+        }
+        (left @ Value::Rational(_), right) | (left, right @ Value::Rational(_)) => {
+            let (left, right) = left.to_rational().zip(right.to_rational()).ok_or_else(invalid_operand)?;
+            apply_rational_arithmetic(operator, &left, &right, span)
+        }
+        (left, right) => {
+            let (left, right) = left.to_bigint().zip(right.to_bigint()).ok_or_else(invalid_operand)?;
+            apply_bigint_arithmetic(operator, &left, &right, span)
+        }
+    }
+}
+
+fn apply_int_arithmetic(operator: ArithmeticOperator, left: i32, right: i32, span: Span) -> Result<Value, EvalError> {
+    if operator.is_division() && right == 0 {
+        return Err(EvalError::DivisionByZero { span });
+    }
+
+    // every operation on two i32 values but a power fits in an i64, so the
+    // result only has to be checked against the i32 bounds afterwards
+    let (left, right) = (i64::from(left), i64::from(right));
+    let result = match operator {
+        ArithmeticOperator::Add => left + right,
+        ArithmeticOperator::Sub => left - right,
+        ArithmeticOperator::Mul => left * right,
+        ArithmeticOperator::Div => left / right,
+        ArithmeticOperator::IntDiv => floor_div_rem(left, right).0,
+        ArithmeticOperator::Mod => floor_div_rem(left, right).1,
+        ArithmeticOperator::Pow => {
+            let exponent = u32::try_from(right).map_err(|_| EvalError::InvalidExponent { span })?;
+            match left.checked_pow(exponent) {
+                Some(result) => result,
+                None if left < 0 && exponent % 2 == 1 => {
+                    return Err(EvalError::Underflow { kind: NumberKind::Integer, span })
+                }
+                None => return Err(EvalError::Overflow { kind: NumberKind::Integer, span }),
+            }
+        }
+    };
+
+    if result > i64::from(i32::MAX) {
+        Err(EvalError::Overflow { kind: NumberKind::Integer, span })
+    } else if result < i64::from(i32::MIN) {
+        Err(EvalError::Underflow { kind: NumberKind::Integer, span })
+    } else {
+        Ok(Value::Int(result as i32))
+    }
+}
+
+fn floor_div_rem(left: i64, right: i64) -> (i64, i64) {
+    let (quotient, remainder) = (left / right, left % right);
+    if remainder != 0 && (remainder < 0) != (right < 0) {
+        (quotient - 1, remainder + right)
+    } else {
+        (quotient, remainder)
+    }
+}
+
+fn apply_float_arithmetic(operator: ArithmeticOperator, left: f64, right: f64, span: Span) -> Result<Value, EvalError> {
+    if operator.is_division() && right == 0.0 {
+        return Err(EvalError::DivisionByZero { span });
+    }
+
+    let result = match operator {
+        ArithmeticOperator::Add => left + right,
+        ArithmeticOperator::Sub => left - right,
+        ArithmeticOperator::Mul => left * right,
+        ArithmeticOperator::Div => left / right,
+        ArithmeticOperator::IntDiv => (left / right).floor(),
+        ArithmeticOperator::Mod => left - right * (left / right).floor(),
+        ArithmeticOperator::Pow => left.powf(right),
+    };
+
+    // finite operands only ever leave the finite range by exceeding it
+    if result == f64::INFINITY && left.is_finite() && right.is_finite() {
+        Err(EvalError::Overflow { kind: NumberKind::Float, span })
+    } else if result == f64::NEG_INFINITY && left.is_finite() && right.is_finite() {
+        Err(EvalError::Underflow { kind: NumberKind::Float, span })
+    } else {
+        Ok(Value::Float(result))
+    }
+}
+
+fn apply_bigint_arithmetic(
+    operator: ArithmeticOperator,
+    left: &BigInt,
+    right: &BigInt,
+    span: Span,
+) -> Result<Value, EvalError> {
+    let result = match operator {
+        ArithmeticOperator::Add => left + right,
+        ArithmeticOperator::Sub => left - right,
+        ArithmeticOperator::Mul => left * right,
+        ArithmeticOperator::Div => match left.div_rem(right) {
+            Some((quotient, _)) => quotient,
+            None => return Err(EvalError::DivisionByZero { span }),
+        },
+        ArithmeticOperator::IntDiv | ArithmeticOperator::Mod => match left.div_rem_floor(right) {
+            Some((quotient, _)) if operator == ArithmeticOperator::IntDiv => quotient,
+            Some((_, remainder)) => remainder,
+            None => return Err(EvalError::DivisionByZero { span }),
+        },
+        ArithmeticOperator::Pow => {
+            if right.is_negative() {
+                return Err(EvalError::InvalidExponent { span });
+            }
+            let exponent = right.to_i64().and_then(|exponent| u32::try_from(exponent).ok());
+            let exponent = exponent.ok_or(EvalError::Overflow { kind: NumberKind::Integer, span })?;
+            if power_digits(left, exponent) > MAX_POWER_DIGITS {
+                return Err(EvalError::Overflow { kind: NumberKind::Integer, span });
+            }
+            left.pow(exponent)
+        }
+    };
+    Ok(Value::BigInt(result))
+}
+
+fn apply_rational_arithmetic(
+    operator: ArithmeticOperator,
+    left: &Rational,
+    right: &Rational,
+    span: Span,
+) -> Result<Value, EvalError> {
+    let result = match operator {
+        ArithmeticOperator::Add => left + right,
+        ArithmeticOperator::Sub => left - right,
+        ArithmeticOperator::Mul => left * right,
+        ArithmeticOperator::Div => left.checked_div(right).ok_or(EvalError::DivisionByZero { span })?,
+        ArithmeticOperator::IntDiv | ArithmeticOperator::Mod => {
+            let quotient = Rational::from(left.checked_div(right).ok_or(EvalError::DivisionByZero { span })?.floor());
+            match operator {
+                ArithmeticOperator::IntDiv => quotient,
+                _ => left - &(right * &quotient),
+            }
+        }
+        ArithmeticOperator::Pow => {
+            if !right.is_integer() {
+                return Err(EvalError::InvalidExponent { span });
+            }
+            let exponent = right.numerator().to_i32().ok_or(EvalError::Overflow { kind: NumberKind::Fraction, span })?;
+            let magnitude = exponent.unsigned_abs();
+            if power_digits(left.numerator(), magnitude).max(power_digits(left.denominator(), magnitude)) > MAX_POWER_DIGITS
+            {
+                return Err(EvalError::Overflow { kind: NumberKind::Fraction, span });
+            }
+            left.pow(exponent).ok_or(EvalError::DivisionByZero { span })?
+        }
+    };
+    Ok(Value::Rational(result))
+}
+
+/// Most decimal digits the result of a power can have, computing larger
+/// ones would take too long.
+const MAX_POWER_DIGITS: f64 = 100_000.0;
+
+/// About how many decimal digits the power has.
+fn power_digits(base: &BigInt, exponent: u32) -> f64 {
+    match base.is_zero() {
+        true => 1.0,
+        false => f64::from(exponent) * base.log10(),
+    }
+}
+
+pub(crate) fn negate(value: Value, span: Span) -> Result<Value, EvalError> {
+    match value {
+        Value::Int(value) => apply_int_arithmetic(ArithmeticOperator::Sub, 0, value, span),
+        Value::BigInt(value) => Ok(Value::BigInt(-value)),
+        Value::Rational(value) => Ok(Value::Rational(-&value)),
+        Value::Float(value) => Ok(Value::Float(-value)),
+        Value::Bool(_) | Value::Function(_) => Err(EvalError::InvalidOperand {
+            expected: OperandKind::Number,
+            span,
+        }),
+    }
+}
+
+/// Orders two numbers, promoting them the same way arithmetic does.
+/// `None` when either value is not a number.
+fn numeric_ordering(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Int(left), Value::Int(right)) => Some(left.cmp(right)),
+        (Value::Float(_), _) | (_, Value::Float(_)) => left.to_float()?.partial_cmp(&right.to_float()?),
+        (Value::Rational(_), _) | (_, Value::Rational(_)) => {
+            let difference = &left.to_rational()? - &right.to_rational()?;
+            Some(difference.numerator().cmp(&BigInt::zero()))
+        }
+        _ => Some(left.to_bigint()?.cmp(&right.to_bigint()?)),
+    }
+}
+
+/// Compares two numbers, or two booleans for (in)equality.
+pub(crate) fn compare(operator: ComparisonOperator, left: Value, right: Value, span: Span) -> Result<Value, EvalError> {
+    let invalid_operand = EvalError::InvalidOperand {
+        expected: OperandKind::Number,
+        span,
+    };
+    let ordering = match (&left, &right) {
+        (Value::Bool(left), Value::Bool(right)) => match operator {
+            ComparisonOperator::Equal | ComparisonOperator::NotEqual => left.cmp(right),
+            _ => return Err(invalid_operand),
+        },
+        _ => numeric_ordering(&left, &right).ok_or(invalid_operand)?,
+    };
+    let result = match operator {
+        ComparisonOperator::Equal => ordering == Ordering::Equal,
+        ComparisonOperator::NotEqual => ordering != Ordering::Equal,
+        ComparisonOperator::Less => ordering == Ordering::Less,
+        ComparisonOperator::LessEqual => ordering != Ordering::Greater,
+        ComparisonOperator::Greater => ordering == Ordering::Greater,
+        ComparisonOperator::GreaterEqual => ordering != Ordering::Less,
+    };
+    Ok(Value::Bool(result))
+}
+
+/// The number as a float, for a conditional with an integer and a float branch.
+pub(crate) fn to_float(value: Value) -> Value {
+    value.to_float().map_or(value, Value::Float)
+}
+
+pub(crate) fn to_bool(value: Value, span: Span) -> Result<bool, EvalError> {
+    match value {
+        Value::Bool(value) => Ok(value),
+        _ => Err(EvalError::InvalidOperand {
+            expected: OperandKind::Boolean,
+            span,
+        }),
+    }
+}
+
+/// Calls a function in a fresh frame: the environment the function was
+/// defined in, extended with the function itself and its arguments.
+fn call_function(callee: Value, arguments: Vec<Value>, span: Span, context: &Context) -> Result<Value, EvalError> {
+    let (function, frame) = enter_function(callee, arguments, span, context)?;
+    let result = function.body.evaluate(context, &frame);
+    context.call_depth.set(context.call_depth.get() - 1);
+    result
+}
+
+/// Checks a call and builds the frame its body runs in, counting it towards
+/// the call depth. The caller has to decrease the depth once the body ran.
+pub(crate) fn enter_function(
+    callee: Value,
+    arguments: Vec<Value>,
+    span: Span,
+    context: &Context,
+) -> Result<(Rc<Function>, Environment), EvalError> {
+    let function = match callee {
+        Value::Function(function) => function,
+        _ => return Err(EvalError::NotAFunction { span }),
+    };
+    if arguments.len() != function.parameters.len() {
+        return Err(EvalError::ArityMismatch {
+            expected: function.parameters.len(),
+            got: arguments.len(),
+            span,
+        });
+    }
+
+    let depth = context.call_depth.get();
+    let stack = stack_address();
+    if depth == 0 {
+        context.stack_start.set(stack);
+    }
+    if depth >= context.max_call_depth {
+        return Err(EvalError::RecursionLimit { limit: context.max_call_depth, span });
+    }
+    // the stack grows down on every supported platform
+    if context.stack_start.get().saturating_sub(stack) > context.stack_size {
+        return Err(EvalError::StackOverflow { depth, span });
+    }
+
+    let mut frame = function.environment.clone();
+    if let Some(name) = &function.name {
+        frame = frame.extend(name.clone(), Value::Function(function.clone()));
+    }
+    for (parameter, argument) in function.parameters.iter().zip(arguments) {
+        frame = frame.extend(parameter.clone(), argument);
+    }
+
+    context.call_depth.set(depth + 1);
+    Ok((function, frame))
+}
+
+/// Address of a local of the calling function, which tells how deep the
+/// stack is.
+#[inline(never)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+/// Turns an integer literal into a value of the active numeric mode.
+pub(crate) fn int_literal(value: &BigInt, span: Span, context: &Context) -> Result<Value, EvalError> {
+    match context.numeric_mode {
+        NumericMode::Machine => match value.to_i32() {
+            Some(value) => Ok(Value::Int(value)),
+            None => Err(EvalError::Overflow { kind: NumberKind::Integer, span }),
+        },
+        NumericMode::BigInt => Ok(Value::BigInt(value.clone())),
+        NumericMode::Rational => Ok(Value::Rational(Rational::from(value.clone()))),
+    }
+}
+
+impl StatementNode {
+    /// Runs the statement, a `let` extends the environment with its binding.
+    /// Returns the value of the expression or the bound value.
+    pub fn execute(&self, context: &Context, environment: &mut Environment) -> Result<Value, EvalError> {
+        match self {
+            Self::LetNode { name, value, .. } => {
+                let value = value.evaluate(context, environment)?;
+                *environment = environment.extend(name.clone(), value.clone());
+                Ok(value)
+            }
+            Self::FunctionNode {
+                name,
+                parameters,
+                body,
+                ..
+            } => {
+                let function = Value::Function(Rc::new(Function {
+                    name: Some(name.clone()),
+                    parameters: parameters.clone(),
+                    body: body.clone(),
+                    environment: environment.clone(),
+                    chunk: OnceCell::new(),
+                }));
+                *environment = environment.extend(name.clone(), function.clone());
+                Ok(function)
+            }
+            Self::ExpressionStatementNode(exp) => exp.evaluate(context, environment),
+            Self::ErrorNode { span } => Err(EvalError::InvalidSyntax { span: *span }),
+        }
+    }
+}
+
+// The evaluators of the nodes only dispatch, the arms call out to functions
+// of their own. A nested expression stacks up one frame of each evaluator,
+// and without optimizations a frame has room for every arm.
+impl ExpressionNode {
+    pub fn evaluate(&self, context: &Context, environment: &Environment) -> Result<Value, EvalError> {
+        match self {
+            Self::SingleTermNode(t) => t.evaluate(context, environment),
+            Self::OrNode {
+                left,
+                right,
+                operator_span,
+            } => logical(true, &**left, &**right, *operator_span, context, environment),
+            Self::AndNode {
+                left,
+                right,
+                operator_span,
+            } => logical(false, &**left, &**right, *operator_span, context, environment),
+            Self::ComparisonNode {
+                operator,
+                left,
+                right,
+                operator_span,
+            } => comparison(*operator, &**left, &**right, *operator_span, context, environment),
+            Self::AdditionTermNode {
+                left,
+                right,
+                operator_span,
+            } => arithmetic(ArithmeticOperator::Add, &**left, right, *operator_span, context, environment),
+            Self::SubstractionTermNode {
+                left,
+                right,
+                operator_span,
+            } => arithmetic(ArithmeticOperator::Sub, &**left, right, *operator_span, context, environment),
+        }
+    }
+}
+
+impl TermNode {
+    pub fn evaluate(&self, context: &Context, environment: &Environment) -> Result<Value, EvalError> {
+        match self {
+            Self::SingleFactorNode(t) => t.evaluate(context, environment),
+            Self::MultiplicationFactorNode {
+                left,
+                right,
+                operator_span,
+            } => arithmetic(ArithmeticOperator::Mul, &**left, right, *operator_span, context, environment),
+            Self::DivisionFactorNode {
+                left,
+                right,
+                operator_span,
+            } => arithmetic(ArithmeticOperator::Div, &**left, right, *operator_span, context, environment),
+            Self::IntegerDivisionFactorNode {
+                left,
+                right,
+                operator_span,
+            } => arithmetic(ArithmeticOperator::IntDiv, &**left, right, *operator_span, context, environment),
+            Self::ModuloFactorNode {
+                left,
+                right,
+                operator_span,
+            } => arithmetic(ArithmeticOperator::Mod, &**left, right, *operator_span, context, environment),
+        }
+    }
+}
+
+impl FactorNode {
+    pub fn evaluate(&self, context: &Context, environment: &Environment) -> Result<Value, EvalError> {
+        match self {
+            Self::LiteralNode { value, span } => int_literal(value, *span, context),
+            Self::FloatLiteralNode { value, .. } => Ok(Value::Float(*value)),
+            Self::BoolLiteralNode { value, .. } => Ok(Value::Bool(*value)),
+            Self::VariableNode { name, span } => variable(name, *span, environment),
+            Self::LambdaNode { parameter, body, .. } => Ok(lambda(parameter, body, environment)),
+            Self::IfNode {
+                condition,
+                consequence,
+                alternative,
+                if_span,
+                widen,
+                ..
+            } => conditional([condition, consequence, alternative], *if_span, widen.get(), context, environment),
+            Self::CallNode {
+                callee,
+                arguments,
+                arguments_span,
+            } => call(callee, arguments, *arguments_span, context, environment),
+            Self::ExpressionNode { expression: exp, .. } => exp.evaluate(context, environment),
+            Self::PowerNode {
+                base,
+                exponent,
+                operator_span,
+            } => arithmetic(ArithmeticOperator::Pow, &**base, &**exponent, *operator_span, context, environment),
+            Self::NegativeExpressionNode { operand, operator_span } => negative(operand, *operator_span, context, environment),
+            Self::NotExpressionNode { operand, operator_span } => not(operand, *operator_span, context, environment),
+            Self::ErrorNode { span } => Err(EvalError::InvalidSyntax { span: *span }),
+        }
+    }
+}
+
+/// Node the arms of the evaluators take operands from.
+trait Operand {
+    fn value(&self, context: &Context, environment: &Environment) -> Result<Value, EvalError>;
+}
+
+impl Operand for ExpressionNode {
+    fn value(&self, context: &Context, environment: &Environment) -> Result<Value, EvalError> {
+        self.evaluate(context, environment)
+    }
+}
+
+impl Operand for TermNode {
+    fn value(&self, context: &Context, environment: &Environment) -> Result<Value, EvalError> {
+        self.evaluate(context, environment)
+    }
+}
+
+impl Operand for FactorNode {
+    fn value(&self, context: &Context, environment: &Environment) -> Result<Value, EvalError> {
+        self.evaluate(context, environment)
+    }
+}
+
+fn arithmetic(
+    operator: ArithmeticOperator,
+    left: &impl Operand,
+    right: &impl Operand,
+    span: Span,
+    context: &Context,
+    environment: &Environment,
+) -> Result<Value, EvalError> {
+    let left = left.value(context, environment)?;
+    apply_arithmetic(operator, left, right.value(context, environment)?, span)
+}
+
+fn comparison(
+    operator: ComparisonOperator,
+    left: &impl Operand,
+    right: &impl Operand,
+    span: Span,
+    context: &Context,
+    environment: &Environment,
+) -> Result<Value, EvalError> {
+    let left = left.value(context, environment)?;
+    compare(operator, left, right.value(context, environment)?, span)
+}
+
+/// `||` when `or`, `&&` otherwise. The right operand is only evaluated
+/// when the left one does not decide the result.
+fn logical(
+    or: bool,
+    left: &impl Operand,
+    right: &impl Operand,
+    span: Span,
+    context: &Context,
+    environment: &Environment,
+) -> Result<Value, EvalError> {
+    match to_bool(left.value(context, environment)?, span)? {
+        decided if decided == or => Ok(Value::Bool(or)),
+        _ => Ok(Value::Bool(to_bool(right.value(context, environment)?, span)?)),
+    }
+}
+
+fn variable(name: &str, span: Span, environment: &Environment) -> Result<Value, EvalError> {
+    environment.lookup(name).cloned().ok_or_else(|| EvalError::UndefinedVariable {
+        name: name.to_string(),
+        span,
+    })
+}
+
+fn lambda(parameter: &str, body: &Rc<ExpressionNode>, environment: &Environment) -> Value {
+    Value::Function(Rc::new(Function {
+        name: None,
+        parameters: vec![parameter.to_string()],
+        body: body.clone(),
+        environment: environment.clone(),
+        chunk: OnceCell::new(),
+    }))
+}
+
+/// Evaluates the branch the condition selects, converted to a float when
+/// the conditional is widened.
+fn conditional(
+    [condition, consequence, alternative]: [&ExpressionNode; 3],
+    span: Span,
+    widen: bool,
+    context: &Context,
+    environment: &Environment,
+) -> Result<Value, EvalError> {
+    let value = match to_bool(condition.evaluate(context, environment)?, span)? {
+        true => consequence.evaluate(context, environment)?,
+        false => alternative.evaluate(context, environment)?,
+    };
+    Ok(if widen { to_float(value) } else { value })
+}
+
+fn call(
+    callee: &FactorNode,
+    arguments: &[ExpressionNode],
+    span: Span,
+    context: &Context,
+    environment: &Environment,
+) -> Result<Value, EvalError> {
+    let callee = callee.evaluate(context, environment)?;
+    let arguments = arguments
+        .iter()
+        .map(|argument| argument.evaluate(context, environment))
+        .collect::<Result<Vec<_>, _>>()?;
+    call_function(callee, arguments, span, context)
+}
+
+fn negative(operand: &FactorNode, span: Span, context: &Context, environment: &Environment) -> Result<Value, EvalError> {
+    match operand {
+        // negated before it becomes a value, so that -2147483648 fits into an int
+        FactorNode::LiteralNode { value, span: literal_span } => int_literal(&-value, span.to(*literal_span), context),
+        operand => negate(operand.evaluate(context, environment)?, span),
+    }
+}
+
+fn not(operand: &FactorNode, span: Span, context: &Context, environment: &Environment) -> Result<Value, EvalError> {
+    Ok(Value::Bool(!to_bool(operand.evaluate(context, environment)?, span)?))
+}
+
+#[test]
+fn evaluate_arithmetic() {
+    assert_eq!(evaluate_str("2 * 3 - 4 / 2"), Ok(Value::Int(4)));
+    assert_eq!(evaluate_str("-(2 - 7)"), Ok(Value::Int(5)));
+    assert_eq!(evaluate_str("2147483647 - 1 + 1"), Ok(Value::Int(i32::MAX)));
+    assert_eq!(evaluate_str("-2147483647 - 1"), Ok(Value::Int(i32::MIN)));
+}
+
+#[test]
+fn negate_literals_before_they_overflow() {
+    assert_eq!(evaluate_str("-2147483648"), Ok(Value::Int(i32::MIN)));
+    assert_eq!(evaluate_str("1 + -2147483648"), Ok(Value::Int(-2147483647)));
+    assert_eq!(evaluate_str("-2147483649"), Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(1, 12) }));
+    assert_eq!(
+        evaluate_str("-(2147483648)"),
+        Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(3, 13) })
+    );
+    assert_eq!(evaluate_str("--2147483648"), Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(1, 2) }));
+}
+
+#[test]
+fn evaluate_float_arithmetic() {
+    assert_eq!(evaluate_str("1.5 * 2"), Ok(Value::Float(3.0)));
+    assert_eq!(evaluate_str("2 * 1.5"), Ok(Value::Float(3.0)));
+    assert_eq!(evaluate_str(".5 + .25"), Ok(Value::Float(0.75)));
+    assert_eq!(evaluate_str("7 / 2"), Ok(Value::Int(3)));
+    assert_eq!(evaluate_str("7 / 2.0"), Ok(Value::Float(3.5)));
+    assert_eq!(evaluate_str("-(1.5e2)"), Ok(Value::Float(-150.0)));
+    assert_eq!(evaluate_str("2.5e-1 * 4"), Ok(Value::Float(1.0)));
+}
+
+#[test]
+fn evaluate_division_by_zero() {
+    assert_eq!(evaluate_str("1/0"), Err(EvalError::DivisionByZero { span: span(2, 3) }));
+    assert_eq!(evaluate_str("4 / (2 - 2)"), Err(EvalError::DivisionByZero { span: span(3, 4) }));
+    assert_eq!(evaluate_str("1.5 / 0"), Err(EvalError::DivisionByZero { span: span(5, 6) }));
+}
+
+#[test]
+fn evaluate_overflow() {
+    assert_eq!(
+        evaluate_str("2147483647+1"),
+        Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(11, 12) })
+    );
+    assert_eq!(evaluate_str("65536 * 32768"), Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(7, 8) }));
+    assert_eq!(
+        evaluate_str("(-2147483647 - 1) / -1"),
+        Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(19, 20) })
+    );
+    assert_eq!(
+        evaluate_str("-(-2147483647 - 1)"),
+        Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(1, 2) })
+    );
+    assert_eq!(evaluate_str("1e308 * 10"), Err(EvalError::Overflow { kind: NumberKind::Float, span: span(7, 8) }));
+}
+
+#[test]
+fn evaluate_underflow() {
+    assert_eq!(
+        evaluate_str("-2147483647 - 2"),
+        Err(EvalError::Underflow { kind: NumberKind::Integer, span: span(13, 14) })
+    );
+    assert_eq!(
+        evaluate_str("65536 * -32769"),
+        Err(EvalError::Underflow { kind: NumberKind::Integer, span: span(7, 8) })
+    );
+    assert_eq!(evaluate_str("-1e308 - 1e308"), Err(EvalError::Underflow { kind: NumberKind::Float, span: span(8, 9) }));
+}
+
+#[test]
+fn describe_errors_by_operand() {
+    let message = |source| evaluate_str(source).unwrap_err().message();
+    assert_eq!(message("2147483647 + 1"), "Integer overflow");
+    assert_eq!(message("1e308 * 10"), "Float overflow");
+    assert_eq!(message("-1e308 - 1e308"), "Float underflow");
+    let fraction = evaluate_str_in_mode("(1 / 3) ** -2000000000", NumericMode::Rational);
+    assert_eq!(fraction.unwrap_err().message(), "Fraction overflow");
+
+    assert_eq!(message("true + 1"), "Operator applied to a value that is not a number");
+    assert_eq!(message("if 1 then 2 else 3"), "Condition or logical operand is not a boolean");
+    assert_eq!(message("false || 1"), "Condition or logical operand is not a boolean");
+}
+
+#[test]
+fn evaluate_literal_overflow() {
+    assert_eq!(evaluate_str("9000000000"), Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(1, 11) }));
+}
+
+#[test]
+fn evaluate_bigint_arithmetic() {
+    let bigint = |string: &str| Ok(Value::BigInt(string.parse().unwrap()));
+
+    assert_eq!(evaluate_str_in_mode("9000000000", NumericMode::BigInt), bigint("9000000000"));
+    assert_eq!(evaluate_str_in_mode("2147483647 + 1", NumericMode::BigInt), bigint("2147483648"));
+    assert_eq!(
+        evaluate_str_in_mode("99999999999999999999 * 99999999999999999999", NumericMode::BigInt),
+        bigint("9999999999999999999800000000000000000001")
+    );
+    assert_eq!(
+        evaluate_str_in_mode("-100000000000000000000 / 3 - 1", NumericMode::BigInt),
+        bigint("-33333333333333333334")
+    );
+    assert_eq!(evaluate_str_in_mode("10000000000 * 0.5", NumericMode::BigInt), Ok(Value::Float(5e9)));
+    assert_eq!(
+        evaluate_str_in_mode("100000000000000000000 / 0", NumericMode::BigInt),
+        Err(EvalError::DivisionByZero { span: span(23, 24) })
+    );
+}
+
+#[test]
+fn evaluate_rational_arithmetic() {
+    let rational = |string: &str| {
+        let (numerator, denominator) = string.split_once('/').unwrap();
+        Ok(Value::Rational(
+            Rational::new(numerator.parse().unwrap(), denominator.parse().unwrap()).unwrap(),
+        ))
+    };
+
+    assert_eq!(evaluate_str_in_mode("7 / 2", NumericMode::Rational), rational("7/2"));
+    assert_eq!(evaluate_str_in_mode("1 / 3 + 1 / 6", NumericMode::Rational), rational("1/2"));
+    assert_eq!(evaluate_str_in_mode("-(4 / 6) * 3", NumericMode::Rational), rational("-2/1"));
+    assert_eq!(
+        evaluate_str_in_mode("1 / 100000000000000000000", NumericMode::Rational),
+        rational("1/100000000000000000000")
+    );
+    assert_eq!(evaluate_str_in_mode("1 / 4 + 0.5", NumericMode::Rational), Ok(Value::Float(0.75)));
+    assert_eq!(
+        evaluate_str_in_mode("1 / (1 / 2 - 1 / 2)", NumericMode::Rational),
+        Err(EvalError::DivisionByZero { span: span(3, 4) })
+    );
+}
+
+#[test]
+fn execute_let_bindings() {
+    let context = Context::default();
+    let mut environment = Environment::default();
+    let mut execute = |string: &str| parse_valid_statement(string).execute(&context, &mut environment);
+
+    assert_eq!(execute("let x = 2 * 3"), Ok(Value::Int(6)));
+    assert_eq!(execute("let y = x + 1"), Ok(Value::Int(7)));
+    assert_eq!(execute("x * y"), Ok(Value::Int(42)));
+    assert_eq!(execute("let x = x / 2"), Ok(Value::Int(3)));
+    assert_eq!(execute("x"), Ok(Value::Int(3)));
+    assert_eq!(
+        execute("let z = x + w"),
+        Err(EvalError::UndefinedVariable {
+            name: "w".to_string(),
+            span: span(13, 14)
+        })
+    );
+    assert_eq!(
+        execute("z"),
+        Err(EvalError::UndefinedVariable {
+            name: "z".to_string(),
+            span: span(1, 2)
+        })
+    );
+}
+
+#[cfg(test)]
+fn execute_lines(lines: &[&str], context: &Context) -> Result<Value, EvalError> {
+    let mut environment = Environment::default();
+    let mut result = Ok(Value::Int(0));
+    for line in lines {
+        result = parse_valid_statement(line).execute(context, &mut environment);
+    }
+    result
+}
+
+#[test]
+fn execute_function_calls() {
+    let context = Context::default();
+
+    assert_eq!(
+        execute_lines(&["fn add(a, b) = a + b", "add(1, 2) * add(3, 4)"], &context),
+        Ok(Value::Int(21))
+    );
+    assert_eq!(execute_lines(&["fn answer() = 42", "answer()"], &context), Ok(Value::Int(42)));
+    assert_eq!(
+        execute_lines(&["fn square(x) = x * x", "fn sum_of_squares(a, b) = square(a) + square(b)", "sum_of_squares(3, 4)"], &context),
+        Ok(Value::Int(25))
+    );
+}
+
+#[test]
+fn execute_function_captures_definition_environment() {
+    let context = Context::default();
+
+    assert_eq!(
+        execute_lines(&["let k = 10", "fn scale(x) = k * x", "let k = 1000", "scale(2)"], &context),
+        Ok(Value::Int(20))
+    );
+    assert_eq!(
+        execute_lines(&["let x = 1", "fn shadow(x) = x", "shadow(5) + x"], &context),
+        Ok(Value::Int(6))
+    );
+}
+
+#[test]
+fn execute_function_errors() {
+    let context = Context::default();
+
+    assert_eq!(
+        execute_lines(&["fn add(a, b) = a + b", "add(1)"], &context),
+        Err(EvalError::ArityMismatch {
+            expected: 2,
+            got: 1,
+            span: span(4, 7)
+        })
+    );
+    assert_eq!(
+        execute_lines(&["let x = 3", "x(1)"], &context),
+        Err(EvalError::NotAFunction { span: span(2, 5) })
+    );
+    assert_eq!(
+        execute_lines(&["fn f() = 1", "f + 1"], &context),
+        Err(EvalError::InvalidOperand { expected: OperandKind::Number, span: span(3, 4) })
+    );
+}
+
+#[test]
+fn execute_recursion_limit() {
+    let context = Context {
+        max_call_depth: 50,
+        ..Context::default()
+    };
+
+    assert_eq!(
+        execute_lines(&["fn forever(n) = forever(n + 1)", "forever(0)"], &context),
+        Err(EvalError::RecursionLimit {
+            limit: 50,
+            span: span(24, 31)
+        })
+    );
+    assert_eq!(context.call_depth.get(), 0);
+
+    // the limit counts nesting, not the total number of calls
+    assert_eq!(
+        execute_lines(&["fn inc(n) = n + 1", "fn twice(n) = inc(inc(n))", "twice(twice(twice(0)))"], &context),
+        Ok(Value::Int(6))
+    );
+}
+
+#[test]
+fn execute_within_the_stack_size() {
+    // the smallest stack Rust gives a thread, twice the default stack size
+    let evaluation = std::thread::Builder::new().stack_size(2 * 1024 * 1024).spawn(|| {
+        let context = Context {
+            max_call_depth: usize::MAX,
+            ..Context::default()
+        };
+        let result = execute_lines(&["fn f(n) = if n == 0 then 0 else 1 + f(n - 1)", "f(1000000)"], &context);
+        (result.map(|_| ()), context.call_depth.get())
+    });
+
+    let (result, depth) = evaluation.unwrap().join().unwrap();
+    assert!(matches!(result, Err(EvalError::StackOverflow { depth, .. }) if depth > 10), "{:?}", result);
+    assert_eq!(depth, 0);
+}
+
+#[test]
+fn execute_lambdas() {
+    let context = Context::default();
+
+    assert_eq!(execute_lines(&["(fun x -> x + 1) 41"], &context), Ok(Value::Int(42)));
+    assert_eq!(execute_lines(&["(fun x -> x * 2)(21)"], &context), Ok(Value::Int(42)));
+    assert_eq!(
+        execute_lines(&["fn twice(f, x) = f(f(x))", "twice(fun x -> x * 3, 2)"], &context),
+        Ok(Value::Int(18))
+    );
+    assert_eq!(
+        execute_lines(&["let compose = fun f -> fun g -> fun x -> f (g x)", "compose (fun x -> x + 1) (fun x -> x * 10) 4"], &context),
+        Ok(Value::Int(41))
+    );
+}
+
+#[test]
+fn execute_closures() {
+    let context = Context::default();
+
+    assert_eq!(
+        execute_lines(&["let make_adder = fun n -> fun x -> x + n", "let add5 = make_adder 5", "add5 10"], &context),
+        Ok(Value::Int(15))
+    );
+    assert_eq!(
+        execute_lines(&["fn adder(n) = fun x -> x + n", "adder(2)(3) + adder 10 1"], &context),
+        Ok(Value::Int(16))
+    );
+    // closures see the bindings from where they were created, not from where they are called
+    assert_eq!(
+        execute_lines(&["let n = 1", "let f = fun x -> x + n", "let n = 100", "f 1"], &context),
+        Ok(Value::Int(2))
+    );
+    assert_eq!(
+        execute_lines(&["let f = fun x -> fun y -> x - y", "let x = 100", "f 10 3"], &context),
+        Ok(Value::Int(7))
+    );
+    assert_eq!(
+        execute_lines(&["let apply = fun f -> f 1", "apply (fun y -> y + x)"], &context),
+        Err(EvalError::UndefinedVariable {
+            name: "x".to_string(),
+            span: span(21, 22)
+        })
+    );
+}
+
+#[test]
+fn evaluate_comparisons() {
+    assert_eq!(evaluate_str("1 + 1 == 2"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate_str("3 != 3"), Ok(Value::Bool(false)));
+    assert_eq!(evaluate_str("2 < 2.5"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate_str("2 <= 2"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate_str("-1 > 0"), Ok(Value::Bool(false)));
+    assert_eq!(evaluate_str("true == !false"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate_str_in_mode("1 / 3 < 0.34", NumericMode::Rational), Ok(Value::Bool(true)));
+    assert_eq!(evaluate_str_in_mode("2 / 6 == 1 / 3", NumericMode::Rational), Ok(Value::Bool(true)));
+    assert_eq!(
+        evaluate_str_in_mode("10000000000 > 9999999999", NumericMode::BigInt),
+        Ok(Value::Bool(true))
+    );
+    assert_eq!(
+        evaluate_str("true < false"),
+        Err(EvalError::InvalidOperand { expected: OperandKind::Number, span: span(6, 7) })
+    );
+    assert_eq!(
+        evaluate_str("1 == true"),
+        Err(EvalError::InvalidOperand { expected: OperandKind::Number, span: span(3, 5) })
+    );
+}
+
+#[test]
+fn evaluate_logical_operators() {
+    assert_eq!(evaluate_str("true && !false"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate_str("false || 1 > 2"), Ok(Value::Bool(false)));
+    assert_eq!(evaluate_str("!(1 < 2) || 2 < 3 && 3 < 4"), Ok(Value::Bool(true)));
+    assert_eq!(
+        evaluate_str("1 && true"),
+        Err(EvalError::InvalidOperand { expected: OperandKind::Boolean, span: span(3, 5) })
+    );
+    assert_eq!(evaluate_str("!1"), Err(EvalError::InvalidOperand { expected: OperandKind::Boolean, span: span(1, 2) }));
+    assert_eq!(
+        evaluate_str("-true"),
+        Err(EvalError::InvalidOperand { expected: OperandKind::Number, span: span(1, 2) })
+    );
+    assert_eq!(
+        evaluate_str("true + 1"),
+        Err(EvalError::InvalidOperand { expected: OperandKind::Number, span: span(6, 7) })
+    );
+}
+
+#[test]
+fn evaluate_logical_operators_short_circuit() {
+    // the right side would fail if it was evaluated
+    assert_eq!(evaluate_str("false && 1 / 0 == 1"), Ok(Value::Bool(false)));
+    assert_eq!(evaluate_str("true || undefined"), Ok(Value::Bool(true)));
+    assert_eq!(
+        evaluate_str("true && 1 / 0 == 1"),
+        Err(EvalError::DivisionByZero { span: span(11, 12) })
+    );
+}
+
+#[test]
+fn evaluate_conditionals() {
+    let context = Context::default();
+
+    assert_eq!(evaluate_str("if 1 < 2 then 10 else 20"), Ok(Value::Int(10)));
+    assert_eq!(evaluate_str("2 * if false then 1 else 3 + 4"), Ok(Value::Int(14)));
+    assert_eq!(
+        execute_lines(&["let x = -5", "if x > 0 then x else -x"], &context),
+        Ok(Value::Int(5))
+    );
+    assert_eq!(
+        execute_lines(&["fn fact(n) = if n <= 1 then 1 else n * fact(n - 1)", "fact(10)"], &context),
+        Ok(Value::Int(3628800))
+    );
+    // only the selected branch is evaluated
+    assert_eq!(evaluate_str("if true then 1 else 1 / 0"), Ok(Value::Int(1)));
+    assert_eq!(
+        evaluate_str("if 1 then 2 else 3"),
+        Err(EvalError::InvalidOperand { expected: OperandKind::Boolean, span: span(1, 3) })
+    );
+}
+
+#[test]
+fn evaluate_modulo_and_integer_division() {
+    // `//` rounds down and `%` takes the sign of the divisor
+    assert_eq!(evaluate_str("7 // 2"), Ok(Value::Int(3)));
+    assert_eq!(evaluate_str("-7 // 2"), Ok(Value::Int(-4)));
+    assert_eq!(evaluate_str("-7 / 2"), Ok(Value::Int(-3)));
+    assert_eq!(evaluate_str("-7 % 2"), Ok(Value::Int(1)));
+    assert_eq!(evaluate_str("7 % -2"), Ok(Value::Int(-1)));
+    assert_eq!(evaluate_str("6 % 3"), Ok(Value::Int(0)));
+    assert_eq!(evaluate_str("7.5 // 2"), Ok(Value::Float(3.0)));
+    assert_eq!(evaluate_str("-7.5 % 2"), Ok(Value::Float(0.5)));
+    assert_eq!(evaluate_str_in_mode("-7 % 2", NumericMode::BigInt), Ok(Value::BigInt(BigInt::from(1))));
+    assert_eq!(
+        evaluate_str_in_mode("-7 // 2", NumericMode::BigInt),
+        Ok(Value::BigInt(BigInt::from(-4)))
+    );
+    assert_eq!(
+        evaluate_str_in_mode("7 / 2 // 1", NumericMode::Rational),
+        Ok(Value::Rational(Rational::from(3)))
+    );
+    assert_eq!(
+        evaluate_str_in_mode("-7 / 2 % 2", NumericMode::Rational),
+        Ok(Value::Rational(Rational::new(BigInt::from(1), BigInt::from(2)).unwrap()))
+    );
+    assert_eq!(
+        evaluate_str("(-2147483647 - 1) // -1"),
+        Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(19, 21) })
+    );
+    assert_eq!(evaluate_str("1 % 0"), Err(EvalError::DivisionByZero { span: span(3, 4) }));
+    assert_eq!(evaluate_str("1.5 // 0"), Err(EvalError::DivisionByZero { span: span(5, 7) }));
+}
+
+#[test]
+fn evaluate_power() {
+    assert_eq!(evaluate_str("2 ** 10"), Ok(Value::Int(1024)));
+    assert_eq!(evaluate_str("(-3) ** 3"), Ok(Value::Int(-27)));
+    assert_eq!(evaluate_str("0 ** 0"), Ok(Value::Int(1)));
+    assert_eq!(evaluate_str("4 ** 0.5"), Ok(Value::Float(2.0)));
+    assert_eq!(evaluate_str("2 ** 31"), Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(3, 5) }));
+    assert_eq!(evaluate_str("(-2) ** 31"), Ok(Value::Int(i32::MIN)));
+    assert_eq!(evaluate_str("(-3) ** 41"), Err(EvalError::Underflow { kind: NumberKind::Integer, span: span(6, 8) }));
+    assert_eq!(evaluate_str("2 ** 100"), Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(3, 5) }));
+    assert_eq!(evaluate_str("10.0 ** 400"), Err(EvalError::Overflow { kind: NumberKind::Float, span: span(6, 8) }));
+    assert_eq!(evaluate_str("2 ** -1"), Err(EvalError::InvalidExponent { span: span(3, 5) }));
+    assert_eq!(
+        evaluate_str_in_mode("2 ** 100", NumericMode::BigInt),
+        Ok(Value::BigInt("1267650600228229401496703205376".parse().unwrap()))
+    );
+    assert_eq!(
+        evaluate_str_in_mode("(2 / 3) ** -2", NumericMode::Rational),
+        Ok(Value::Rational(Rational::new(BigInt::from(9), BigInt::from(4)).unwrap()))
+    );
+    assert_eq!(
+        evaluate_str_in_mode("4 ** (1 / 2)", NumericMode::Rational),
+        Err(EvalError::InvalidExponent { span: span(3, 5) })
+    );
+    assert_eq!(
+        evaluate_str_in_mode("0 ** -1", NumericMode::Rational),
+        Err(EvalError::DivisionByZero { span: span(3, 5) })
+    );
+
+    // results too large to compute in reasonable time
+    assert_eq!(
+        evaluate_str_in_mode("2 ** 4000000000", NumericMode::BigInt),
+        Err(EvalError::Overflow { kind: NumberKind::Integer, span: span(3, 5) })
+    );
+    assert_eq!(
+        evaluate_str_in_mode("(1 / 3) ** -2000000000", NumericMode::Rational),
+        Err(EvalError::Overflow { kind: NumberKind::Fraction, span: span(9, 11) })
+    );
+    assert!(matches!(evaluate_str_in_mode("10 ** 9999", NumericMode::BigInt), Ok(Value::BigInt(_))));
+    assert_eq!(
+        evaluate_str_in_mode("1 ** 4000000000 + (-1) ** 4000000001", NumericMode::BigInt),
+        Ok(Value::BigInt(BigInt::zero()))
+    );
+}
@@ -0,0 +1,85 @@
+use std::cell::Cell;
+
+/// How integer literals and integer arithmetic are represented.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum NumericMode {
+    /// 32-bit integers, overflow is reported as an error.
+    #[default]
+    Machine,
+    /// Integers of unlimited size.
+    BigInt,
+    /// Exact fractions, division never truncates.
+    Rational,
+}
+
+impl NumericMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "int" => Some(Self::Machine),
+            "bigint" => Some(Self::BigInt),
+            "rational" => Some(Self::Rational),
+            _ => None,
+        }
+    }
+}
+
+/// How rational results are printed.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum RationalFormat {
+    /// `7/2`
+    #[default]
+    Fraction,
+    /// `3 1/2`
+    MixedNumber,
+    /// `3.50` with the given number of digits after the point
+    Decimal(usize),
+}
+
+impl RationalFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fraction" => Some(Self::Fraction),
+            "mixed" => Some(Self::MixedNumber),
+            _ => name
+                .strip_prefix("decimal:")
+                .and_then(|digits| digits.parse().ok())
+                .map(Self::Decimal),
+        }
+    }
+}
+
+/// As deep as calls of small functions nest within the default stack size
+/// in an unoptimized build, optimized builds take about a fifth of the stack.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 100;
+
+/// Half of the smallest stack Rust gives a thread, the other half is left
+/// to the code around the evaluation.
+pub const DEFAULT_STACK_SIZE: usize = 1024 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct Context {
+    pub numeric_mode: NumericMode,
+    pub rational_format: RationalFormat,
+    /// Number of nested function calls after which evaluation fails.
+    pub max_call_depth: usize,
+    /// Bytes of stack nested calls can take, evaluation fails once they
+    /// would take more, before the call depth limit is reached if need be.
+    /// Has to be less than the stack of the thread evaluating.
+    pub stack_size: usize,
+    pub(crate) call_depth: Cell<usize>,
+    /// Stack address of the outermost call.
+    pub(crate) stack_start: Cell<usize>,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            numeric_mode: NumericMode::default(),
+            rational_format: RationalFormat::default(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            stack_size: DEFAULT_STACK_SIZE,
+            call_depth: Cell::new(0),
+            stack_start: Cell::new(0),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::{collections::HashSet, rc::Rc};
+
+use super::value::{Function, Value};
+
+struct Binding {
+    name: String,
+    value: Value,
+    parent: Option<Rc<Binding>>,
+}
+
+/// Name to value bindings. Bindings are never mutated: extending an
+/// environment returns a new one that shadows earlier bindings of the same
+/// name, while the original stays untouched and cheap to keep around.
+#[derive(Clone, Default)]
+pub struct Environment {
+    bindings: Option<Rc<Binding>>,
+}
+
+impl Environment {
+    pub fn extend(&self, name: String, value: Value) -> Self {
+        Self {
+            bindings: Some(Rc::new(Binding {
+                name,
+                value,
+                parent: self.bindings.clone(),
+            })),
+        }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Value> {
+        let mut binding = self.bindings.as_deref();
+        while let Some(current) = binding {
+            if current.name == name {
+                return Some(&current.value);
+            }
+            binding = current.parent.as_deref();
+        }
+        None
+    }
+
+    /// Every function reachable from the bindings, shadowed ones and the
+    /// environments functions captured included.
+    pub fn functions(&self) -> Vec<&Function> {
+        let mut functions = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![self.bindings.as_deref()];
+        while let Some(mut binding) = pending.pop() {
+            // environments share their parents, a visited binding had its parents visited too
+            while let Some(current) = binding.filter(|current| visited.insert(*current as *const Binding)) {
+                if let Value::Function(function) = &current.value {
+                    functions.push(function.as_ref());
+                    pending.push(function.environment.bindings.as_deref());
+                }
+                binding = current.parent.as_deref();
+            }
+        }
+        functions
+    }
+}
+
+#[test]
+fn extend_and_lookup() {
+    let empty = Environment::default();
+    let first = empty.extend("x".to_string(), Value::Int(1));
+    let second = first.extend("y".to_string(), Value::Int(2));
+    let shadowed = second.extend("x".to_string(), Value::Int(3));
+
+    assert_eq!(empty.lookup("x"), None);
+    assert_eq!(first.lookup("x"), Some(&Value::Int(1)));
+    assert_eq!(second.lookup("x"), Some(&Value::Int(1)));
+    assert_eq!(second.lookup("y"), Some(&Value::Int(2)));
+    assert_eq!(shadowed.lookup("x"), Some(&Value::Int(3)));
+    assert_eq!(shadowed.lookup("y"), Some(&Value::Int(2)));
+    assert_eq!(shadowed.lookup("z"), None);
+}
@@ -0,0 +1,103 @@
+use crate::lexer::token::Span;
+
+/// Kind of number whose range an operation left.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NumberKind {
+    Integer,
+    Fraction,
+    Float,
+}
+
+impl NumberKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Integer => "Integer",
+            Self::Fraction => "Fraction",
+            Self::Float => "Float",
+        }
+    }
+}
+
+/// Kind of value an operator or condition needs.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OperandKind {
+    Number,
+    Boolean,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum EvalError {
+    DivisionByZero { span: Span },
+    Overflow { kind: NumberKind, span: Span },
+    Underflow { kind: NumberKind, span: Span },
+    UndefinedVariable { name: String, span: Span },
+    InvalidOperand { expected: OperandKind, span: Span },
+    InvalidExponent { span: Span },
+    NotAFunction { span: Span },
+    ArityMismatch { expected: usize, got: usize, span: Span },
+    RecursionLimit { limit: usize, span: Span },
+    InvalidSyntax { span: Span },
+    StackOverflow { depth: usize, span: Span },
+}
+
+impl EvalError {
+    /// Span of the operator whose application failed, or of the offending name.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::DivisionByZero { span }
+            | Self::Overflow { span, .. }
+            | Self::Underflow { span, .. }
+            | Self::UndefinedVariable { span, .. }
+            | Self::InvalidOperand { span, .. }
+            | Self::InvalidExponent { span }
+            | Self::NotAFunction { span }
+            | Self::ArityMismatch { span, .. }
+            | Self::RecursionLimit { span, .. }
+            | Self::InvalidSyntax { span }
+            | Self::StackOverflow { span, .. } => *span,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DivisionByZero { .. } => "E0201",
+            Self::Overflow { .. } => "E0202",
+            Self::Underflow { .. } => "E0203",
+            Self::UndefinedVariable { .. } => "E0204",
+            Self::InvalidOperand { .. } => "E0205",
+            Self::InvalidExponent { .. } => "E0206",
+            Self::NotAFunction { .. } => "E0207",
+            Self::ArityMismatch { .. } => "E0208",
+            Self::RecursionLimit { .. } => "E0209",
+            Self::InvalidSyntax { .. } => "E0210",
+            Self::StackOverflow { .. } => "E0211",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::DivisionByZero { .. } => "Division by zero".to_string(),
+            Self::Overflow { kind, .. } => format!("{} overflow", kind.name()),
+            Self::Underflow { kind, .. } => format!("{} underflow", kind.name()),
+            Self::UndefinedVariable { name, .. } => format!("Undefined variable {}", name),
+            Self::InvalidOperand {
+                expected: OperandKind::Number,
+                ..
+            } => "Operator applied to a value that is not a number".to_string(),
+            Self::InvalidOperand {
+                expected: OperandKind::Boolean,
+                ..
+            } => "Condition or logical operand is not a boolean".to_string(),
+            Self::InvalidExponent { .. } => {
+                "Integers can only be raised to non-negative integer powers, fractions to integer powers".to_string()
+            },
+            Self::NotAFunction { .. } => "Called a value that is not a function".to_string(),
+            Self::ArityMismatch { expected, got, .. } => {
+                format!("Function expects {} argument(s), got {}", expected, got)
+            }
+            Self::RecursionLimit { limit, .. } => format!("Exceeded the limit of {} nested calls", limit),
+            Self::InvalidSyntax { .. } => "Cannot evaluate code that failed to parse".to_string(),
+            Self::StackOverflow { depth, .. } => format!("Ran out of stack after {} nested calls", depth),
+        }
+    }
+}
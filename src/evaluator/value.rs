@@ -0,0 +1,128 @@
+use std::{cell::OnceCell, fmt, rc::Rc};
+
+use crate::{
+    numeric::{bigint::BigInt, rational::Rational},
+    parser::syntax_tree::ExpressionNode,
+    vm::chunk::Chunk,
+};
+
+use super::{context::RationalFormat, environment::Environment};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Int(i32),
+    BigInt(BigInt),
+    Rational(Rational),
+    Float(f64),
+    Bool(bool),
+    Function(Rc<Function>),
+}
+
+/// A function together with the environment it was defined in.
+pub struct Function {
+    /// Named functions can call themselves recursively under that name.
+    pub name: Option<String>,
+    pub parameters: Vec<String>,
+    pub body: Rc<ExpressionNode>,
+    pub environment: Environment,
+    /// The body compiled by the bytecode VM, once it is first called.
+    pub(crate) chunk: OnceCell<Rc<Chunk>>,
+}
+
+impl PartialEq for Function {
+    /// Functions are only equal to themselves.
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<fn {}({})>",
+            self.name.as_deref().unwrap_or_default(),
+            self.parameters.join(", ")
+        )
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Value {
+    /// Widens a numeric value to a float, used whenever an int meets a float.
+    /// `None` for values that are not numbers.
+    pub fn to_float(&self) -> Option<f64> {
+        match self {
+            Self::Int(value) => Some(f64::from(*value)),
+            Self::BigInt(value) => Some(value.to_f64()),
+            Self::Rational(value) => Some(value.to_f64()),
+            Self::Float(value) => Some(*value),
+            Self::Bool(_) | Self::Function(_) => None,
+        }
+    }
+
+    /// Exact integer value, `None` for fractions, floats and non-numbers.
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Self::Int(value) => Some(BigInt::from(*value)),
+            Self::BigInt(value) => Some(value.clone()),
+            Self::Rational(_) | Self::Float(_) | Self::Bool(_) | Self::Function(_) => None,
+        }
+    }
+
+    /// Exact rational value, `None` for floats and non-numbers.
+    pub fn to_rational(&self) -> Option<Rational> {
+        match self {
+            Self::Rational(value) => Some(value.clone()),
+            Self::Float(_) | Self::Bool(_) | Self::Function(_) => None,
+            other => other.to_bigint().map(Rational::from),
+        }
+    }
+
+    /// Formats the value like `Display` does, with rationals printed in the
+    /// requested format.
+    pub fn format(&self, rational_format: RationalFormat) -> String {
+        match (self, rational_format) {
+            (Self::Rational(value), RationalFormat::MixedNumber) => value.to_mixed_string(),
+            (Self::Rational(value), RationalFormat::Decimal(digits)) => value.to_decimal_string(digits),
+            (value, _) => value.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(value) => write!(f, "{}", value),
+            Self::BigInt(value) => write!(f, "{}", value),
+            Self::Rational(value) => write!(f, "{}", value),
+            // debug formatting keeps the trailing `.0`, so floats never look like ints
+            Self::Float(value) => write!(f, "{:?}", value),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Function(function) => write!(f, "{}", function),
+        }
+    }
+}
+
+#[test]
+fn display() {
+    assert_eq!(Value::Int(3).to_string(), "3");
+    assert_eq!(Value::Float(3.0).to_string(), "3.0");
+    assert_eq!(Value::Float(0.5).to_string(), "0.5");
+    assert_eq!(Value::Bool(false).to_string(), "false");
+}
+
+#[test]
+fn format_rational() {
+    let value = Value::Rational(Rational::new(BigInt::from(7), BigInt::from(2)).unwrap());
+    assert_eq!(value.to_string(), "7/2");
+    assert_eq!(value.format(RationalFormat::Fraction), "7/2");
+    assert_eq!(value.format(RationalFormat::MixedNumber), "3 1/2");
+    assert_eq!(value.format(RationalFormat::Decimal(2)), "3.50");
+    assert_eq!(Value::Int(7).format(RationalFormat::Decimal(2)), "7");
+}
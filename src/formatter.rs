@@ -0,0 +1,398 @@
+//! Source formatter: rewrites a program in the canonical style, with
+//! single spaces around binary operators, one statement per line and lines
+//! longer than the width broken before the loosest binding operators or
+//! inside parentheses. Comments and the user's parentheses are kept.
+
+use std::{mem, slice};
+
+use crate::{
+    lexer::token::TokenKind,
+    parser::{binary_precedence, syntax_tree::InvalidExpressionNode},
+};
+
+use self::cst::{ConcreteSyntaxTree, CstNode, CstToken, Trivia};
+
+pub mod cst;
+
+/// Width lines are kept within, as far as breaking them allows.
+pub const DEFAULT_WIDTH: usize = 80;
+
+const INDENT: &str = "    ";
+
+/// Formats a source, failing with its syntax errors if it does not parse.
+/// Formatting a formatted source gives it back unchanged.
+pub fn format(source: &str, width: usize) -> Result<String, Vec<InvalidExpressionNode>> {
+    let mut tree = ConcreteSyntaxTree::parse(source)?;
+    let mut printer = Printer::new(width);
+    for index in 0..tree.statements.len() {
+        // a `-` starting the next line would read as continuing this statement
+        let keep_separator = tree.statements.get(index + 1).is_some_and(|next| {
+            next.nodes.first().is_some_and(|node| *node.first().kind() == TokenKind::SubOperator)
+        });
+        let statement = &mut tree.statements[index];
+        if let Some(first) = statement.nodes.first_mut() {
+            let leading = mem::take(&mut first_mut(first).leading);
+            printer.leading(&leading, 0, true);
+            printer.previous = None;
+            printer.layout(&statement.nodes, 1, keep_separator as usize);
+        }
+        for (position, separator) in statement.separators.iter().enumerate() {
+            match position == 0 && keep_separator && !statement.nodes.is_empty() {
+                true => printer.token(separator, 1),
+                false => printer.comments(separator),
+            }
+        }
+        printer.newline(0);
+    }
+    printer.leading(&tree.end, 0, true);
+    Ok(printer.finish())
+}
+
+fn first_mut(node: &mut CstNode) -> &mut CstToken {
+    match node {
+        CstNode::Token(token) => token,
+        CstNode::Group { open, .. } => open,
+    }
+}
+
+/// Whether a token of this kind can end an operand, so that a `-` after
+/// it is binary and a `(` after it starts call arguments.
+fn ends_operand(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Identifier
+            | TokenKind::IntLiteral(_)
+            | TokenKind::FloatLiteral(_)
+            | TokenKind::BoolLiteral(_)
+            | TokenKind::ParenthesisClose
+    )
+}
+
+/// Positions of the loosest binding operators of a sequence, where it is
+/// broken when it does not fit. `then` and `else` bind loosest of all,
+/// operators inside parentheses are not part of the sequence.
+fn break_points(nodes: &[CstNode], mut previous: Option<TokenKind>) -> Vec<usize> {
+    let mut points = Vec::new();
+    let mut loosest = u8::MAX;
+    for (index, node) in nodes.iter().enumerate() {
+        let rank = match node {
+            CstNode::Token(token) => match token.kind() {
+                TokenKind::ThenKeyword | TokenKind::ElseKeyword => Some(0),
+                TokenKind::SubOperator if !previous.as_ref().is_some_and(ends_operand) => None,
+                kind => binary_precedence(kind),
+            },
+            CstNode::Group { .. } => None,
+        };
+        previous = Some(match node {
+            CstNode::Token(token) => token.kind().clone(),
+            CstNode::Group { .. } => TokenKind::ParenthesisClose,
+        });
+        match rank {
+            Some(rank) if index > 0 && rank < loosest => {
+                loosest = rank;
+                points = vec![index];
+            }
+            Some(rank) if index > 0 && rank == loosest => points.push(index),
+            _ => (),
+        }
+    }
+    points
+}
+
+/// Writes lines of tokens, deciding the spacing from the previous token.
+struct Printer {
+    width: usize,
+    lines: Vec<String>,
+    /// The current line without its indentation.
+    line: String,
+    indent: usize,
+    previous: Option<TokenKind>,
+    /// Whether the previous token is a unary operator, which is written
+    /// right before its operand.
+    after_unary: bool,
+}
+
+/// State of a [`Printer`] to go back to when a layout does not fit.
+struct Mark {
+    lines: usize,
+    line: String,
+    indent: usize,
+    previous: Option<TokenKind>,
+    after_unary: bool,
+}
+
+impl Printer {
+    fn new(width: usize) -> Self {
+        Printer {
+            width,
+            lines: Vec::new(),
+            line: String::new(),
+            indent: 0,
+            previous: None,
+            after_unary: false,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.newline(0);
+        while self.lines.last().is_some_and(|line| line.is_empty()) {
+            self.lines.pop();
+        }
+        self.lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    fn mark(&self) -> Mark {
+        Mark {
+            lines: self.lines.len(),
+            line: self.line.clone(),
+            indent: self.indent,
+            previous: self.previous.clone(),
+            after_unary: self.after_unary,
+        }
+    }
+
+    fn reset(&mut self, mark: Mark) {
+        self.lines.truncate(mark.lines);
+        self.line = mark.line;
+        self.indent = mark.indent;
+        self.previous = mark.previous;
+        self.after_unary = mark.after_unary;
+    }
+
+    /// Whether the lines written since the mark are within the width, with
+    /// `reserve` characters left on the current one for what follows.
+    fn fits(&self, mark: &Mark, reserve: usize) -> bool {
+        let column = self.indent * INDENT.len() + self.line.chars().count();
+        self.lines[mark.lines..].iter().all(|line| line.chars().count() <= self.width) && column + reserve <= self.width
+    }
+
+    /// Ends the current line unless it is empty, the next one is indented
+    /// by `indent` levels.
+    fn newline(&mut self, indent: usize) {
+        if !self.line.is_empty() {
+            self.lines.push(format!("{}{}", INDENT.repeat(self.indent), self.line.trim_end()));
+            self.line.clear();
+        }
+        self.indent = indent;
+    }
+
+    fn blank_line(&mut self) {
+        self.newline(self.indent);
+        if self.lines.last().is_some_and(|line| !line.is_empty()) {
+            self.lines.push(String::new());
+        }
+    }
+
+    /// Writes the comments of leading trivia on lines of their own, keeping
+    /// a single blank line where there were any if `blank_lines` is set.
+    fn leading(&mut self, trivia: &[Trivia], indent: usize, blank_lines: bool) {
+        let mut newlines = 0;
+        for trivia in trivia {
+            match trivia {
+                Trivia::Newline => newlines += 1,
+                Trivia::Comment(comment) => {
+                    if blank_lines && newlines > 1 {
+                        self.blank_line();
+                    }
+                    self.newline(indent);
+                    self.line.push_str(comment);
+                    self.newline(indent);
+                    newlines = 0;
+                }
+                Trivia::Whitespace(_) => (),
+            }
+        }
+        if blank_lines && newlines > 1 {
+            self.blank_line();
+        }
+    }
+
+    /// Writes only the comments of a token that is left out.
+    fn comments(&mut self, token: &CstToken) {
+        self.leading(&token.leading, 0, false);
+        if let Some(comment) = token.trailing_comment() {
+            if !self.line.is_empty() {
+                self.line.push(' ');
+            }
+            self.line.push_str(comment);
+            self.newline(0);
+        }
+    }
+
+    /// Writes a token with its comments, lines its comments force are
+    /// continued with `indent` levels.
+    fn token(&mut self, token: &CstToken, indent: usize) {
+        self.leading(&token.leading, indent, false);
+        let kind = token.kind();
+        let spaced = match (&self.previous, kind) {
+            (_, TokenKind::ParenthesisClose | TokenKind::Comma | TokenKind::Semicolon) => false,
+            (Some(TokenKind::ParenthesisOpen), _) => false,
+            (Some(previous), TokenKind::ParenthesisOpen) if ends_operand(previous) => false,
+            _ => !self.after_unary,
+        };
+        if spaced && !self.line.is_empty() {
+            self.line.push(' ');
+        }
+        self.line.push_str(&token.token.lexem);
+        self.after_unary = *kind == TokenKind::NotOperator
+            || (*kind == TokenKind::SubOperator && !self.previous.as_ref().is_some_and(ends_operand));
+        self.previous = Some(kind.clone());
+        if let Some(comment) = token.trailing_comment() {
+            self.line.push(' ');
+            self.line.push_str(comment);
+            self.newline(indent);
+        }
+    }
+
+    fn flat(&mut self, nodes: &[CstNode], indent: usize) {
+        for node in nodes {
+            match node {
+                CstNode::Token(token) => self.token(token, indent),
+                CstNode::Group { open, children, close } => {
+                    self.token(open, indent);
+                    self.flat(children, indent);
+                    self.token(close, indent);
+                }
+            }
+        }
+    }
+
+    /// Writes a sequence on the current line if it fits. Otherwise it is
+    /// broken before its loosest binding operators, with the following
+    /// parts on lines indented by `indent` levels, or if it has none, its
+    /// parentheses that do not fit get their contents on lines of their
+    /// own, one more level in and an argument per line.
+    fn layout(&mut self, nodes: &[CstNode], indent: usize, reserve: usize) {
+        let mark = self.mark();
+        self.flat(nodes, indent);
+        if self.fits(&mark, reserve) {
+            return;
+        }
+        self.reset(mark);
+
+        let points = break_points(nodes, self.previous.clone());
+        if !points.is_empty() {
+            let mut start = 0;
+            for end in points.into_iter().chain([nodes.len()]) {
+                if start > 0 {
+                    self.newline(indent);
+                }
+                let reserve = if end == nodes.len() { reserve } else { 0 };
+                self.layout(&nodes[start..end], indent + 1, reserve);
+                start = end;
+            }
+            return;
+        }
+
+        for (index, node) in nodes.iter().enumerate() {
+            let reserve = if index + 1 == nodes.len() { reserve } else { 0 };
+            let CstNode::Group { open, children, close } = node else {
+                self.token(node.first(), indent);
+                continue;
+            };
+            let mark = self.mark();
+            self.flat(slice::from_ref(node), indent);
+            if children.is_empty() || self.fits(&mark, reserve) {
+                continue;
+            }
+            self.reset(mark);
+
+            let outer = self.indent;
+            self.token(open, outer + 1);
+            for argument in children.split_inclusive(|child| *child.first().kind() == TokenKind::Comma) {
+                self.newline(outer + 1);
+                match argument.split_last() {
+                    Some((CstNode::Token(comma), argument)) if *comma.kind() == TokenKind::Comma => {
+                        self.layout(argument, outer + 2, 1);
+                        self.token(comma, outer + 2);
+                    }
+                    _ => self.layout(argument, outer + 2, 0),
+                }
+            }
+            self.newline(outer);
+            self.token(close, outer + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+fn assert_formats(source: &str, width: usize, expected: &str) {
+    let formatted = format(source, width).unwrap();
+    assert_eq!(formatted, expected, "{}", source);
+    assert_eq!(format(&formatted, width).unwrap(), formatted, "{}", source);
+}
+
+#[test]
+fn normalise_spacing() {
+    assert_formats("let  x=1+2*-3", 80, "let x = 1 + 2 * -3\n");
+    assert_formats("fn f( a ,b )=a**b//2 %(-a)", 80, "fn f(a, b) = a ** b // 2 % (-a)\n");
+    assert_formats("f (x)(! y)-1;x - - 1", 80, "f(x)(!y) - 1\nx - -1\n");
+    assert_formats("if a<=b&&c then fun x->x else g", 80, "if a <= b && c then fun x -> x else g\n");
+}
+
+#[test]
+fn separate_statements() {
+    assert_formats(
+        "; let a = 1;;b\n\n\n\nc; -1\n\n",
+        80,
+        "let a = 1\nb\n\nc;\n-1\n",
+    );
+    assert_formats("", 80, "");
+}
+
+#[test]
+fn keep_comments() {
+    assert_formats(
+        "# header\n\nlet a = 1 # one\n  # before b\nlet b = a +  # plus\n 2; # done\n# end\n",
+        80,
+        "# header\n\nlet a = 1 # one\n# before b\nlet b = a + # plus\n    2 # done\n# end\n",
+    );
+}
+
+#[test]
+fn break_long_lines() {
+    assert_formats(
+        "let total = first_value * 2 + second_value * 3 - third_value * 4",
+        30,
+        "let total = first_value * 2\n    + second_value * 3\n    - third_value * 4\n",
+    );
+    assert_formats(
+        "let r = if ready && count > 10 then compute(count) else 0",
+        30,
+        "let r = if ready && count > 10\n    then compute(count)\n    else 0\n",
+    );
+    assert_formats(
+        "result(first_argument + 1, second_argument, (third + fourth) * fifth)",
+        30,
+        "result(\n    first_argument + 1,\n    second_argument,\n    (third + fourth) * fifth\n)\n",
+    );
+    assert_formats(
+        "let x = outer(inner(alpha, beta, gamma), delta)",
+        24,
+        "let x = outer(\n    inner(\n        alpha,\n        beta,\n        gamma\n    ),\n    delta\n)\n",
+    );
+}
+
+#[test]
+fn formatting_keeps_the_meaning() {
+    use crate::parser::parse_program_str;
+
+    let sources = [
+        "let a=1;b\n-1",
+        "fn fact(n)=if n<=1 then 1 else n*fact(n-1)\nfact(10)",
+        "let twice = fun f -> fun x -> f(f(x)); twice(fun x -> x * 3)(2)",
+        "-2**-2 - -(3) // 4 ^ 5 % 6",
+        "f x + g(1, 2) * (h)(3) # comment\n# another\n",
+        "let long = some_function(argument_one, argument_two) + another_function(argument_three) * (a - b)",
+    ];
+    for width in [10, 40, 80] {
+        for source in sources {
+            let formatted = format(source, width).unwrap();
+            let (expected, _) = parse_program_str(source);
+            let (program, errors) = parse_program_str(&formatted);
+            assert!(errors.is_empty(), "{}", formatted);
+            assert_eq!(program.to_string(), expected.to_string(), "{}", formatted);
+            assert_eq!(format(&formatted, width).unwrap(), formatted);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use std::{fmt, iter::Peekable};
+
+use crate::{
+    lexer::{
+        token::{Token, TokenKind},
+        Lexer,
+    },
+    parser::{
+        syntax_tree::{InvalidExpressionNode, StatementNode},
+        Parser,
+    },
+};
+
+/// Source text between two tokens.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Trivia {
+    /// Spaces and tabs within a line.
+    Whitespace(String),
+    Newline,
+    /// A `#` comment, without the line break ending it.
+    Comment(String),
+}
+
+/// A token with the trivia around it. Trailing trivia runs up to the end
+/// of the token's line, everything else before the next token is leading
+/// trivia of that token, so a leading comment always has a line of its own.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CstToken {
+    pub leading: Vec<Trivia>,
+    pub token: Token,
+    pub trailing: Vec<Trivia>,
+}
+
+impl CstToken {
+    pub fn kind(&self) -> &TokenKind {
+        &self.token.kind
+    }
+
+    /// The comment at the end of the token's line, if any.
+    pub fn trailing_comment(&self) -> Option<&str> {
+        self.trailing.iter().find_map(|trivia| match trivia {
+            Trivia::Comment(comment) => Some(comment.as_str()),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum CstNode {
+    Token(CstToken),
+    /// Tokens between matching parentheses, of a call or a parenthesised
+    /// expression.
+    Group {
+        open: CstToken,
+        children: Vec<CstNode>,
+        close: CstToken,
+    },
+}
+
+impl CstNode {
+    pub fn first(&self) -> &CstToken {
+        match self {
+            CstNode::Token(token) => token,
+            CstNode::Group { open, .. } => open,
+        }
+    }
+}
+
+/// The tokens of a statement followed by the `;` tokens ending it. A
+/// program starting with `;` has a first statement without tokens.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CstStatement {
+    pub nodes: Vec<CstNode>,
+    pub separators: Vec<CstToken>,
+}
+
+/// Concrete syntax tree of a program: every token of the source with the
+/// whitespace and comments around it, so [`Display`](fmt::Display) gives
+/// back the exact source.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ConcreteSyntaxTree {
+    pub statements: Vec<CstStatement>,
+    /// Trivia after the last token.
+    pub end: Vec<Trivia>,
+}
+
+impl ConcreteSyntaxTree {
+    /// Builds the tree of a source, which has to parse without errors as
+    /// statements are told apart by the parser.
+    pub fn parse(source: &str) -> Result<Self, Vec<InvalidExpressionNode>> {
+        let (program, errors) = Parser::from_tokens(Lexer::from_str(source).into_tokens()).parse_program();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut tokens = Vec::<CstToken>::new();
+        let mut previous_end = 0;
+        for token in Lexer::from_str(source).into_tokens() {
+            let mut leading = trivia(&source[previous_end..token.start_position.offset]);
+            if let Some(previous) = tokens.last_mut() {
+                previous.trailing = split_line(&mut leading);
+            }
+            previous_end = token.end_position.offset;
+            tokens.push(CstToken {
+                leading,
+                token,
+                trailing: Vec::new(),
+            });
+        }
+        let mut end = trivia(&source[previous_end..]);
+        if let Some(previous) = tokens.last_mut() {
+            previous.trailing = split_line(&mut end);
+        }
+
+        let mut tokens = tokens.into_iter().peekable();
+        let mut statements = Vec::new();
+        if tokens.peek().is_some_and(|token| *token.kind() == TokenKind::Semicolon) {
+            statements.push(CstStatement {
+                nodes: Vec::new(),
+                separators: Vec::new(),
+            });
+        }
+        for statement in &program.statements {
+            let last = statement_end(statement);
+            if let Some(previous) = statements.last_mut() {
+                while let Some(separator) = tokens.next_if(|token| *token.kind() == TokenKind::Semicolon) {
+                    previous.separators.push(separator);
+                }
+            }
+            let mut statement_tokens = Vec::new();
+            while let Some(token) = tokens.next_if(|token| token.token.start_position.offset < last) {
+                statement_tokens.push(token);
+            }
+            statements.push(CstStatement {
+                nodes: nest(&mut statement_tokens.into_iter().peekable()),
+                separators: Vec::new(),
+            });
+        }
+        if let Some(previous) = statements.last_mut() {
+            previous.separators.extend(tokens);
+        }
+        Ok(ConcreteSyntaxTree { statements, end })
+    }
+}
+
+/// Byte offset where the last token of a statement ends.
+fn statement_end(statement: &StatementNode) -> usize {
+    match statement {
+        StatementNode::LetNode { value, .. } => value.span().end.offset,
+        StatementNode::FunctionNode { body, .. } => body.span().end.offset,
+        StatementNode::ExpressionStatementNode(expression) => expression.span().end.offset,
+        StatementNode::ErrorNode { span } => span.end.offset,
+    }
+}
+
+/// Groups the tokens between matching parentheses, up to a `)` closing an
+/// enclosing group.
+fn nest(tokens: &mut Peekable<impl Iterator<Item = CstToken>>) -> Vec<CstNode> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next_if(|token| *token.kind() != TokenKind::ParenthesisClose) {
+        match token.kind() {
+            TokenKind::ParenthesisOpen => {
+                let children = nest(tokens);
+                let close = tokens.next().expect("parsed parentheses are balanced");
+                nodes.push(CstNode::Group {
+                    open: token,
+                    children,
+                    close,
+                });
+            }
+            _ => nodes.push(CstNode::Token(token)),
+        }
+    }
+    nodes
+}
+
+fn trivia(text: &str) -> Vec<Trivia> {
+    let mut trivia = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let length = match c {
+            '\n' => {
+                trivia.push(Trivia::Newline);
+                1
+            }
+            '#' => {
+                let length = rest.find('\n').unwrap_or(rest.len());
+                trivia.push(Trivia::Comment(rest[..length].to_string()));
+                length
+            }
+            _ => {
+                let length = rest.find(['\n', '#']).unwrap_or(rest.len());
+                trivia.push(Trivia::Whitespace(rest[..length].to_string()));
+                length
+            }
+        };
+        rest = &rest[length..];
+    }
+    trivia
+}
+
+/// Removes and returns the trivia before the first line break.
+fn split_line(trivia: &mut Vec<Trivia>) -> Vec<Trivia> {
+    let line = trivia.iter().position(|trivia| *trivia == Trivia::Newline).unwrap_or(trivia.len());
+    trivia.drain(..line).collect()
+}
+
+impl fmt::Display for Trivia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trivia::Whitespace(text) | Trivia::Comment(text) => write!(f, "{}", text),
+            Trivia::Newline => writeln!(f),
+        }
+    }
+}
+
+impl fmt::Display for CstToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.leading.iter().try_for_each(|trivia| write!(f, "{}", trivia))?;
+        write!(f, "{}", self.token.lexem)?;
+        self.trailing.iter().try_for_each(|trivia| write!(f, "{}", trivia))
+    }
+}
+
+impl fmt::Display for CstNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CstNode::Token(token) => write!(f, "{}", token),
+            CstNode::Group { open, children, close } => {
+                write!(f, "{}", open)?;
+                children.iter().try_for_each(|child| write!(f, "{}", child))?;
+                write!(f, "{}", close)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConcreteSyntaxTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for statement in &self.statements {
+            statement.nodes.iter().try_for_each(|node| write!(f, "{}", node))?;
+            statement.separators.iter().try_for_each(|separator| write!(f, "{}", separator))?;
+        }
+        self.end.iter().try_for_each(|trivia| write!(f, "{}", trivia))
+    }
+}
+
+#[test]
+fn keep_the_source() {
+    let sources = [
+        "",
+        "  # only a comment\n\n",
+        "; let x = 1 ;;  # one\n\n# two\nfn f(a, b) =\n  a * (b +\n 1)   \nf(x)(2)",
+        "-1\n-2; -3 # done",
+        "\tif x then (y) else z",
+    ];
+    for source in sources {
+        let tree = ConcreteSyntaxTree::parse(source).unwrap();
+        assert_eq!(tree.to_string(), source);
+    }
+}
+
+#[test]
+fn attach_trivia() {
+    let tree = ConcreteSyntaxTree::parse("# a\nx  # b\n\n(y)").unwrap();
+    let [first, second] = &tree.statements[..] else {
+        panic!("{:?} does not have two statements", tree.statements);
+    };
+
+    let CstNode::Token(x) = &first.nodes[0] else {
+        panic!("{:?} is not a token", first.nodes[0]);
+    };
+    assert_eq!(x.leading, vec![Trivia::Comment("# a".to_string()), Trivia::Newline]);
+    assert_eq!(
+        x.trailing,
+        vec![Trivia::Whitespace("  ".to_string()), Trivia::Comment("# b".to_string())]
+    );
+
+    let CstNode::Group { open, children, .. } = &second.nodes[0] else {
+        panic!("{:?} is not a group", second.nodes[0]);
+    };
+    assert_eq!(open.leading, vec![Trivia::Newline, Trivia::Newline]);
+    assert_eq!(children.len(), 1);
+}
@@ -1,19 +1,57 @@
-use self::{source::*, token::*};
-use crate::lexer::token::TokenKind;
+use self::{source::*, source_map::SourceFile, token::*};
+use std::io::Read;
+use crate::{lexer::token::TokenKind, numeric::bigint::BigInt};
 
 pub mod source;
+pub mod source_map;
 pub mod token;
 
+/// Characters that end a literal or an unrecognised lexem without being part of it.
+fn is_delimiter(c: &char) -> bool {
+    c.is_whitespace() || matches!(c, '+' | '-' | '*' | '/' | '(' | ')' | ',' | ';' | '=' | '<' | '>' | '!' | '&' | '|' | '%' | '^' | '#')
+}
+
+/// Words that cannot be used as names, in the order of `keyword_kind`.
+pub const KEYWORDS: [&str; 8] = ["let", "fn", "fun", "if", "then", "else", "true", "false"];
+
+fn keyword_kind(lexem: &str) -> Option<TokenKind> {
+    match lexem {
+        "let" => Some(TokenKind::LetKeyword),
+        "fn" => Some(TokenKind::FnKeyword),
+        "fun" => Some(TokenKind::FunKeyword),
+        "if" => Some(TokenKind::IfKeyword),
+        "then" => Some(TokenKind::ThenKeyword),
+        "else" => Some(TokenKind::ElseKeyword),
+        "true" => Some(TokenKind::BoolLiteral(true)),
+        "false" => Some(TokenKind::BoolLiteral(false)),
+        _ => None,
+    }
+}
+
 pub struct Lexer<TSource: CharactersSource> {
     current_position: Position,
     characters: TSource,
 }
 
 impl Lexer<StringSource> {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(string: &str) -> Self {
         let source = StringSource::from_str(string);
         Self::from_character_source(source)
     }
+
+    /// Lexer over a file of a source map, with offsets counted in the map.
+    pub fn from_source_file(file: &SourceFile) -> Self {
+        let mut lexer = Self::from_str(&file.text);
+        lexer.current_position.offset = file.start;
+        lexer
+    }
+}
+
+impl<R: Read> Lexer<ReaderSource<R>> {
+    pub fn from_reader(reader: R) -> Self {
+        Self::from_character_source(ReaderSource::new(reader))
+    }
 }
 
 impl<TSource: CharactersSource> Lexer<TSource> {
@@ -30,52 +68,85 @@ impl<TSource: CharactersSource> Lexer<TSource> {
             }
             _ => (),
         }
+        if let Some(c) = c {
+            self.current_position.offset += c.len_utf8();
+        }
 
-        return c;
+        c
     }
 
     fn from_character_source(source: TSource) -> Self {
+        Self::from_characters_at(source, 0)
+    }
+
+    /// Lexer over characters placed at the offset of a source map.
+    pub fn from_characters_at(characters: TSource, offset: usize) -> Self {
         Self {
-            characters: source,
-            current_position: Position { column: 1, row: 1 },
+            characters,
+            current_position: Position { column: 1, row: 1, offset },
         }
     }
 
-    fn skip_whitespaces(&mut self) {
-        while self.characters.peek().is_some_and(|c| c.is_whitespace()) {
-            self.advance_character();
+    /// Skips whitespace and `#` comments, which run to the end of the line.
+    fn skip_trivia(&mut self) {
+        loop {
+            match self.characters.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.advance_character();
+                }
+                Some('#') => {
+                    while self.characters.peek().is_some_and(|c| *c != '\n') {
+                        self.advance_character();
+                    }
+                }
+                _ => break,
+            }
         }
     }
 
     fn get_next_token(&mut self) -> Option<Token> {
-        self.skip_whitespaces();
+        self.skip_trivia();
         match self.characters.peek() {
             Some(character) => match character {
-                '+' | '-' | '*' | '/' => self.get_operator(),
-                '0'..='9' => self.get_int_literal(),
-                '(' | ')' => self.get_paren(),
+                '+' | '-' | '*' | '/' | '%' | '^' | '=' | '<' | '>' | '!' | '&' | '|' => self.get_operator(),
+                '0'..='9' | '.' => self.get_number_literal(),
+                '(' | ')' | ',' | ';' => self.get_punctuation(),
+                c if c.is_alphabetic() || *c == '_' => self.get_identifier(),
                 _ => self.get_unrecognised(),
             },
-            None => None,
+            None => self.get_read_error(),
         }
     }
 
-    fn get_paren(&mut self) -> Option<Token> {
-        let start_position = self.current_position.clone();
+    /// Turns an error that ended the characters into a final token.
+    fn get_read_error(&mut self) -> Option<Token> {
+        let error = self.characters.take_error()?;
+        Some(Token {
+            start_position: self.current_position,
+            end_position: self.current_position,
+            lexem: error.to_string(),
+            kind: TokenKind::ReadError,
+        })
+    }
+
+    fn get_punctuation(&mut self) -> Option<Token> {
+        let start_position = self.current_position;
         let mut lexem_buf = Vec::<char>::new();
         let kind = match self.characters.peek() {
             Some(_) => {
                 let c = self.advance_character().unwrap();
-                lexem_buf.push(c.clone());
+                lexem_buf.push(c);
                 match c {
                     '(' => TokenKind::ParenthesisOpen,
                     ')' => TokenKind::ParenthesisClose,
+                    ',' => TokenKind::Comma,
+                    ';' => TokenKind::Semicolon,
                     _ => TokenKind::Unrecognized,
                 }
             }
             None => return None,
         };
-        let end_position = self.current_position.clone();
+        let end_position = self.current_position;
         let lexem = lexem_buf.into_iter().collect::<String>();
 
         Some(Token {
@@ -87,33 +158,25 @@ impl<TSource: CharactersSource> Lexer<TSource> {
     }
 
     fn get_unrecognised(&mut self) -> Option<Token> {
-        let start_position = self.current_position.clone();
+        let start_position = self.current_position;
         let kind = TokenKind::Unrecognized;
         let mut lexem_buf = Vec::<char>::new();
 
-        loop {
-            match self.characters.peek() {
-                Some(c) => match c {
-                    c if c.is_whitespace() => {
-                        break;
-                    }
-                    '+' | '-' | '*' | '/' | '(' | ')' => {
-                        break;
-                    }
-                    _ => {
-                        lexem_buf.push(self.advance_character().unwrap());
-                    }
-                },
-                None => {
+        while let Some(c) = self.characters.peek() {
+            match c {
+                c if is_delimiter(c) => {
                     break;
                 }
+                _ => {
+                    lexem_buf.push(self.advance_character().unwrap());
+                }
             };
         }
 
         match lexem_buf {
-            _l if lexem_buf.len() == 0 => None,
+            _l if lexem_buf.is_empty() => None,
             _ => {
-                let end_position = self.current_position.clone();
+                let end_position = self.current_position;
                 let lexem = lexem_buf.into_iter().collect::<String>();
 
                 Some(Token {
@@ -126,24 +189,91 @@ impl<TSource: CharactersSource> Lexer<TSource> {
         }
     }
 
+    fn get_identifier(&mut self) -> Option<Token> {
+        let start_position = self.current_position;
+        let mut lexem_buf = Vec::<char>::new();
+
+        while self.characters.peek().is_some_and(|c| c.is_alphanumeric() || *c == '_') {
+            lexem_buf.push(self.advance_character().unwrap());
+        }
+
+        match lexem_buf {
+            _l if lexem_buf.is_empty() => None,
+            _ => {
+                let end_position = self.current_position;
+                let lexem = lexem_buf.into_iter().collect::<String>();
+                let kind = keyword_kind(&lexem).unwrap_or(TokenKind::Identifier);
+
+                Some(Token {
+                    start_position,
+                    end_position,
+                    lexem,
+                    kind,
+                })
+            }
+        }
+    }
+
     fn get_operator(&mut self) -> Option<Token> {
-        let start_position = self.current_position.clone();
+        let start_position = self.current_position;
         let mut lexem_buf = Vec::<char>::new();
         let kind = match self.characters.peek() {
             Some(_) => {
                 let c = self.advance_character().unwrap();
-                lexem_buf.push(c.clone());
+                lexem_buf.push(c);
                 match c {
                     '+' => TokenKind::AddOperator,
+                    '-' if self.characters.peek() == Some(&'>') => {
+                        lexem_buf.push(self.advance_character().unwrap());
+                        TokenKind::Arrow
+                    }
                     '-' => TokenKind::SubOperator,
+                    '*' if self.characters.peek() == Some(&'*') => {
+                        lexem_buf.push(self.advance_character().unwrap());
+                        TokenKind::PowOperator
+                    }
                     '*' => TokenKind::MulOperator,
+                    '/' if self.characters.peek() == Some(&'/') => {
+                        lexem_buf.push(self.advance_character().unwrap());
+                        TokenKind::IntDivOperator
+                    }
                     '/' => TokenKind::DivOperator,
+                    '%' => TokenKind::ModOperator,
+                    '^' => TokenKind::PowOperator,
+                    '=' if self.characters.peek() == Some(&'=') => {
+                        lexem_buf.push(self.advance_character().unwrap());
+                        TokenKind::EqualOperator
+                    }
+                    '=' => TokenKind::Assign,
+                    '!' if self.characters.peek() == Some(&'=') => {
+                        lexem_buf.push(self.advance_character().unwrap());
+                        TokenKind::NotEqualOperator
+                    }
+                    '!' => TokenKind::NotOperator,
+                    '<' if self.characters.peek() == Some(&'=') => {
+                        lexem_buf.push(self.advance_character().unwrap());
+                        TokenKind::LessEqualOperator
+                    }
+                    '<' => TokenKind::LessOperator,
+                    '>' if self.characters.peek() == Some(&'=') => {
+                        lexem_buf.push(self.advance_character().unwrap());
+                        TokenKind::GreaterEqualOperator
+                    }
+                    '>' => TokenKind::GreaterOperator,
+                    '&' if self.characters.peek() == Some(&'&') => {
+                        lexem_buf.push(self.advance_character().unwrap());
+                        TokenKind::AndOperator
+                    }
+                    '|' if self.characters.peek() == Some(&'|') => {
+                        lexem_buf.push(self.advance_character().unwrap());
+                        TokenKind::OrOperator
+                    }
                     _ => TokenKind::Unrecognized,
                 }
             }
             None => return None,
         };
-        let end_position = self.current_position.clone();
+        let end_position = self.current_position;
         let lexem = lexem_buf.into_iter().collect::<String>();
 
         Some(Token {
@@ -154,54 +284,62 @@ impl<TSource: CharactersSource> Lexer<TSource> {
         })
     }
 
-    fn get_int_literal(&mut self) -> Option<Token> {
-        let start_position = self.current_position.clone();
-        let mut kind = TokenKind::IntLiteral(0);
+    fn get_number_literal(&mut self) -> Option<Token> {
+        let start_position = self.current_position;
+        let mut recognized = true;
+        let mut is_float = false;
         let mut lexem_buf = Vec::<char>::new();
 
-        loop {
-            match self.characters.peek() {
-                Some(c) => match c {
-                    '0' => {
-                        lexem_buf.push(self.advance_character().unwrap());
+        while let Some(c) = self.characters.peek() {
+            match c {
+                '0'..='9' => {
+                    lexem_buf.push(self.advance_character().unwrap());
+                }
+                '.' => {
+                    lexem_buf.push(self.advance_character().unwrap());
+                    is_float = true;
+                }
+                'e' | 'E' => {
+                    lexem_buf.push(self.advance_character().unwrap());
+                    is_float = true;
 
-                        // first digit can't be zero, unless it's a single zero
-                        if lexem_buf.len() == 1 && self.characters.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
-                            kind = TokenKind::Unrecognized;
-                        }
-                    }
-                    '1'..='9' => {
+                    // the exponent may carry its own sign, e.g. 3e-4
+                    if let Some('+' | '-') = self.characters.peek() {
                         lexem_buf.push(self.advance_character().unwrap());
                     }
-                    c if c.is_whitespace() => {
-                        break;
-                    }
-                    '+' | '-' | '*' | '/' | '(' | ')' => {
-                        break;
-                    }
-                    _ => {
-                        lexem_buf.push(self.advance_character().unwrap());
-                        kind = TokenKind::Unrecognized;
-                    }
-                },
-                None => {
+                }
+                c if is_delimiter(c) => {
                     break;
                 }
+                _ => {
+                    lexem_buf.push(self.advance_character().unwrap());
+                    recognized = false;
+                }
             };
         }
 
         match lexem_buf {
-            _l if lexem_buf.len() == 0 => None,
+            _l if lexem_buf.is_empty() => None,
             _ => {
-                let end_position = self.current_position.clone();
+                let end_position = self.current_position;
                 let lexem = lexem_buf.into_iter().collect::<String>();
 
-                kind = match kind {
-                    TokenKind::IntLiteral(_) => match lexem.parse::<i32>() {
+                // first digit can't be zero, unless it's a single zero
+                let integer_part = lexem.split(['.', 'e', 'E']).next().unwrap_or_default();
+                if integer_part.len() > 1 && integer_part.starts_with('0') {
+                    recognized = false;
+                }
+
+                let kind = match (recognized, is_float) {
+                    (true, false) => match lexem.parse::<BigInt>() {
                         Ok(value) => TokenKind::IntLiteral(value),
                         _ => TokenKind::Unrecognized,
                     },
-                    _ => kind,
+                    (true, true) => match lexem.parse::<f64>() {
+                        Ok(value) => TokenKind::FloatLiteral(value),
+                        _ => TokenKind::Unrecognized,
+                    },
+                    (false, _) => TokenKind::Unrecognized,
                 };
 
                 Some(Token {
@@ -240,50 +378,165 @@ fn from_str() {
     assert_eq!(lexer.characters.next(), None);
 }
 
+#[test]
+fn from_source_file() {
+    let mut sources = source_map::SourceMap::default();
+    sources.add("a.mlor", "1");
+    let file = sources.add("b.mlor", "ąę +\n x");
+    let mut tokens = Lexer::from_source_file(file).into_tokens();
+
+    let name = tokens.next().unwrap();
+    assert_eq!(name.start_position, Position { column: 1, row: 1, offset: 2 });
+    assert_eq!(name.end_position, Position { column: 3, row: 1, offset: 6 });
+    let operator = tokens.next().unwrap();
+    assert_eq!(operator.start_position, Position { column: 4, row: 1, offset: 7 });
+    let variable = tokens.next().unwrap();
+    assert_eq!(variable.start_position, Position { column: 2, row: 2, offset: 10 });
+}
+
+#[test]
+fn from_reader() {
+    struct FailingReader<'a>(&'a [u8]);
+    impl Read for FailingReader<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.split_first() {
+                Some((byte, rest)) => {
+                    buffer[0] = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                None => Err(std::io::Error::other("disk on fire")),
+            }
+        }
+    }
+
+    let tokens = Lexer::from_reader("łoś ** 2".as_bytes()).into_tokens().collect::<Vec<_>>();
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[0].lexem, "łoś");
+    assert_eq!(tokens[2].end_position, Position { column: 9, row: 1, offset: 10 });
+
+    let mut tokens = Lexer::from_reader(FailingReader("x +\n1".as_bytes())).into_tokens();
+    assert_eq!(tokens.next().unwrap().lexem, "x");
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::AddOperator);
+    assert_eq!(tokens.next().unwrap().lexem, "1");
+    let error = tokens.next().unwrap();
+    assert_eq!(error.kind, TokenKind::ReadError);
+    assert_eq!(error.lexem, "disk on fire");
+    assert_eq!(error.start_position, Position { column: 2, row: 2, offset: 5 });
+    assert!(tokens.next().is_none());
+
+    let (_, errors) = crate::parser::Parser::from_tokens(Lexer::from_reader(FailingReader(b"1 +")).into_tokens())
+        .parse_program();
+    assert_eq!(errors[0].code(), "E0003");
+    assert_eq!(errors[0].message(), "Could not read the input: disk on fire");
+}
+
 #[test]
 fn get_int_literal() {
     let lexer = Lexer::from_str("123 0423 9000000000 65a2\n34 0");
     let mut tokens = lexer.into_tokens();
 
     let first = tokens.next().unwrap();
-    assert_eq!(first.kind, TokenKind::IntLiteral(123));
-    assert_eq!(first.start_position, Position { column: 1, row: 1 });
-    assert_eq!(first.end_position, Position { column: 4, row: 1 });
+    assert_eq!(first.kind, TokenKind::IntLiteral(BigInt::from(123)));
+    assert_eq!(first.start_position, Position { column: 1, row: 1, offset: 0 });
+    assert_eq!(first.end_position, Position { column: 4, row: 1, offset: 3 });
     assert_eq!(first.lexem, format!("123"));
 
     let second = tokens.next().unwrap();
     assert_eq!(second.kind, TokenKind::Unrecognized);
-    assert_eq!(second.start_position, Position { column: 5, row: 1 });
-    assert_eq!(second.end_position, Position { column: 9, row: 1 });
+    assert_eq!(second.start_position, Position { column: 5, row: 1, offset: 4 });
+    assert_eq!(second.end_position, Position { column: 9, row: 1, offset: 8 });
     assert_eq!(second.lexem, format!("0423"));
 
     let third = tokens.next().unwrap();
-    assert_eq!(third.kind, TokenKind::Unrecognized);
-    assert_eq!(third.start_position, Position { column: 10, row: 1 });
-    assert_eq!(third.end_position, Position { column: 20, row: 1 });
+    assert_eq!(third.kind, TokenKind::IntLiteral("9000000000".parse().unwrap()));
+    assert_eq!(third.start_position, Position { column: 10, row: 1, offset: 9 });
+    assert_eq!(third.end_position, Position { column: 20, row: 1, offset: 19 });
     assert_eq!(third.lexem, format!("9000000000"));
 
     let fourth = tokens.next().unwrap();
     assert_eq!(fourth.kind, TokenKind::Unrecognized);
-    assert_eq!(fourth.start_position, Position { column: 21, row: 1 });
-    assert_eq!(fourth.end_position, Position { column: 25, row: 1 });
+    assert_eq!(fourth.start_position, Position { column: 21, row: 1, offset: 20 });
+    assert_eq!(fourth.end_position, Position { column: 25, row: 1, offset: 24 });
     assert_eq!(fourth.lexem, format!("65a2"));
 
     let fifth = tokens.next().unwrap();
-    assert_eq!(fifth.kind, TokenKind::IntLiteral(34));
-    assert_eq!(fifth.start_position, Position { column: 1, row: 2 });
-    assert_eq!(fifth.end_position, Position { column: 3, row: 2 });
+    assert_eq!(fifth.kind, TokenKind::IntLiteral(BigInt::from(34)));
+    assert_eq!(fifth.start_position, Position { column: 1, row: 2, offset: 25 });
+    assert_eq!(fifth.end_position, Position { column: 3, row: 2, offset: 27 });
     assert_eq!(fifth.lexem, format!("34"));
 
     let sixth = tokens.next().unwrap();
-    assert_eq!(sixth.kind, TokenKind::IntLiteral(0));
-    assert_eq!(sixth.start_position, Position { column: 4, row: 2 });
-    assert_eq!(sixth.end_position, Position { column: 5, row: 2 });
+    assert_eq!(sixth.kind, TokenKind::IntLiteral(BigInt::from(0)));
+    assert_eq!(sixth.start_position, Position { column: 4, row: 2, offset: 28 });
+    assert_eq!(sixth.end_position, Position { column: 5, row: 2, offset: 29 });
     assert_eq!(sixth.lexem, format!("0"));
 
     assert_eq!(tokens.next(), None);
 }
 
+#[test]
+fn get_long_int_literal() {
+    let lexer = Lexer::from_str("123456789012345678901234567890");
+    let mut tokens = lexer.into_tokens();
+
+    let first = tokens.next().unwrap();
+    assert_eq!(first.kind, TokenKind::IntLiteral("123456789012345678901234567890".parse().unwrap()));
+    assert_eq!(first.end_position, Position { column: 31, row: 1, offset: 30 });
+
+    assert_eq!(tokens.next(), None);
+}
+
+#[test]
+fn get_float_literal() {
+    let lexer = Lexer::from_str("1.5 .5 3e-4 2.5E+3 0.25 1. 00.5 1.2.3 1e");
+    let mut tokens = lexer.into_tokens();
+
+    let first = tokens.next().unwrap();
+    assert_eq!(first.kind, TokenKind::FloatLiteral(1.5));
+    assert_eq!(first.start_position, Position { column: 1, row: 1, offset: 0 });
+    assert_eq!(first.end_position, Position { column: 4, row: 1, offset: 3 });
+    assert_eq!(first.lexem, format!("1.5"));
+
+    let second = tokens.next().unwrap();
+    assert_eq!(second.kind, TokenKind::FloatLiteral(0.5));
+    assert_eq!(second.start_position, Position { column: 5, row: 1, offset: 4 });
+    assert_eq!(second.end_position, Position { column: 7, row: 1, offset: 6 });
+    assert_eq!(second.lexem, format!(".5"));
+
+    let third = tokens.next().unwrap();
+    assert_eq!(third.kind, TokenKind::FloatLiteral(3e-4));
+    assert_eq!(third.start_position, Position { column: 8, row: 1, offset: 7 });
+    assert_eq!(third.end_position, Position { column: 12, row: 1, offset: 11 });
+    assert_eq!(third.lexem, format!("3e-4"));
+
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::FloatLiteral(2500.0));
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::FloatLiteral(0.25));
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::FloatLiteral(1.0));
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::Unrecognized);
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::Unrecognized);
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::Unrecognized);
+
+    assert_eq!(tokens.next(), None);
+}
+
+#[test]
+fn get_float_literal_in_expression() {
+    let lexer = Lexer::from_str("1.5*2-3e-4");
+    let kinds = lexer.into_tokens().map(|token| token.kind).collect::<Vec<_>>();
+
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::FloatLiteral(1.5),
+            TokenKind::MulOperator,
+            TokenKind::IntLiteral(BigInt::from(2)),
+            TokenKind::SubOperator,
+            TokenKind::FloatLiteral(3e-4),
+        ]
+    );
+}
+
 #[test]
 fn get_operator() {
     let lexer = Lexer::from_str("+- *\n/");
@@ -291,26 +544,26 @@ fn get_operator() {
 
     let first = tokens.next().unwrap();
     assert_eq!(first.kind, TokenKind::AddOperator);
-    assert_eq!(first.start_position, Position { column: 1, row: 1 });
-    assert_eq!(first.end_position, Position { column: 2, row: 1 });
+    assert_eq!(first.start_position, Position { column: 1, row: 1, offset: 0 });
+    assert_eq!(first.end_position, Position { column: 2, row: 1, offset: 1 });
     assert_eq!(first.lexem, format!("+"));
 
     let second = tokens.next().unwrap();
     assert_eq!(second.kind, TokenKind::SubOperator);
-    assert_eq!(second.start_position, Position { column: 2, row: 1 });
-    assert_eq!(second.end_position, Position { column: 3, row: 1 });
+    assert_eq!(second.start_position, Position { column: 2, row: 1, offset: 1 });
+    assert_eq!(second.end_position, Position { column: 3, row: 1, offset: 2 });
     assert_eq!(second.lexem, format!("-"));
 
     let third = tokens.next().unwrap();
     assert_eq!(third.kind, TokenKind::MulOperator);
-    assert_eq!(third.start_position, Position { column: 4, row: 1 });
-    assert_eq!(third.end_position, Position { column: 5, row: 1 });
+    assert_eq!(third.start_position, Position { column: 4, row: 1, offset: 3 });
+    assert_eq!(third.end_position, Position { column: 5, row: 1, offset: 4 });
     assert_eq!(third.lexem, format!("*"));
 
     let fourth = tokens.next().unwrap();
     assert_eq!(fourth.kind, TokenKind::DivOperator);
-    assert_eq!(fourth.start_position, Position { column: 1, row: 2 });
-    assert_eq!(fourth.end_position, Position { column: 2, row: 2 });
+    assert_eq!(fourth.start_position, Position { column: 1, row: 2, offset: 5 });
+    assert_eq!(fourth.end_position, Position { column: 2, row: 2, offset: 6 });
     assert_eq!(fourth.lexem, format!("/"));
 
     assert_eq!(tokens.next(), None);
@@ -318,27 +571,83 @@ fn get_operator() {
 
 #[test]
 fn get_unrecognised() {
-    let lexer = Lexer::from_str("a44-W");
+    let lexer = Lexer::from_str("$44-@");
     let mut tokens = lexer.into_tokens();
 
     let first = tokens.next().unwrap();
     assert_eq!(first.kind, TokenKind::Unrecognized);
-    assert_eq!(first.start_position, Position { column: 1, row: 1 });
-    assert_eq!(first.end_position, Position { column: 4, row: 1 });
-    assert_eq!(first.lexem, format!("a44"));
+    assert_eq!(first.start_position, Position { column: 1, row: 1, offset: 0 });
+    assert_eq!(first.end_position, Position { column: 4, row: 1, offset: 3 });
+    assert_eq!(first.lexem, format!("$44"));
 
     let second = tokens.next().unwrap();
     assert_eq!(second.kind, TokenKind::SubOperator);
-    assert_eq!(second.start_position, Position { column: 4, row: 1 });
-    assert_eq!(second.end_position, Position { column: 5, row: 1 });
+    assert_eq!(second.start_position, Position { column: 4, row: 1, offset: 3 });
+    assert_eq!(second.end_position, Position { column: 5, row: 1, offset: 4 });
     assert_eq!(second.lexem, format!("-"));
 
     let third = tokens.next().unwrap();
     assert_eq!(third.kind, TokenKind::Unrecognized);
-    assert_eq!(third.start_position, Position { column: 5, row: 1 });
-    assert_eq!(third.end_position, Position { column: 6, row: 1 });
+    assert_eq!(third.start_position, Position { column: 5, row: 1, offset: 4 });
+    assert_eq!(third.end_position, Position { column: 6, row: 1, offset: 5 });
+    assert_eq!(third.lexem, format!("@"));
+
+    assert_eq!(tokens.next(), None);
+}
+
+#[test]
+fn get_identifier() {
+    let lexer = Lexer::from_str("a44-W let _x1=letter");
+    let mut tokens = lexer.into_tokens();
+
+    let first = tokens.next().unwrap();
+    assert_eq!(first.kind, TokenKind::Identifier);
+    assert_eq!(first.start_position, Position { column: 1, row: 1, offset: 0 });
+    assert_eq!(first.end_position, Position { column: 4, row: 1, offset: 3 });
+    assert_eq!(first.lexem, format!("a44"));
+
+    let second = tokens.next().unwrap();
+    assert_eq!(second.kind, TokenKind::SubOperator);
+
+    let third = tokens.next().unwrap();
+    assert_eq!(third.kind, TokenKind::Identifier);
     assert_eq!(third.lexem, format!("W"));
 
+    let fourth = tokens.next().unwrap();
+    assert_eq!(fourth.kind, TokenKind::LetKeyword);
+    assert_eq!(fourth.start_position, Position { column: 7, row: 1, offset: 6 });
+    assert_eq!(fourth.end_position, Position { column: 10, row: 1, offset: 9 });
+
+    let fifth = tokens.next().unwrap();
+    assert_eq!(fifth.kind, TokenKind::Identifier);
+    assert_eq!(fifth.lexem, format!("_x1"));
+
+    let sixth = tokens.next().unwrap();
+    assert_eq!(sixth.kind, TokenKind::Assign);
+    assert_eq!(sixth.start_position, Position { column: 14, row: 1, offset: 13 });
+    assert_eq!(sixth.end_position, Position { column: 15, row: 1, offset: 14 });
+
+    let seventh = tokens.next().unwrap();
+    assert_eq!(seventh.kind, TokenKind::Identifier);
+    assert_eq!(seventh.lexem, format!("letter"));
+
+    assert_eq!(tokens.next(), None);
+}
+
+#[test]
+fn skip_comments() {
+    let lexer = Lexer::from_str("# note\nx# x\n  +1 #");
+    let mut tokens = lexer.into_tokens();
+
+    let first = tokens.next().unwrap();
+    assert_eq!(first.kind, TokenKind::Identifier);
+    assert_eq!(first.start_position, Position { column: 1, row: 2, offset: 7 });
+
+    let second = tokens.next().unwrap();
+    assert_eq!(second.kind, TokenKind::AddOperator);
+    assert_eq!(second.start_position, Position { column: 3, row: 3, offset: 14 });
+
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::IntLiteral(BigInt::from(1)));
     assert_eq!(tokens.next(), None);
 }
 
@@ -349,82 +658,213 @@ fn get_paren() {
 
     let first = tokens.next().unwrap();
     assert_eq!(first.kind, TokenKind::ParenthesisOpen);
-    assert_eq!(first.start_position, Position { column: 1, row: 1 });
-    assert_eq!(first.end_position, Position { column: 2, row: 1 });
+    assert_eq!(first.start_position, Position { column: 1, row: 1, offset: 0 });
+    assert_eq!(first.end_position, Position { column: 2, row: 1, offset: 1 });
     assert_eq!(first.lexem, format!("("));
 
     let second = tokens.next().unwrap();
     assert_eq!(second.kind, TokenKind::ParenthesisClose);
-    assert_eq!(second.start_position, Position { column: 2, row: 1 });
-    assert_eq!(second.end_position, Position { column: 3, row: 1 });
+    assert_eq!(second.start_position, Position { column: 2, row: 1, offset: 1 });
+    assert_eq!(second.end_position, Position { column: 3, row: 1, offset: 2 });
     assert_eq!(second.lexem, format!(")"));
 
     let third = tokens.next().unwrap();
     assert_eq!(third.kind, TokenKind::ParenthesisOpen);
-    assert_eq!(third.start_position, Position { column: 4, row: 1 });
-    assert_eq!(third.end_position, Position { column: 5, row: 1 });
+    assert_eq!(third.start_position, Position { column: 4, row: 1, offset: 3 });
+    assert_eq!(third.end_position, Position { column: 5, row: 1, offset: 4 });
     assert_eq!(third.lexem, format!("("));
 
     assert_eq!(tokens.next(), None);
 }
 
+#[test]
+fn get_function_definition() {
+    let lexer = Lexer::from_str("fn add(a,b) = a");
+    let kinds = lexer.into_tokens().map(|token| token.kind).collect::<Vec<_>>();
+
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::FnKeyword,
+            TokenKind::Identifier,
+            TokenKind::ParenthesisOpen,
+            TokenKind::Identifier,
+            TokenKind::Comma,
+            TokenKind::Identifier,
+            TokenKind::ParenthesisClose,
+            TokenKind::Assign,
+            TokenKind::Identifier,
+        ]
+    );
+}
+
+#[test]
+fn get_lambda() {
+    let lexer = Lexer::from_str("fun x->x - -1");
+    let mut tokens = lexer.into_tokens();
+
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::FunKeyword);
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::Identifier);
+
+    let arrow = tokens.next().unwrap();
+    assert_eq!(arrow.kind, TokenKind::Arrow);
+    assert_eq!(arrow.start_position, Position { column: 6, row: 1, offset: 5 });
+    assert_eq!(arrow.end_position, Position { column: 8, row: 1, offset: 7 });
+    assert_eq!(arrow.lexem, format!("->"));
+
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::Identifier);
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::SubOperator);
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::SubOperator);
+    assert_eq!(tokens.next().unwrap().kind, TokenKind::IntLiteral(BigInt::from(1)));
+    assert_eq!(tokens.next(), None);
+}
+
+#[test]
+fn get_comparison_and_logical_operators() {
+    let lexer = Lexer::from_str("a<=b==!true&&c!=d||x>y>=z<1=& |");
+    let kinds = lexer.into_tokens().map(|token| token.kind).collect::<Vec<_>>();
+
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Identifier,
+            TokenKind::LessEqualOperator,
+            TokenKind::Identifier,
+            TokenKind::EqualOperator,
+            TokenKind::NotOperator,
+            TokenKind::BoolLiteral(true),
+            TokenKind::AndOperator,
+            TokenKind::Identifier,
+            TokenKind::NotEqualOperator,
+            TokenKind::Identifier,
+            TokenKind::OrOperator,
+            TokenKind::Identifier,
+            TokenKind::GreaterOperator,
+            TokenKind::Identifier,
+            TokenKind::GreaterEqualOperator,
+            TokenKind::Identifier,
+            TokenKind::LessOperator,
+            TokenKind::IntLiteral(BigInt::from(1)),
+            TokenKind::Assign,
+            TokenKind::Unrecognized,
+            TokenKind::Unrecognized,
+        ]
+    );
+
+    let mut tokens = Lexer::from_str("x >= 1").into_tokens();
+    tokens.next();
+    let operator = tokens.next().unwrap();
+    assert_eq!(operator.start_position, Position { column: 3, row: 1, offset: 2 });
+    assert_eq!(operator.end_position, Position { column: 5, row: 1, offset: 4 });
+    assert_eq!(operator.lexem, format!(">="));
+}
+
+#[test]
+fn get_power_and_modulo_operators() {
+    let lexer = Lexer::from_str("a**b^c%d//e*/f");
+    let kinds = lexer.into_tokens().map(|token| token.kind).collect::<Vec<_>>();
+
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Identifier,
+            TokenKind::PowOperator,
+            TokenKind::Identifier,
+            TokenKind::PowOperator,
+            TokenKind::Identifier,
+            TokenKind::ModOperator,
+            TokenKind::Identifier,
+            TokenKind::IntDivOperator,
+            TokenKind::Identifier,
+            TokenKind::MulOperator,
+            TokenKind::DivOperator,
+            TokenKind::Identifier,
+        ]
+    );
+}
+
+#[test]
+fn keywords() {
+    for keyword in KEYWORDS {
+        assert!(keyword_kind(keyword).is_some(), "{} is not a keyword", keyword);
+    }
+}
+
+#[test]
+fn get_conditional() {
+    let lexer = Lexer::from_str("if x then iffy else elsewhere");
+    let kinds = lexer.into_tokens().map(|token| token.kind).collect::<Vec<_>>();
+
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::IfKeyword,
+            TokenKind::Identifier,
+            TokenKind::ThenKeyword,
+            TokenKind::Identifier,
+            TokenKind::ElseKeyword,
+            TokenKind::Identifier,
+        ]
+    );
+}
+
 #[test]
 fn into_tokens() {
     let lexer = Lexer::from_str("2*3 - (5+2)");
     let mut tokens = lexer.into_tokens();
 
     let first = tokens.next().unwrap();
-    assert_eq!(first.kind, TokenKind::IntLiteral(2));
-    assert_eq!(first.start_position, Position { column: 1, row: 1 });
-    assert_eq!(first.end_position, Position { column: 2, row: 1 });
+    assert_eq!(first.kind, TokenKind::IntLiteral(BigInt::from(2)));
+    assert_eq!(first.start_position, Position { column: 1, row: 1, offset: 0 });
+    assert_eq!(first.end_position, Position { column: 2, row: 1, offset: 1 });
     assert_eq!(first.lexem, format!("2"));
 
     let second = tokens.next().unwrap();
     assert_eq!(second.kind, TokenKind::MulOperator);
-    assert_eq!(second.start_position, Position { column: 2, row: 1 });
-    assert_eq!(second.end_position, Position { column: 3, row: 1 });
+    assert_eq!(second.start_position, Position { column: 2, row: 1, offset: 1 });
+    assert_eq!(second.end_position, Position { column: 3, row: 1, offset: 2 });
     assert_eq!(second.lexem, format!("*"));
 
     let third = tokens.next().unwrap();
-    assert_eq!(third.kind, TokenKind::IntLiteral(3));
-    assert_eq!(third.start_position, Position { column: 3, row: 1 });
-    assert_eq!(third.end_position, Position { column: 4, row: 1 });
+    assert_eq!(third.kind, TokenKind::IntLiteral(BigInt::from(3)));
+    assert_eq!(third.start_position, Position { column: 3, row: 1, offset: 2 });
+    assert_eq!(third.end_position, Position { column: 4, row: 1, offset: 3 });
     assert_eq!(third.lexem, format!("3"));
 
     let fourth = tokens.next().unwrap();
     assert_eq!(fourth.kind, TokenKind::SubOperator);
-    assert_eq!(fourth.start_position, Position { column: 5, row: 1 });
-    assert_eq!(fourth.end_position, Position { column: 6, row: 1 });
+    assert_eq!(fourth.start_position, Position { column: 5, row: 1, offset: 4 });
+    assert_eq!(fourth.end_position, Position { column: 6, row: 1, offset: 5 });
     assert_eq!(fourth.lexem, format!("-"));
 
     let fourth = tokens.next().unwrap();
     assert_eq!(fourth.kind, TokenKind::ParenthesisOpen);
-    assert_eq!(fourth.start_position, Position { column: 7, row: 1 });
-    assert_eq!(fourth.end_position, Position { column: 8, row: 1 });
+    assert_eq!(fourth.start_position, Position { column: 7, row: 1, offset: 6 });
+    assert_eq!(fourth.end_position, Position { column: 8, row: 1, offset: 7 });
     assert_eq!(fourth.lexem, format!("("));
 
     let fifth = tokens.next().unwrap();
-    assert_eq!(fifth.kind, TokenKind::IntLiteral(5));
-    assert_eq!(fifth.start_position, Position { column: 8, row: 1 });
-    assert_eq!(fifth.end_position, Position { column: 9, row: 1 });
+    assert_eq!(fifth.kind, TokenKind::IntLiteral(BigInt::from(5)));
+    assert_eq!(fifth.start_position, Position { column: 8, row: 1, offset: 7 });
+    assert_eq!(fifth.end_position, Position { column: 9, row: 1, offset: 8 });
     assert_eq!(fifth.lexem, format!("5"));
 
     let sixth = tokens.next().unwrap();
     assert_eq!(sixth.kind, TokenKind::AddOperator);
-    assert_eq!(sixth.start_position, Position { column: 9, row: 1 });
-    assert_eq!(sixth.end_position, Position { column: 10, row: 1 });
+    assert_eq!(sixth.start_position, Position { column: 9, row: 1, offset: 8 });
+    assert_eq!(sixth.end_position, Position { column: 10, row: 1, offset: 9 });
     assert_eq!(sixth.lexem, format!("+"));
 
     let seventh = tokens.next().unwrap();
-    assert_eq!(seventh.kind, TokenKind::IntLiteral(2));
-    assert_eq!(seventh.start_position, Position { column: 10, row: 1 });
-    assert_eq!(seventh.end_position, Position { column: 11, row: 1 });
+    assert_eq!(seventh.kind, TokenKind::IntLiteral(BigInt::from(2)));
+    assert_eq!(seventh.start_position, Position { column: 10, row: 1, offset: 9 });
+    assert_eq!(seventh.end_position, Position { column: 11, row: 1, offset: 10 });
     assert_eq!(seventh.lexem, format!("2"));
 
     let eigth = tokens.next().unwrap();
     assert_eq!(eigth.kind, TokenKind::ParenthesisClose);
-    assert_eq!(eigth.start_position, Position { column: 11, row: 1 });
-    assert_eq!(eigth.end_position, Position { column: 12, row: 1 });
+    assert_eq!(eigth.start_position, Position { column: 11, row: 1, offset: 10 });
+    assert_eq!(eigth.end_position, Position { column: 12, row: 1, offset: 11 });
     assert_eq!(eigth.lexem, format!(")"));
 
     assert_eq!(tokens.next(), None);
@@ -1,8 +1,30 @@
-use std::iter::Peekable;
+use std::{
+    io::{self, Read},
+    iter::Peekable,
+};
 
 pub trait CharactersSource {
     fn next(&mut self) -> Option<char>;
     fn peek(&mut self) -> Option<&char>;
+
+    /// Error that ended the characters early, reported once.
+    fn take_error(&mut self) -> Option<io::Error> {
+        None
+    }
+}
+
+impl<T: CharactersSource + ?Sized> CharactersSource for &mut T {
+    fn next(&mut self) -> Option<char> {
+        (**self).next()
+    }
+
+    fn peek(&mut self) -> Option<&char> {
+        (**self).peek()
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        (**self).take_error()
+    }
 }
 
 pub struct StringSource {
@@ -10,9 +32,10 @@ pub struct StringSource {
 }
 
 impl StringSource {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(string: &str) -> Self {
         let s = string.to_string();
-        let v = s.chars().into_iter().collect::<Vec<char>>().into_iter();
+        let v = s.chars().collect::<Vec<char>>().into_iter();
         Self { source: v.peekable()}
     }
 }
@@ -27,6 +50,168 @@ impl CharactersSource for StringSource {
     }
 }
 
+/// Decodes UTF-8 from a reader as it is consumed, holding at most
+/// `capacity` bytes at a time. Invalid bytes decode to U+FFFD, a read
+/// error ends the characters and is kept for [`CharactersSource::take_error`].
+pub struct ReaderSource<R: Read> {
+    reader: R,
+    buffer: Box<[u8]>,
+    start: usize,
+    end: usize,
+    peeked: Option<char>,
+    error: Option<io::Error>,
+    finished: bool,
+    /// Characters decoded so far, when recording.
+    text: Option<String>,
+}
+
+impl<R: Read> ReaderSource<R> {
+    const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(reader, Self::DEFAULT_CAPACITY)
+    }
+
+    /// The capacity is raised to 4 bytes, the longest UTF-8 encoded character.
+    pub fn with_capacity(reader: R, capacity: usize) -> Self {
+        Self {
+            reader,
+            buffer: vec![0; capacity.max(4)].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            peeked: None,
+            error: None,
+            finished: false,
+            text: None,
+        }
+    }
+
+    /// Keeps every decoded character, so that the text can be shown in
+    /// diagnostics once it has been read.
+    pub fn recording(mut self) -> Self {
+        self.text = Some(String::new());
+        self
+    }
+
+    /// Text decoded so far, empty unless recording.
+    pub fn into_text(self) -> String {
+        self.text.unwrap_or_default()
+    }
+
+    /// Moves the unread bytes to the front and reads more after them,
+    /// returns false when nothing more can be read.
+    fn fill(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+        self.buffer.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        loop {
+            match self.reader.read(&mut self.buffer[self.end..]) {
+                Ok(0) => {
+                    self.finished = true;
+                    return false;
+                }
+                Ok(read) => {
+                    self.end += read;
+                    return true;
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.error = Some(error);
+                    self.finished = true;
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn decode(&mut self) -> Option<char> {
+        let decoded = self.decode_character();
+        if let (Some(text), Some(c)) = (&mut self.text, decoded) {
+            text.push(c);
+        }
+        decoded
+    }
+
+    fn decode_character(&mut self) -> Option<char> {
+        if self.start == self.end && !self.fill() {
+            return None;
+        }
+        let width = match self.buffer[self.start] {
+            0x00..=0x7f => 1,
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => {
+                self.start += 1;
+                return Some(char::REPLACEMENT_CHARACTER);
+            }
+        };
+        // the rest of a character may still be in the reader
+        while self.end - self.start < width {
+            if !self.fill() {
+                self.start = self.end;
+                return Some(char::REPLACEMENT_CHARACTER);
+            }
+        }
+        match std::str::from_utf8(&self.buffer[self.start..self.start + width]) {
+            Ok(decoded) => {
+                self.start += width;
+                decoded.chars().next()
+            }
+            Err(_) => {
+                self.start += 1;
+                Some(char::REPLACEMENT_CHARACTER)
+            }
+        }
+    }
+}
+
+impl<R: Read> CharactersSource for ReaderSource<R> {
+    fn next(&mut self) -> Option<char> {
+        self.peeked.take().or_else(|| self.decode())
+    }
+
+    fn peek(&mut self) -> Option<&char> {
+        if self.peeked.is_none() {
+            self.peeked = self.decode();
+        }
+        self.peeked.as_ref()
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+/// Reader handing out its bytes a few at a time, then failing if asked to.
+#[cfg(test)]
+struct ChunkedReader {
+    bytes: Vec<u8>,
+    chunk: usize,
+    fail: bool,
+}
+
+#[cfg(test)]
+impl Read for ChunkedReader {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.bytes.is_empty() && self.fail {
+            return Err(io::Error::other("connection reset"));
+        }
+        let read = self.chunk.min(buffer.len()).min(self.bytes.len());
+        buffer[..read].copy_from_slice(&self.bytes[..read]);
+        self.bytes.drain(..read);
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+fn read_all(mut source: impl CharactersSource) -> String {
+    std::iter::from_fn(|| source.next()).collect()
+}
+
 #[test]
 fn string_source_from_str() {
     let mut string_source = StringSource::from_str("abc");
@@ -50,3 +235,64 @@ fn string_source_peek() {
     let mut string_source = StringSource::from_str("abcd");
     assert_eq!(string_source.peek().unwrap(), &'a');
 }
+
+#[test]
+fn reader_source_decodes_characters_split_across_reads() {
+    let text = "ząb + 🦀 = €";
+    for chunk in 1..=5 {
+        let reader = ChunkedReader {
+            bytes: text.as_bytes().to_vec(),
+            chunk,
+            fail: false,
+        };
+        assert_eq!(read_all(ReaderSource::with_capacity(reader, 4)), text);
+    }
+}
+
+#[test]
+fn reader_source_peek() {
+    let mut source = ReaderSource::new("źx".as_bytes());
+    assert_eq!(source.peek(), Some(&'ź'));
+    assert_eq!(source.peek(), Some(&'ź'));
+    assert_eq!(source.next(), Some('ź'));
+    assert_eq!(source.next(), Some('x'));
+    assert_eq!(source.peek(), None);
+    assert_eq!(source.next(), None);
+    assert!(source.take_error().is_none());
+}
+
+#[test]
+fn reader_source_replaces_invalid_bytes() {
+    let bytes: &[u8] = &[b'a', 0xff, b'b', 0xe2, 0x82, b'c', 0xc5];
+    assert_eq!(read_all(ReaderSource::with_capacity(bytes, 2)), "a\u{fffd}b\u{fffd}\u{fffd}c\u{fffd}");
+}
+
+#[test]
+fn reader_source_keeps_read_errors() {
+    let reader = ChunkedReader {
+        bytes: b"12".to_vec(),
+        chunk: 1,
+        fail: true,
+    };
+    let mut source = ReaderSource::new(reader);
+    assert_eq!(source.next(), Some('1'));
+    assert_eq!(source.next(), Some('2'));
+    assert_eq!(source.next(), None);
+    assert_eq!(source.take_error().unwrap().to_string(), "connection reset");
+    assert!(source.take_error().is_none());
+    assert_eq!(source.next(), None);
+}
+
+#[test]
+fn reader_source_records_decoded_text() {
+    let bytes: &[u8] = &[b'a', 0xff, b' ', 0xc5, 0x82];
+    let mut source = ReaderSource::with_capacity(bytes, 2).recording();
+    assert_eq!(source.next(), Some('a'));
+    assert_eq!(source.peek(), Some(&'\u{fffd}'));
+    assert_eq!(source.next(), Some('\u{fffd}'));
+    assert_eq!(source.into_text(), "a\u{fffd}");
+
+    let mut source = ReaderSource::new(bytes);
+    assert_eq!(read_all(&mut source), "a\u{fffd} ł");
+    assert_eq!(source.into_text(), "");
+}
@@ -0,0 +1,140 @@
+use super::token::Position;
+
+/// A named piece of source text placed in a [`SourceMap`].
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+    /// Offset of the first byte of the file in its source map.
+    pub start: usize,
+    /// Offsets of the first byte of each line, relative to `start`.
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(name: String, text: String, start: usize) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self {
+            name,
+            text,
+            start,
+            line_starts,
+        }
+    }
+
+    /// Offset just past the last byte of the file.
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// Row and column of an offset that falls into this file.
+    pub fn position(&self, offset: usize) -> Position {
+        let relative = offset.saturating_sub(self.start).min(self.text.len());
+        let row = self.line_starts.partition_point(|line_start| *line_start <= relative);
+        let line_start = self.line_starts[row - 1];
+        let column = self.text[line_start..relative].chars().count() + 1;
+        Position {
+            column: column as u32,
+            row: row as u32,
+            offset,
+        }
+    }
+
+    /// Text of the 1-based `row`, without its line break.
+    pub fn line(&self, row: u32) -> &str {
+        let Some(start) = self.line_starts.get(row as usize - 1) else {
+            return "";
+        };
+        let end = self.line_starts.get(row as usize).map_or(self.text.len(), |next| next - 1);
+        self.text[*start..end].trim_end_matches('\r')
+    }
+}
+
+/// Source files laid out one after another in a single offset space, so
+/// that the offset of a position is enough to tell which file, line and
+/// column it points at.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    /// Offset the next file starts at, removed files keep their offsets.
+    next_start: usize,
+}
+
+impl SourceMap {
+    /// Places a file after the ones added before. Files are separated by
+    /// one unused offset, so the end of a file is never the start of the next.
+    pub fn add(&mut self, name: impl Into<String>, text: impl Into<String>) -> &SourceFile {
+        let file = SourceFile::new(name.into(), text.into(), self.next_start);
+        self.next_start = file.end() + 1;
+        self.files.push(file);
+        self.files.last().unwrap()
+    }
+
+    /// Offset the next added file starts at.
+    pub fn next_start(&self) -> usize {
+        self.next_start
+    }
+
+    /// Removes the files `keep` returns false for. Their offsets are not
+    /// reused, so no position can point into a different file later.
+    pub fn retain(&mut self, keep: impl FnMut(&SourceFile) -> bool) {
+        self.files.retain(keep);
+    }
+
+    /// Files in the order they were added.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// File containing the offset, the offset just past its end included.
+    pub fn file(&self, offset: usize) -> Option<&SourceFile> {
+        let index = self.files.partition_point(|file| file.start <= offset);
+        self.files[..index].last().filter(|file| offset <= file.end())
+    }
+
+    /// Most recently added file.
+    pub fn last(&self) -> Option<&SourceFile> {
+        self.files.last()
+    }
+}
+
+#[test]
+fn positions_and_lines() {
+    let mut sources = SourceMap::default();
+    let file = sources.add("a.mlor", "let x = 1\n\tżółw + 2\r\n");
+
+    assert_eq!(file.position(4), Position { column: 5, row: 1, offset: 4 });
+    assert_eq!(file.position(10), Position { column: 1, row: 2, offset: 10 });
+    assert_eq!(file.position(19), Position { column: 7, row: 2, offset: 19 });
+    assert_eq!(file.line(1), "let x = 1");
+    assert_eq!(file.line(2), "\tżółw + 2");
+    assert_eq!(file.line(3), "");
+    assert_eq!(file.line(4), "");
+}
+
+#[test]
+fn offsets_map_back_to_their_files() {
+    let mut sources = SourceMap::default();
+    assert_eq!(sources.add("a.mlor", "1 + 2").start, 0);
+    assert_eq!(sources.add("b.mlor", "x\ny").start, 6);
+    assert_eq!(sources.add("c.mlor", "").start, 10);
+
+    let name = |offset| sources.file(offset).map(|file| file.name.as_str());
+    assert_eq!(name(0), Some("a.mlor"));
+    assert_eq!(name(5), Some("a.mlor"));
+    assert_eq!(name(6), Some("b.mlor"));
+    assert_eq!(name(9), Some("b.mlor"));
+    assert_eq!(name(10), Some("c.mlor"));
+    assert_eq!(name(11), None);
+
+    let file = sources.file(8).unwrap();
+    assert_eq!(file.position(8), Position { column: 1, row: 2, offset: 8 });
+    assert_eq!(file.line(2), "y");
+
+    // removed files leave a gap behind
+    sources.retain(|file| file.name != "c.mlor");
+    assert_eq!(sources.add("d.mlor", "z").start, 11);
+    assert!(sources.file(10).is_none());
+    assert_eq!(sources.file(11).map(|file| file.name.as_str()), Some("d.mlor"));
+}
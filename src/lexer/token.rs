@@ -1,3 +1,5 @@
+use crate::numeric::bigint::BigInt;
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct Token {
     pub start_position: Position,
@@ -6,21 +8,133 @@ pub struct Token {
     pub kind: TokenKind,
 }
 
+impl Token {
+    pub fn span(&self) -> Span {
+        Span {
+            start: self.start_position,
+            end: self.end_position,
+        }
+    }
+
+    /// Human readable description of this particular token, quoting the
+    /// source text where the kind alone is not telling enough.
+    pub fn describe(&self) -> String {
+        match self.kind {
+            TokenKind::IntLiteral(_) | TokenKind::FloatLiteral(_) => format!("the number `{}`", self.lexem),
+            TokenKind::Identifier => format!("the name `{}`", self.lexem),
+            TokenKind::Unrecognized => format!("the unrecognised `{}`", self.lexem),
+            _ => self.kind.describe().to_string(),
+        }
+    }
+}
+
+/// Location in the source, `offset` counts bytes from the start of the
+/// source (or from the start of its file in a [`SourceMap`]).
+///
+/// [`SourceMap`]: crate::lexer::source_map::SourceMap
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Position {
     pub column: u32,
     pub row: u32,
+    pub offset: usize,
+}
+
+/// Source range covered by a token, the end position is exclusive.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// Span from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
 }
 
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Clone, Debug)]
 pub enum TokenKind {
-    IntLiteral(i32),
+    IntLiteral(BigInt),
+    FloatLiteral(f64),
+    BoolLiteral(bool),
     AddOperator,
     SubOperator,
     MulOperator,
     DivOperator,
+    IntDivOperator,
+    ModOperator,
+    PowOperator,
+    EqualOperator,
+    NotEqualOperator,
+    LessOperator,
+    LessEqualOperator,
+    GreaterOperator,
+    GreaterEqualOperator,
+    AndOperator,
+    OrOperator,
+    NotOperator,
+    Assign,
+    Arrow,
+    Identifier,
+    LetKeyword,
+    FnKeyword,
+    FunKeyword,
+    IfKeyword,
+    ThenKeyword,
+    ElseKeyword,
     ParenthesisOpen,
     ParenthesisClose,
+    Comma,
+    Semicolon,
     Unrecognized,
-    EOF
-}
\ No newline at end of file
+    /// The input could not be read any further, the lexem holds the reason.
+    ReadError,
+    Eof,
+}
+
+impl TokenKind {
+    /// Human readable name of the kind of token, e.g. "a number" or "`)`".
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::IntLiteral(_) | Self::FloatLiteral(_) => "a number",
+            Self::BoolLiteral(true) => "`true`",
+            Self::BoolLiteral(false) => "`false`",
+            Self::AddOperator => "`+`",
+            Self::SubOperator => "`-`",
+            Self::MulOperator => "`*`",
+            Self::DivOperator => "`/`",
+            Self::IntDivOperator => "`//`",
+            Self::ModOperator => "`%`",
+            Self::PowOperator => "`**`",
+            Self::EqualOperator => "`==`",
+            Self::NotEqualOperator => "`!=`",
+            Self::LessOperator => "`<`",
+            Self::LessEqualOperator => "`<=`",
+            Self::GreaterOperator => "`>`",
+            Self::GreaterEqualOperator => "`>=`",
+            Self::AndOperator => "`&&`",
+            Self::OrOperator => "`||`",
+            Self::NotOperator => "`!`",
+            Self::Assign => "`=`",
+            Self::Arrow => "`->`",
+            Self::Identifier => "a name",
+            Self::LetKeyword => "`let`",
+            Self::FnKeyword => "`fn`",
+            Self::FunKeyword => "`fun`",
+            Self::IfKeyword => "`if`",
+            Self::ThenKeyword => "`then`",
+            Self::ElseKeyword => "`else`",
+            Self::ParenthesisOpen => "`(`",
+            Self::ParenthesisClose => "`)`",
+            Self::Comma => "`,`",
+            Self::Semicolon => "`;`",
+            Self::Unrecognized => "an unrecognised token",
+            Self::ReadError => "an unreadable input",
+            Self::Eof => "the end of the statement",
+        }
+    }
+}
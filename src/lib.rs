@@ -0,0 +1,24 @@
+//! An interpreter for a small statically typed expression language.
+//!
+//! [`Engine`] runs sources and keeps their bindings, the modules below it
+//! expose every stage on its own: [`Lexer`], [`Parser`] and the syntax tree
+//! it builds, the type checker, the evaluator and the bytecode compiler
+//! with its virtual machine, and the [`formatter`] rewriting sources in
+//! the canonical style.
+
+pub mod diagnostics;
+mod engine;
+pub mod evaluator;
+pub mod formatter;
+pub mod lexer;
+pub mod numeric;
+pub mod optimizer;
+pub mod parser;
+pub mod repl;
+pub mod typechecker;
+pub mod vm;
+
+pub use engine::{Backend, Engine, Error};
+pub use evaluator::value::Value;
+pub use lexer::Lexer;
+pub use parser::Parser;
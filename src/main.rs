@@ -1,18 +1,273 @@
-use lexer::Lexer;
-use std::io::{self, BufRead};
+use mlor::{
+    diagnostics::{Diagnostic, Style},
+    evaluator::context::{Context, NumericMode, RationalFormat},
+    formatter,
+    lexer::{source::ReaderSource, source_map::SourceMap, Lexer},
+    parser::{
+        export::{Format, Tree},
+        Parser,
+    },
+    repl::{
+        self,
+        editor::{LineEditor, PlainInput},
+        history::History,
+        Session,
+    },
+    Backend, Engine,
+};
+use std::{
+    env, fs,
+    io::{self, IsTerminal, Read},
+    path::{Path, PathBuf},
+    process, thread,
+};
 
-mod lexer;
-mod parser;
+const USAGE: &str = "Usage: mlor [--numeric=int|bigint|rational] [--rational-format=fraction|mixed|decimal:DIGITS] \
+                     [--max-call-depth=N] [--backend=tree|bytecode] [--color=auto|always|never] \
+                     [--emit=ast-json|sexpr|dot] [SCRIPT]
+       mlor fmt [--check] [--width=N] FILE...";
+
+/// Every nested call recurses through a handful of `evaluate` frames, so the
+/// interpreter runs on a thread with a stack large enough for call depths
+/// well past the library default.
+const INTERPRETER_STACK_SIZE: usize = 512 * 1024 * 1024;
+
+/// Part of the interpreter stack nested calls can take, the rest is left to
+/// the session running them.
+const EVALUATION_STACK_SIZE: usize = INTERPRETER_STACK_SIZE - 16 * 1024 * 1024;
+
+/// Stack a call of a small function takes in an unoptimized build, deeper
+/// call depth limits than the evaluation stack holds are refused.
+const CALL_STACK_SIZE: usize = 32 * 1024;
+const MAX_CALL_DEPTH: usize = EVALUATION_STACK_SIZE / CALL_STACK_SIZE;
+const DEFAULT_CALL_DEPTH: usize = 1000;
+
+struct Options {
+    context: Context,
+    backend: Backend,
+    style: Style,
+    /// Print the syntax tree of the input in this format instead of running it.
+    emit: Option<Format>,
+    /// Script to run instead of reading statements from stdin.
+    script: Option<PathBuf>,
+}
+
+fn default_context() -> Context {
+    let mut context = Context::default();
+    context.max_call_depth = DEFAULT_CALL_DEPTH;
+    context.stack_size = EVALUATION_STACK_SIZE;
+    context
+}
+
+fn parse_arguments() -> Result<Options, String> {
+    let mut context = default_context();
+    let mut backend = Backend::default();
+    let mut script = None;
+    let mut emit = None;
+    let mut style = match io::stdout().is_terminal() {
+        true => Style::Colored,
+        false => Style::Plain,
+    };
+    for argument in env::args().skip(1) {
+        if let Some(name) = argument.strip_prefix("--numeric=") {
+            context.numeric_mode =
+                NumericMode::from_name(name).ok_or_else(|| format!("Unknown numeric mode: {}.", name))?;
+        } else if let Some(name) = argument.strip_prefix("--rational-format=") {
+            context.rational_format =
+                RationalFormat::from_name(name).ok_or_else(|| format!("Unknown rational format: {}.", name))?;
+        } else if let Some(depth) = argument.strip_prefix("--max-call-depth=") {
+            context.max_call_depth = depth.parse().map_err(|_| format!("Invalid call depth: {}.", depth))?;
+            if context.max_call_depth > MAX_CALL_DEPTH {
+                return Err(format!("Call depth {} does not fit on the stack, the most is {}.", depth, MAX_CALL_DEPTH));
+            }
+        } else if let Some(name) = argument.strip_prefix("--backend=") {
+            backend = Backend::from_name(name).ok_or_else(|| format!("Unknown backend: {}.", name))?;
+        } else if let Some(name) = argument.strip_prefix("--emit=") {
+            emit = Some(Format::from_name(name).ok_or_else(|| format!("Unknown emit format: {}.", name))?);
+        } else if let Some(choice) = argument.strip_prefix("--color=") {
+            style = match choice {
+                "auto" => style,
+                "always" => Style::Colored,
+                "never" => Style::Plain,
+                _ => return Err(format!("Unknown color choice: {}.", choice)),
+            };
+        } else if argument.starts_with('-') || script.is_some() {
+            return Err(format!("Unknown argument: {}.", argument));
+        } else {
+            script = Some(PathBuf::from(argument));
+        }
+    }
+    Ok(Options {
+        context,
+        backend,
+        style,
+        emit,
+        script,
+    })
+}
+
+/// Rewrites the files in the canonical style, with `--check` only lists
+/// the ones that would change. Returns the exit code, nonzero if a file
+/// could not be formatted or would change.
+fn format_files(arguments: &[String]) -> i32 {
+    let mut check = false;
+    let mut width = formatter::DEFAULT_WIDTH;
+    let mut files = Vec::new();
+    for argument in arguments {
+        let error = if argument == "--check" {
+            check = true;
+            continue;
+        } else if let Some(value) = argument.strip_prefix("--width=") {
+            match value.parse() {
+                Ok(value) => {
+                    width = value;
+                    continue;
+                }
+                Err(_) => format!("Invalid width: {}.", value),
+            }
+        } else if argument.starts_with('-') {
+            format!("Unknown argument: {}.", argument)
+        } else {
+            files.push(PathBuf::from(argument));
+            continue;
+        };
+        eprintln!("{}\n{}", error, USAGE);
+        return 2;
+    }
+    if files.is_empty() {
+        eprintln!("No files to format.\n{}", USAGE);
+        return 2;
+    }
+
+    let style = match io::stderr().is_terminal() {
+        true => Style::Colored,
+        false => Style::Plain,
+    };
+    let mut failed = false;
+    for path in files {
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("Could not read {}: {}", path.display(), error);
+                failed = true;
+                continue;
+            }
+        };
+        match formatter::format(&source, width) {
+            Ok(formatted) if formatted == source => (),
+            Ok(_) if check => {
+                println!("{} is not formatted", path.display());
+                failed = true;
+            }
+            Ok(formatted) => {
+                if let Err(error) = fs::write(&path, formatted) {
+                    eprintln!("Could not write {}: {}", path.display(), error);
+                    failed = true;
+                }
+            }
+            Err(errors) => {
+                let mut sources = SourceMap::default();
+                sources.add(path.display().to_string(), source);
+                for error in &errors {
+                    eprintln!("{}", Diagnostic::from(error).render(&sources, style));
+                }
+                failed = true;
+            }
+        }
+    }
+    failed as i32
+}
+
+/// Prints the syntax tree of the script or of the whole standard input,
+/// syntax errors are reported after it. Returns the exit code.
+fn emit_tree(format: Format, script: Option<&Path>, style: Style) -> i32 {
+    let (name, reader): (_, Box<dyn Read>) = match script {
+        Some(script) => match fs::File::open(script) {
+            Ok(file) => (script.display().to_string(), Box::new(file)),
+            Err(error) => {
+                eprintln!("Could not read {}: {}", script.display(), error);
+                return 1;
+            }
+        },
+        None => ("<stdin>".to_string(), Box::new(io::stdin().lock())),
+    };
+
+    // the source is lexed as it is read, a read error ends it with a syntax error
+    let mut sources = SourceMap::default();
+    let mut characters = ReaderSource::new(reader).recording();
+    let lexer = Lexer::from_characters_at(&mut characters, sources.next_start());
+    let (program, errors) = Parser::from_tokens(lexer.into_tokens()).parse_program();
+    sources.add(name, characters.into_text());
+    print!("{}", Tree::from(&program).render(format));
+    for error in &errors {
+        eprintln!("{}", Diagnostic::from(error).render(&sources, style));
+    }
+    !errors.is_empty() as i32
+}
+
+/// Runs the interpreter on a thread with a stack of its own.
+fn spawn_interpreter<T: Send + 'static>(run: impl FnOnce() -> T + Send + 'static) -> thread::JoinHandle<T> {
+    thread::Builder::new().stack_size(INTERPRETER_STACK_SIZE).spawn(run).unwrap()
+}
 
 fn main() {
-    let stdin = io::stdin();
-    for line in stdin.lock().lines() {
-        let lexer = Lexer::from_str(&line.unwrap());
-        let mut parser = parser::Parser::from_tokens(lexer.into_tokens());
-        let node = parser.parse();
-        match node {
-            Ok(exp_node) => println!("Expression evaluated to: {0:?}", exp_node.evaluate()),
-            Err(inv_node) => println!("{}", inv_node.describe()),
+    let arguments = env::args().skip(1).collect::<Vec<_>>();
+    if arguments.first().is_some_and(|command| command == "fmt") {
+        process::exit(format_files(&arguments[1..]));
+    }
+
+    let options = parse_arguments().unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        process::exit(2);
+    });
+    if let Some(format) = options.emit {
+        process::exit(emit_tree(format, options.script.as_deref(), options.style));
+    }
+
+    let interpreter = spawn_interpreter(move || {
+        let engine = Engine::with_context(options.context).with_backend(options.backend);
+        let mut session = Session::new(engine, options.style, io::stdout(), io::stderr());
+        match &options.script {
+            Some(script) => session.load(script),
+            None => {
+                // piped input is neither prompted for nor kept in the history
+                if !io::stdin().is_terminal() {
+                    let mut input = PlainInput::new(io::stdin().lock(), false);
+                    return repl::run(&mut session, &mut input, &mut History::default()).map(|_| true);
+                }
+                let mut history = History::from_environment();
+                match LineEditor::new() {
+                    Ok(mut editor) => repl::run(&mut session, &mut editor, &mut history),
+                    Err(_) => repl::run(&mut session, &mut PlainInput::new(io::stdin().lock(), true), &mut history),
+                }
+                .map(|_| true)
+            }
+        }
+    });
+    match interpreter.join().unwrap() {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
         }
     }
 }
+
+#[test]
+fn default_call_depth_fits_the_stack() {
+    use mlor::{evaluator::error::EvalError, Error};
+
+    let interpreter = spawn_interpreter(|| {
+        let mut engine = Engine::with_context(default_context());
+        let result = engine.eval("fn f(n) = if n == 0 then 0 else 1 + f(n - 1); f(100000)");
+        result.map(|_| ()).map_err(|error| match error {
+            Error::Eval(error) => Some(error),
+            _ => None,
+        })
+    });
+    assert!(matches!(
+        interpreter.join().unwrap(),
+        Err(Some(EvalError::RecursionLimit { limit: DEFAULT_CALL_DEPTH, .. }))
+    ));
+}
@@ -0,0 +1,429 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+/// Limbs are stored in base 10^9, which keeps parsing and printing in
+/// decimal trivial while a product of two limbs still fits in a u64.
+const BASE: u64 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+/// Arbitrary-precision signed integer.
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    // least significant limb first, no trailing zero limbs, empty for zero
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            negative: false,
+            magnitude: self.magnitude.clone(),
+        }
+    }
+
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        trim(&mut magnitude);
+        Self {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    /// Truncating division, the remainder takes the sign of the dividend.
+    /// Returns `None` when dividing by zero.
+    pub fn div_rem(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        if other.is_zero() {
+            return None;
+        }
+
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &other.magnitude);
+        Some((
+            Self::from_parts(self.negative != other.negative, quotient),
+            Self::from_parts(self.negative, remainder),
+        ))
+    }
+
+    /// Floored division, the remainder takes the sign of the divisor.
+    /// Returns `None` when dividing by zero.
+    pub fn div_rem_floor(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        let (quotient, remainder) = self.div_rem(other)?;
+        if !remainder.is_zero() && remainder.is_negative() != other.is_negative() {
+            Some((&quotient - &BigInt::from(1), &remainder + other))
+        } else {
+            Some((quotient, remainder))
+        }
+    }
+
+    /// Exponentiation by squaring.
+    pub fn pow(&self, mut exponent: u32) -> BigInt {
+        let mut base = self.clone();
+        let mut result = BigInt::from(1);
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result = &result * &base;
+            }
+            exponent /= 2;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    /// Base 10 logarithm of the magnitude, one less than the number of
+    /// decimal digits up to a fraction. Negative infinity for zero.
+    pub fn log10(&self) -> f64 {
+        match self.magnitude.last() {
+            Some(&top) => f64::from(top).log10() + ((self.magnitude.len() - 1) * BASE_DIGITS) as f64,
+            None => f64::NEG_INFINITY,
+        }
+    }
+
+    pub fn to_i32(&self) -> Option<i32> {
+        self.to_i64().and_then(|value| i32::try_from(value).ok())
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 3 {
+            return None;
+        }
+
+        let magnitude = self.magnitude.iter().rev().fold(0i128, |acc, &limb| acc * BASE as i128 + limb as i128);
+        let value = if self.negative { -magnitude } else { magnitude };
+        i64::try_from(value).ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.magnitude.iter().rev().fold(0f64, |acc, &limb| acc * BASE as f64 + limb as f64);
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let negative = value < 0;
+        let mut rest = value.unsigned_abs();
+        let mut magnitude = Vec::new();
+        while rest > 0 {
+            magnitude.push((rest % BASE) as u32);
+            rest /= BASE;
+        }
+        Self::from_parts(negative, magnitude)
+    }
+}
+
+impl From<i32> for BigInt {
+    fn from(value: i32) -> Self {
+        Self::from(i64::from(value))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseBigIntError;
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    /// Parses an optionally negative string of decimal digits.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match string.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, string),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigIntError);
+        }
+
+        let magnitude = digits
+            .as_bytes()
+            .rchunks(BASE_DIGITS)
+            .map(|chunk| chunk.iter().fold(0u32, |acc, b| acc * 10 + (b - b'0') as u32))
+            .collect();
+        Ok(Self::from_parts(negative, magnitude))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut limbs = self.magnitude.iter().rev();
+        match limbs.next() {
+            None => write!(f, "0"),
+            Some(top) => {
+                if self.negative {
+                    write!(f, "-")?;
+                }
+                write!(f, "{}", top)?;
+                for limb in limbs {
+                    write!(f, "{:09}", limb)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BigInt({})", self)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        -&self
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_magnitude(&self.magnitude, &other.magnitude));
+        }
+
+        match cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Equal => BigInt::zero(),
+            Ordering::Greater => BigInt::from_parts(self.negative, sub_magnitude(&self.magnitude, &other.magnitude)),
+            Ordering::Less => BigInt::from_parts(other.negative, sub_magnitude(&other.magnitude, &self.magnitude)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &(-other)
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(self.negative != other.negative, mul_magnitude(&self.magnitude, &other.magnitude))
+    }
+}
+
+fn trim(magnitude: &mut Vec<u32>) {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+}
+
+fn cmp_magnitude(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(left.len().max(right.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..left.len().max(right.len()) {
+        let sum = *left.get(i).unwrap_or(&0) as u64 + *right.get(i).unwrap_or(&0) as u64 + carry;
+        result.push((sum % BASE) as u32);
+        carry = sum / BASE;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+/// Subtracts magnitudes, `left` has to be at least as large as `right`.
+fn sub_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(left.len());
+    let mut borrow = 0i64;
+    for (i, &limb) in left.iter().enumerate() {
+        let mut difference = limb as i64 - *right.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += BASE as i64;
+            borrow = 1;
+        }
+        result.push(difference as u32);
+    }
+    trim(&mut result);
+    result
+}
+
+fn mul_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    if left.is_empty() || right.is_empty() {
+        return Vec::new();
+    }
+
+    let mut result = vec![0u64; left.len() + right.len()];
+    for (i, &l) in left.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &r) in right.iter().enumerate() {
+            let current = result[i + j] + l as u64 * r as u64 + carry;
+            result[i + j] = current % BASE;
+            carry = current / BASE;
+        }
+        result[i + right.len()] += carry;
+    }
+
+    let mut result = result.into_iter().map(|limb| limb as u32).collect();
+    trim(&mut result);
+    result
+}
+
+/// Schoolbook long division, every quotient limb is found by a binary search.
+fn div_rem_magnitude(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder = Vec::<u32>::new();
+
+    for (i, &limb) in dividend.iter().enumerate().rev() {
+        remainder.insert(0, limb);
+        trim(&mut remainder);
+
+        let (mut low, mut high) = (0u32, (BASE - 1) as u32);
+        while low < high {
+            let middle = low + (high - low).div_ceil(2);
+            if cmp_magnitude(&mul_magnitude(divisor, &[middle]), &remainder) == Ordering::Greater {
+                high = middle - 1;
+            } else {
+                low = middle;
+            }
+        }
+
+        if low > 0 {
+            remainder = sub_magnitude(&remainder, &mul_magnitude(divisor, &[low]));
+        }
+        quotient[i] = low;
+    }
+
+    trim(&mut quotient);
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+fn big(string: &str) -> BigInt {
+    string.parse().unwrap()
+}
+
+#[test]
+fn parse_and_display() {
+    assert_eq!(big("0").to_string(), "0");
+    assert_eq!(big("-0").to_string(), "0");
+    assert_eq!(big("000123").to_string(), "123");
+    assert_eq!(big("9000000000").to_string(), "9000000000");
+    assert_eq!(big("-1000000000000000000000").to_string(), "-1000000000000000000000");
+    assert_eq!("12a".parse::<BigInt>(), Err(ParseBigIntError));
+    assert_eq!("".parse::<BigInt>(), Err(ParseBigIntError));
+}
+
+#[test]
+fn addition_and_subtraction() {
+    assert_eq!(&big("999999999") + &big("1"), big("1000000000"));
+    assert_eq!(&big("-5") + &big("3"), big("-2"));
+    assert_eq!(&big("5") + &big("-5"), BigInt::zero());
+    assert_eq!(&big("1000000000000000000") - &big("1"), big("999999999999999999"));
+    assert_eq!(&big("1") - &big("1000000000000000000"), big("-999999999999999999"));
+}
+
+#[test]
+fn multiplication() {
+    assert_eq!(&big("123456789123456789") * &big("987654321987654321"), big("121932631356500531347203169112635269"));
+    assert_eq!(&big("-2") * &big("3"), big("-6"));
+    assert_eq!(&big("-2") * &big("0"), BigInt::zero());
+}
+
+#[test]
+fn division() {
+    let (quotient, remainder) = big("121932631356500531347203169112635270").div_rem(&big("987654321987654321")).unwrap();
+    assert_eq!(quotient, big("123456789123456789"));
+    assert_eq!(remainder, big("1"));
+
+    let (quotient, remainder) = big("-7").div_rem(&big("2")).unwrap();
+    assert_eq!(quotient, big("-3"));
+    assert_eq!(remainder, big("-1"));
+
+    assert_eq!(big("7").div_rem(&BigInt::zero()), None);
+
+    let (quotient, remainder) = big("-7").div_rem_floor(&big("2")).unwrap();
+    assert_eq!(quotient, big("-4"));
+    assert_eq!(remainder, big("1"));
+
+    let (quotient, remainder) = big("7").div_rem_floor(&big("-2")).unwrap();
+    assert_eq!(quotient, big("-4"));
+    assert_eq!(remainder, big("-1"));
+
+    let (quotient, remainder) = big("-6").div_rem_floor(&big("3")).unwrap();
+    assert_eq!(quotient, big("-2"));
+    assert_eq!(remainder, BigInt::zero());
+}
+
+#[test]
+fn power() {
+    assert_eq!(big("2").pow(100), big("1267650600228229401496703205376"));
+    assert_eq!(big("-3").pow(3), big("-27"));
+    assert_eq!(big("0").pow(0), big("1"));
+    assert_eq!(big("7").pow(1), big("7"));
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(big("2147483647").to_i32(), Some(i32::MAX));
+    assert_eq!(big("-2147483648").to_i32(), Some(i32::MIN));
+    assert_eq!(big("2147483648").to_i32(), None);
+    assert_eq!(big("-9223372036854775808").to_i64(), Some(i64::MIN));
+    assert_eq!(BigInt::from(i64::MIN), big("-9223372036854775808"));
+    assert_eq!(big("-1500000000").to_f64(), -1.5e9);
+    assert_eq!(big("-1000").log10(), 3.0);
+    assert_eq!(big("1000000000000000000000").log10(), 21.0);
+    assert_eq!(big("0").log10(), f64::NEG_INFINITY);
+    assert!(big("-3") < big("2"));
+    assert!(big("-3") < big("-2"));
+    assert!(big("1000000000") > big("999999999"));
+}
@@ -0,0 +1,228 @@
+use std::{
+    fmt,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use super::bigint::BigInt;
+
+/// Exact fraction, always kept in lowest terms with a positive denominator.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Rational {
+    numerator: BigInt,
+    denominator: BigInt,
+}
+
+fn gcd(left: &BigInt, right: &BigInt) -> BigInt {
+    let (mut a, mut b) = (left.abs(), right.abs());
+    while !b.is_zero() {
+        let (_, remainder) = a.div_rem(&b).unwrap();
+        a = b;
+        b = remainder;
+    }
+    a
+}
+
+impl Rational {
+    /// Builds a normalized fraction, `None` when the denominator is zero.
+    pub fn new(numerator: BigInt, denominator: BigInt) -> Option<Self> {
+        if denominator.is_zero() {
+            return None;
+        }
+
+        let divisor = gcd(&numerator, &denominator);
+        let divisor = if denominator.is_negative() { -divisor } else { divisor };
+        let (numerator, _) = numerator.div_rem(&divisor).unwrap();
+        let (denominator, _) = denominator.div_rem(&divisor).unwrap();
+        Some(Self { numerator, denominator })
+    }
+
+    pub fn numerator(&self) -> &BigInt {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &BigInt {
+        &self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator.is_zero()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == BigInt::from(1)
+    }
+
+    /// Returns `None` when dividing by zero.
+    pub fn checked_div(&self, other: &Rational) -> Option<Rational> {
+        Self::new(&self.numerator * &other.denominator, &self.denominator * &other.numerator)
+    }
+
+    /// Largest integer not greater than the fraction.
+    pub fn floor(&self) -> BigInt {
+        self.numerator.div_rem_floor(&self.denominator).unwrap().0
+    }
+
+    /// Raises the fraction to an integer power, `None` when raising zero
+    /// to a negative power.
+    pub fn pow(&self, exponent: i32) -> Option<Rational> {
+        let magnitude = exponent.unsigned_abs();
+        let (numerator, denominator) = (self.numerator.pow(magnitude), self.denominator.pow(magnitude));
+        if exponent < 0 {
+            Self::new(denominator, numerator)
+        } else {
+            Some(Self { numerator, denominator })
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator.to_f64() / self.denominator.to_f64()
+    }
+
+    /// Formats the fraction as a mixed number, e.g. `3 1/2` for `7/2`.
+    pub fn to_mixed_string(&self) -> String {
+        let (whole, remainder) = self.numerator.div_rem(&self.denominator).unwrap();
+        if remainder.is_zero() {
+            whole.to_string()
+        } else if whole.is_zero() {
+            self.to_string()
+        } else {
+            format!("{} {}/{}", whole, remainder.abs(), self.denominator)
+        }
+    }
+
+    /// Formats the fraction as a decimal with exactly `digits` digits after
+    /// the point, rounding half away from zero.
+    pub fn to_decimal_string(&self, digits: usize) -> String {
+        let scale = (0..digits).fold(BigInt::from(1), |scale, _| &scale * &BigInt::from(10));
+        let (mut scaled, remainder) = (&self.numerator.abs() * &scale).div_rem(&self.denominator).unwrap();
+        if &remainder * &BigInt::from(2) >= self.denominator {
+            scaled = &scaled + &BigInt::from(1);
+        }
+
+        let mut digits_string = format!("{:0>width$}", scaled.to_string(), width = digits + 1);
+        if digits > 0 {
+            digits_string.insert(digits_string.len() - digits, '.');
+        }
+        if self.numerator.is_negative() && !scaled.is_zero() {
+            digits_string.insert(0, '-');
+        }
+        digits_string
+    }
+}
+
+impl From<BigInt> for Rational {
+    fn from(value: BigInt) -> Self {
+        Self {
+            numerator: value,
+            denominator: BigInt::from(1),
+        }
+    }
+}
+
+impl From<i32> for Rational {
+    fn from(value: i32) -> Self {
+        Self::from(BigInt::from(value))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+impl Neg for &Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational {
+            numerator: -&self.numerator,
+            denominator: self.denominator.clone(),
+        }
+    }
+}
+
+impl Add for &Rational {
+    type Output = Rational;
+
+    fn add(self, other: &Rational) -> Rational {
+        Rational::new(
+            &(&self.numerator * &other.denominator) + &(&other.numerator * &self.denominator),
+            &self.denominator * &other.denominator,
+        )
+        .unwrap()
+    }
+}
+
+impl Sub for &Rational {
+    type Output = Rational;
+
+    fn sub(self, other: &Rational) -> Rational {
+        self + &(-other)
+    }
+}
+
+impl Mul for &Rational {
+    type Output = Rational;
+
+    fn mul(self, other: &Rational) -> Rational {
+        Rational::new(&self.numerator * &other.numerator, &self.denominator * &other.denominator).unwrap()
+    }
+}
+
+#[cfg(test)]
+fn ratio(numerator: i32, denominator: i32) -> Rational {
+    Rational::new(BigInt::from(numerator), BigInt::from(denominator)).unwrap()
+}
+
+#[test]
+fn normalization() {
+    assert_eq!(ratio(14, 4).to_string(), "7/2");
+    assert_eq!(ratio(3, -6).to_string(), "-1/2");
+    assert_eq!(ratio(-4, -2).to_string(), "2");
+    assert_eq!(ratio(0, -5).to_string(), "0");
+    assert_eq!(Rational::new(BigInt::from(1), BigInt::zero()), None);
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(&ratio(1, 2) + &ratio(1, 3), ratio(5, 6));
+    assert_eq!(&ratio(1, 2) - &ratio(3, 4), ratio(-1, 4));
+    assert_eq!(&ratio(2, 3) * &ratio(9, 4), ratio(3, 2));
+    assert_eq!(ratio(7, 1).checked_div(&ratio(2, 1)), Some(ratio(7, 2)));
+    assert_eq!(ratio(7, 1).checked_div(&ratio(0, 1)), None);
+}
+
+#[test]
+fn floor_and_power() {
+    assert_eq!(ratio(7, 2).floor(), BigInt::from(3));
+    assert_eq!(ratio(-7, 2).floor(), BigInt::from(-4));
+    assert_eq!(ratio(4, 1).floor(), BigInt::from(4));
+    assert_eq!(ratio(2, 3).pow(3), Some(ratio(8, 27)));
+    assert_eq!(ratio(-2, 3).pow(-3), Some(ratio(-27, 8)));
+    assert_eq!(ratio(5, 7).pow(0), Some(ratio(1, 1)));
+    assert_eq!(ratio(0, 1).pow(-1), None);
+}
+
+#[test]
+fn mixed_string() {
+    assert_eq!(ratio(7, 2).to_mixed_string(), "3 1/2");
+    assert_eq!(ratio(-7, 2).to_mixed_string(), "-3 1/2");
+    assert_eq!(ratio(1, 2).to_mixed_string(), "1/2");
+    assert_eq!(ratio(8, 2).to_mixed_string(), "4");
+}
+
+#[test]
+fn decimal_string() {
+    assert_eq!(ratio(7, 2).to_decimal_string(3), "3.500");
+    assert_eq!(ratio(1, 3).to_decimal_string(5), "0.33333");
+    assert_eq!(ratio(2, 3).to_decimal_string(2), "0.67");
+    assert_eq!(ratio(-1, 3).to_decimal_string(2), "-0.33");
+    assert_eq!(ratio(-1, 1000).to_decimal_string(2), "0.00");
+    assert_eq!(ratio(7, 2).to_decimal_string(0), "4");
+    assert_eq!(ratio(1, 8).to_decimal_string(1), "0.1");
+}
@@ -0,0 +1,688 @@
+//! Simplifies syntax trees of statements that passed the type checker. The
+//! simplified tree evaluates to the same value as the original one, or
+//! fails with the same error.
+
+use std::rc::Rc;
+
+use crate::{
+    evaluator::{
+        context::{Context, NumericMode},
+        environment::Environment,
+        error::EvalError,
+        value::Value,
+    },
+    lexer::token::Span,
+    numeric::bigint::BigInt,
+    parser::syntax_tree::{ExpressionNode, FactorNode, StatementNode, TermNode},
+    typechecker::{environment::TypeEnvironment, types::Type},
+};
+
+/// Folds constant subexpressions, drops parentheses the tree does not need,
+/// collapses double negation and applies identities like `x * 1 = x`.
+///
+/// Identities that would turn a float into an int, such as `x * 0 = 0`, are
+/// only applied when `x` is known to be an int: a literal, or a variable the
+/// type environment binds to `int`.
+pub struct Optimizer<'a> {
+    context: &'a Context,
+    types: &'a TypeEnvironment,
+    // names bound by the optimized code itself, their types are not known
+    locals: Vec<String>,
+}
+
+impl<'a> Optimizer<'a> {
+    pub fn new(context: &'a Context, types: &'a TypeEnvironment) -> Self {
+        Self {
+            context,
+            types,
+            locals: Vec::new(),
+        }
+    }
+
+    /// Later statements see the bindings of earlier ones, so statements
+    /// of a program have to be optimized in order.
+    pub fn statement(&mut self, statement: StatementNode) -> StatementNode {
+        match statement {
+            StatementNode::LetNode { name, name_span, value } => {
+                let value = self.expression(value);
+                self.locals.push(name.clone());
+                StatementNode::LetNode { name, name_span, value }
+            }
+            StatementNode::FunctionNode {
+                name,
+                name_span,
+                parameters,
+                body,
+            } => {
+                self.locals.push(name.clone());
+                let body = self.scoped(&parameters, body);
+                StatementNode::FunctionNode {
+                    name,
+                    name_span,
+                    parameters,
+                    body,
+                }
+            }
+            StatementNode::ExpressionStatementNode(exp) => StatementNode::ExpressionStatementNode(self.expression(exp)),
+            statement @ StatementNode::ErrorNode { .. } => statement,
+        }
+    }
+
+    pub fn expression(&mut self, expression: ExpressionNode) -> ExpressionNode {
+        let expression = match expression {
+            ExpressionNode::SingleTermNode(term) => match self.term(term) {
+                // parentheses around a whole expression
+                TermNode::SingleFactorNode(FactorNode::ExpressionNode { expression, .. }) => return *expression,
+                term => ExpressionNode::SingleTermNode(term),
+            },
+            ExpressionNode::OrNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                let span = left.span().to(right.span());
+                match (self.expression(*left), self.expression(*right)) {
+                    (left, _) if constant_bool(&left) == Some(true) => return boolean(true, span),
+                    (left, right) if constant_bool(&left) == Some(false) => return right,
+                    (left, right) => ExpressionNode::OrNode {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                        operator_span,
+                    },
+                }
+            }
+            ExpressionNode::AndNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                let span = left.span().to(right.span());
+                match (self.expression(*left), self.expression(*right)) {
+                    (left, _) if constant_bool(&left) == Some(false) => return boolean(false, span),
+                    (left, right) if constant_bool(&left) == Some(true) => return right,
+                    (left, right) => ExpressionNode::AndNode {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                        operator_span,
+                    },
+                }
+            }
+            ExpressionNode::ComparisonNode {
+                operator,
+                left,
+                right,
+                operator_span,
+            } => ExpressionNode::ComparisonNode {
+                operator,
+                left: Box::new(self.expression(*left)),
+                right: Box::new(self.expression(*right)),
+                operator_span,
+            },
+            ExpressionNode::AdditionTermNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                let (left, right) = (self.expression(*left), self.term(right));
+                if is_int_literal(&right, 0) && self.is_int_expression(&left) {
+                    return left;
+                }
+                if is_int_literal(&left, 0) && self.is_int_term(&right) {
+                    return right.into();
+                }
+                ExpressionNode::AdditionTermNode {
+                    left: Box::new(left),
+                    right,
+                    operator_span,
+                }
+            }
+            ExpressionNode::SubstractionTermNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                let (left, right) = (self.expression(*left), self.term(right));
+                // unlike `x + 0`, this keeps the sign of a float zero
+                if is_int_literal(&right, 0) {
+                    return left;
+                }
+                ExpressionNode::SubstractionTermNode {
+                    left: Box::new(left),
+                    right,
+                    operator_span,
+                }
+            }
+        };
+        match self.fold(&expression, expression.span()) {
+            Some(literal) => literal.into(),
+            None => expression,
+        }
+    }
+
+    fn term(&mut self, term: TermNode) -> TermNode {
+        let term = match term {
+            TermNode::SingleFactorNode(factor) => match self.factor(factor) {
+                FactorNode::ExpressionNode { expression, span } => match *expression {
+                    ExpressionNode::SingleTermNode(term) => return term,
+                    expression => {
+                        return TermNode::SingleFactorNode(FactorNode::ExpressionNode {
+                            expression: Box::new(expression),
+                            span,
+                        })
+                    }
+                },
+                factor => return TermNode::SingleFactorNode(factor),
+            },
+            TermNode::MultiplicationFactorNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                let span = left.span().to(right.span());
+                let (left, right) = (self.term(*left), self.factor(right));
+                if is_int_literal(&right, 1) {
+                    return left;
+                }
+                if is_int_literal(&left, 1) {
+                    return TermNode::SingleFactorNode(right);
+                }
+                // `x * 0` drops `x`, so it must not be able to fail
+                if (is_int_literal(&right, 0) && self.is_int_term(&left) && is_pure_term(&left))
+                    || (is_int_literal(&left, 0) && self.is_int_factor(&right) && is_pure(&right))
+                {
+                    return TermNode::SingleFactorNode(integer(BigInt::zero(), span));
+                }
+                TermNode::MultiplicationFactorNode {
+                    left: Box::new(left),
+                    right,
+                    operator_span,
+                }
+            }
+            TermNode::DivisionFactorNode {
+                left,
+                right,
+                operator_span,
+            } => TermNode::DivisionFactorNode {
+                left: Box::new(self.term(*left)),
+                right: self.factor(right),
+                operator_span,
+            },
+            TermNode::IntegerDivisionFactorNode {
+                left,
+                right,
+                operator_span,
+            } => TermNode::IntegerDivisionFactorNode {
+                left: Box::new(self.term(*left)),
+                right: self.factor(right),
+                operator_span,
+            },
+            TermNode::ModuloFactorNode {
+                left,
+                right,
+                operator_span,
+            } => TermNode::ModuloFactorNode {
+                left: Box::new(self.term(*left)),
+                right: self.factor(right),
+                operator_span,
+            },
+        };
+        match self.fold(&term, term.span()) {
+            Some(literal) => TermNode::SingleFactorNode(literal),
+            None => term,
+        }
+    }
+
+    fn factor(&mut self, factor: FactorNode) -> FactorNode {
+        let factor = match factor {
+            FactorNode::LambdaNode {
+                parameter,
+                fun_span,
+                parameter_span,
+                body,
+            } => {
+                let body = self.scoped(std::slice::from_ref(&parameter), body);
+                return FactorNode::LambdaNode {
+                    parameter,
+                    fun_span,
+                    parameter_span,
+                    body,
+                };
+            }
+            FactorNode::IfNode {
+                condition,
+                consequence,
+                alternative,
+                if_span,
+                else_span,
+                widen,
+            } => {
+                let condition = self.expression(*condition);
+                // a widened branch stays in the conditional, which converts it to a float
+                match constant_bool(&condition).filter(|_| !widen.get()) {
+                    Some(true) => return self.expression(*consequence).into_factor(),
+                    Some(false) => return self.expression(*alternative).into_factor(),
+                    None => FactorNode::IfNode {
+                        condition: Box::new(condition),
+                        consequence: Box::new(self.expression(*consequence)),
+                        alternative: Box::new(self.expression(*alternative)),
+                        if_span,
+                        else_span,
+                        widen,
+                    },
+                }
+            }
+            FactorNode::CallNode {
+                callee,
+                arguments,
+                arguments_span,
+            } => FactorNode::CallNode {
+                callee: Box::new(self.factor(*callee)),
+                arguments: arguments.into_iter().map(|argument| self.expression(argument)).collect(),
+                arguments_span,
+            },
+            FactorNode::ExpressionNode { expression, span } => match self.expression(*expression) {
+                ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(factor)) => return factor,
+                expression => FactorNode::ExpressionNode {
+                    expression: Box::new(expression),
+                    span,
+                },
+            },
+            FactorNode::PowerNode {
+                base,
+                exponent,
+                operator_span,
+            } => FactorNode::PowerNode {
+                base: Box::new(self.factor(*base)),
+                exponent: Box::new(self.factor(*exponent)),
+                operator_span,
+            },
+            FactorNode::NegativeExpressionNode { operand, operator_span } => match self.factor(*operand) {
+                // `--x` overflows for the smallest machine integer
+                FactorNode::NegativeExpressionNode { operand, .. } if self.context.numeric_mode != NumericMode::Machine => {
+                    return *operand
+                }
+                operand => FactorNode::NegativeExpressionNode {
+                    operand: Box::new(operand),
+                    operator_span,
+                },
+            },
+            FactorNode::NotExpressionNode { operand, operator_span } => FactorNode::NotExpressionNode {
+                operand: Box::new(self.factor(*operand)),
+                operator_span,
+            },
+            factor @ (FactorNode::LiteralNode { .. }
+            | FactorNode::FloatLiteralNode { .. }
+            | FactorNode::BoolLiteralNode { .. }
+            | FactorNode::VariableNode { .. }
+            | FactorNode::ErrorNode { .. }) => return factor,
+        };
+        // a negative literal is already as simple as it gets
+        if is_literal(&factor) {
+            return factor;
+        }
+        self.fold(&factor, factor.span()).unwrap_or(factor)
+    }
+
+    /// Optimizes a function body, the parameters hide bindings of the same name.
+    fn scoped(&mut self, parameters: &[String], body: Rc<ExpressionNode>) -> Rc<ExpressionNode> {
+        let depth = self.locals.len();
+        self.locals.extend(parameters.iter().cloned());
+        let body = self.expression(Rc::unwrap_or_clone(body));
+        self.locals.truncate(depth);
+        Rc::new(body)
+    }
+
+    /// A literal with the value of the node, if all of its operands are
+    /// literals and it evaluates without an error. Errors are left to be
+    /// reported when the code runs.
+    fn fold(&self, node: &impl Foldable, span: Span) -> Option<FactorNode> {
+        if !node.has_literal_operands() {
+            return None;
+        }
+        let value = node.evaluate_constant(self.context).ok()?;
+        let literal = literal(&value, span)?;
+        // the literal has to mean the same value in this numeric mode
+        match literal.evaluate(self.context, &Environment::default()) {
+            Ok(folded) if folded == value => Some(literal),
+            _ => None,
+        }
+    }
+
+    fn is_int_expression(&self, expression: &ExpressionNode) -> bool {
+        match expression {
+            ExpressionNode::SingleTermNode(term) => self.is_int_term(term),
+            ExpressionNode::AdditionTermNode { left, right, .. } | ExpressionNode::SubstractionTermNode { left, right, .. } => {
+                self.is_int_expression(left) && self.is_int_term(right)
+            }
+            ExpressionNode::OrNode { .. } | ExpressionNode::AndNode { .. } | ExpressionNode::ComparisonNode { .. } => false,
+        }
+    }
+
+    fn is_int_term(&self, term: &TermNode) -> bool {
+        match term {
+            TermNode::SingleFactorNode(factor) => self.is_int_factor(factor),
+            TermNode::MultiplicationFactorNode { left, right, .. }
+            | TermNode::DivisionFactorNode { left, right, .. }
+            | TermNode::IntegerDivisionFactorNode { left, right, .. }
+            | TermNode::ModuloFactorNode { left, right, .. } => self.is_int_term(left) && self.is_int_factor(right),
+        }
+    }
+
+    fn is_int_factor(&self, factor: &FactorNode) -> bool {
+        match factor {
+            FactorNode::LiteralNode { .. } => true,
+            FactorNode::VariableNode { name, .. } => {
+                !self.locals.contains(name)
+                    && self
+                        .types
+                        .lookup(name)
+                        .is_some_and(|scheme| scheme.body == Type::integer(self.context.numeric_mode))
+            }
+            FactorNode::ExpressionNode { expression, .. } => self.is_int_expression(expression),
+            FactorNode::NegativeExpressionNode { operand, .. } => self.is_int_factor(operand),
+            FactorNode::PowerNode { base, exponent, .. } => self.is_int_factor(base) && self.is_int_factor(exponent),
+            FactorNode::IfNode {
+                consequence,
+                alternative,
+                ..
+            } => self.is_int_expression(consequence) && self.is_int_expression(alternative),
+            _ => false,
+        }
+    }
+}
+
+/// Nodes that constant folding applies to.
+trait Foldable {
+    fn has_literal_operands(&self) -> bool;
+    fn evaluate_constant(&self, context: &Context) -> Result<Value, EvalError>;
+}
+
+impl Foldable for ExpressionNode {
+    fn has_literal_operands(&self) -> bool {
+        match self {
+            Self::SingleTermNode(_) => false,
+            Self::OrNode { left, right, .. }
+            | Self::AndNode { left, right, .. }
+            | Self::ComparisonNode { left, right, .. } => as_literal(left).is_some() && as_literal(right).is_some(),
+            Self::AdditionTermNode { left, right, .. } | Self::SubstractionTermNode { left, right, .. } => {
+                as_literal(left).is_some() && matches!(right, TermNode::SingleFactorNode(factor) if is_literal(factor))
+            }
+        }
+    }
+
+    fn evaluate_constant(&self, context: &Context) -> Result<Value, EvalError> {
+        self.evaluate(context, &Environment::default())
+    }
+}
+
+impl Foldable for TermNode {
+    fn has_literal_operands(&self) -> bool {
+        match self {
+            Self::SingleFactorNode(_) => false,
+            Self::MultiplicationFactorNode { left, right, .. }
+            | Self::DivisionFactorNode { left, right, .. }
+            | Self::IntegerDivisionFactorNode { left, right, .. }
+            | Self::ModuloFactorNode { left, right, .. } => {
+                matches!(left.as_ref(), TermNode::SingleFactorNode(factor) if is_literal(factor)) && is_literal(right)
+            }
+        }
+    }
+
+    fn evaluate_constant(&self, context: &Context) -> Result<Value, EvalError> {
+        self.evaluate(context, &Environment::default())
+    }
+}
+
+impl Foldable for FactorNode {
+    fn has_literal_operands(&self) -> bool {
+        match self {
+            Self::PowerNode { base, exponent, .. } => is_literal(base) && is_literal(exponent),
+            Self::NegativeExpressionNode { operand, .. } | Self::NotExpressionNode { operand, .. } => is_literal(operand),
+            _ => false,
+        }
+    }
+
+    fn evaluate_constant(&self, context: &Context) -> Result<Value, EvalError> {
+        self.evaluate(context, &Environment::default())
+    }
+}
+
+/// A literal, possibly negated: the shape folded numbers take.
+fn is_literal(factor: &FactorNode) -> bool {
+    match factor {
+        FactorNode::LiteralNode { .. } | FactorNode::FloatLiteralNode { .. } | FactorNode::BoolLiteralNode { .. } => true,
+        FactorNode::NegativeExpressionNode { operand, .. } => {
+            matches!(**operand, FactorNode::LiteralNode { .. } | FactorNode::FloatLiteralNode { .. })
+        }
+        _ => false,
+    }
+}
+
+fn as_literal(expression: &ExpressionNode) -> Option<&FactorNode> {
+    match expression {
+        ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(factor)) if is_literal(factor) => Some(factor),
+        _ => None,
+    }
+}
+
+fn constant_bool(expression: &ExpressionNode) -> Option<bool> {
+    match as_literal(expression)? {
+        FactorNode::BoolLiteralNode { value, .. } => Some(*value),
+        _ => None,
+    }
+}
+
+/// Whether the node is the integer literal `value`.
+fn is_int_literal(node: &impl IntoFactor, value: i32) -> bool {
+    matches!(node.as_factor(), Some(FactorNode::LiteralNode { value: literal, .. }) if *literal == BigInt::from(value))
+}
+
+/// Evaluating the node can not fail, so dropping it does not hide an error.
+fn is_pure(factor: &FactorNode) -> bool {
+    matches!(factor, FactorNode::VariableNode { .. }) || is_literal(factor)
+}
+
+fn is_pure_term(term: &TermNode) -> bool {
+    matches!(term, TermNode::SingleFactorNode(factor) if is_pure(factor))
+}
+
+/// Nodes that can be a single factor.
+trait IntoFactor {
+    fn as_factor(&self) -> Option<&FactorNode>;
+}
+
+impl IntoFactor for ExpressionNode {
+    fn as_factor(&self) -> Option<&FactorNode> {
+        match self {
+            ExpressionNode::SingleTermNode(term) => term.as_factor(),
+            _ => None,
+        }
+    }
+}
+
+impl IntoFactor for TermNode {
+    fn as_factor(&self) -> Option<&FactorNode> {
+        match self {
+            TermNode::SingleFactorNode(factor) => Some(factor),
+            _ => None,
+        }
+    }
+}
+
+impl IntoFactor for FactorNode {
+    fn as_factor(&self) -> Option<&FactorNode> {
+        Some(self)
+    }
+}
+
+/// The value as a literal, negated literals stand for negative numbers.
+fn literal(value: &Value, span: Span) -> Option<FactorNode> {
+    match value {
+        Value::Int(value) => Some(integer(BigInt::from(*value), span)),
+        Value::BigInt(value) => Some(integer(value.clone(), span)),
+        Value::Rational(value) if value.is_integer() => Some(integer(value.numerator().clone(), span)),
+        Value::Float(value) if value.is_finite() => {
+            let literal = FactorNode::FloatLiteralNode {
+                value: value.abs(),
+                span,
+            };
+            Some(negated_if(value.is_sign_negative(), literal, span))
+        }
+        Value::Bool(value) => Some(FactorNode::BoolLiteralNode { value: *value, span }),
+        Value::Rational(_) | Value::Float(_) | Value::Function(_) => None,
+    }
+}
+
+fn integer(value: BigInt, span: Span) -> FactorNode {
+    negated_if(value.is_negative(), FactorNode::LiteralNode { value: value.abs(), span }, span)
+}
+
+fn negated_if(negative: bool, factor: FactorNode, span: Span) -> FactorNode {
+    match negative {
+        true => FactorNode::NegativeExpressionNode {
+            operand: Box::new(factor),
+            operator_span: span,
+        },
+        false => factor,
+    }
+}
+
+fn boolean(value: bool, span: Span) -> ExpressionNode {
+    FactorNode::BoolLiteralNode { value, span }.into()
+}
+
+#[cfg(test)]
+fn statements(source: &str) -> Vec<StatementNode> {
+    crate::parser::parse_valid_program(source).statements
+}
+
+/// Bytecode of the optimized source next to the bytecode of the expected
+/// one, it leaves spans out of the comparison.
+#[cfg(test)]
+fn assert_optimizes_to(source: &str, expected: &str, mode: NumericMode) {
+    use crate::{typechecker::types::TypeScheme, vm::compile};
+
+    let context = Context {
+        numeric_mode: mode,
+        ..Context::default()
+    };
+    let types = [("n", Type::Int), ("f", Type::Float), ("b", Type::Bool)]
+        .into_iter()
+        .fold(TypeEnvironment::default(), |types, (name, ty)| {
+            types.extend(name.to_string(), TypeScheme::monomorphic(ty))
+        });
+    let mut optimizer = Optimizer::new(&context, &types);
+    let bytecode = |statements: Vec<StatementNode>| match statements.into_iter().last() {
+        Some(StatementNode::ExpressionStatementNode(expression)) => compile(&expression, &context).to_string(),
+        statement => panic!("{:?} is not an expression", statement),
+    };
+    let optimized = statements(source).into_iter().map(|statement| optimizer.statement(statement)).collect();
+    assert_eq!(bytecode(optimized), bytecode(statements(expected)), "{} in {:?}", source, mode);
+}
+
+#[test]
+fn fold_constants() {
+    for (source, expected) in [
+        ("1 + 2 * 3", "7"),
+        ("2 ** 10 - 24 // 5", "1020"),
+        ("-(3 - 5)", "2"),
+        ("3 - 5", "-2"),
+        ("0.5 * -4", "-2.0"),
+        ("n + (2 * 3 - 1)", "n + 5"),
+        ("1 < 2 && b", "b"),
+        ("1 > 2 && b", "false"),
+        ("!(1 == 1) || b", "b"),
+        ("if 1 > 2 then n else 2 * 3", "6"),
+        // failing subexpressions are left for the evaluator to report
+        ("1 / 0 + 1", "1 / 0 + 1"),
+        ("2147483647 + 1", "2147483647 + 1"),
+        ("1.0 / 0.0", "1.0 / 0.0"),
+    ] {
+        assert_optimizes_to(source, expected, NumericMode::Machine);
+    }
+    assert_optimizes_to("2147483647 + 1", "2147483648", NumericMode::BigInt);
+    assert_optimizes_to("6 / 3 + 1 / 2", "2 + 1 / 2", NumericMode::Rational);
+}
+
+#[test]
+fn apply_identities() {
+    for (source, expected) in [
+        ("n * 1 + 0", "n"),
+        ("1 * f", "f"),
+        ("f - 0", "f"),
+        // -0.0 + 0 is 0.0, and `f * 0` is not an int
+        ("f + 0", "f + 0"),
+        ("f * 0", "f * 0"),
+        ("n * 0 + 0 * n", "0"),
+        ("n * (n * 0)", "0"),
+        ("(n + 1) * 0", "(n + 1) * 0"),
+        // the parameter hides the int `n`
+        ("fun n -> n + 0", "fun n -> n + 0"),
+        ("let n = 1.5; n + 0", "n + 0"),
+    ] {
+        assert_optimizes_to(source, expected, NumericMode::Machine);
+    }
+}
+
+#[test]
+fn collapse_double_negation() {
+    assert_optimizes_to("-(-n)", "n", NumericMode::BigInt);
+    assert_optimizes_to("--f", "f", NumericMode::Rational);
+    // -(-2147483648) overflows
+    assert_optimizes_to("-(-n)", "-(-n)", NumericMode::Machine);
+}
+
+#[test]
+fn remove_parentheses() {
+    let context = Context::default();
+    let types = TypeEnvironment::default();
+    let mut optimizer = Optimizer::new(&context, &types);
+    let optimized = statements("((n)) + (2 * n) * (n - 1)")
+        .into_iter()
+        .map(|statement| optimizer.statement(statement))
+        .collect::<Vec<_>>();
+    let [StatementNode::ExpressionStatementNode(ExpressionNode::AdditionTermNode { left, right, .. })] = &optimized[..] else {
+        panic!("{:?}", optimized);
+    };
+    assert!(matches!(**left, ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(FactorNode::VariableNode { .. }))));
+    let TermNode::MultiplicationFactorNode { left, right, .. } = right else {
+        panic!("{:?}", right);
+    };
+    assert!(matches!(**left, TermNode::MultiplicationFactorNode { .. }));
+    assert!(matches!(right, FactorNode::ExpressionNode { .. }));
+}
+
+#[test]
+fn optimized_code_evaluates_the_same() {
+    let sources = [
+        "let k = 3; fn scale(x) = k * x * 1 + 0; scale(2) + --k",
+        "fn fact(n) = if n <= 1 then 1 else n * fact(n - 1)\nfact(10 - 2 * 1)",
+        "let twice = fun f -> fun x -> f(f(x)); twice(fun x -> x * (3 - 1))(2 ** 3)",
+        "if 2 > 1 && !false then 7 / 2 else 0",
+        "(2147483647 - 1) + 2 * 1",
+        "let x = 1 // 0 * 0; x",
+        "-(-(0 - 2147483647 - 1))",
+        "let z = 0.1 + 0.2; z * 1 - 0 == 0.30000000000000004",
+        "fn g(a, b) = a * 0 + b * 1; g(2, 3) + g(1.5, 2.5) * 2.0",
+    ];
+    for mode in [NumericMode::Machine, NumericMode::BigInt, NumericMode::Rational] {
+        let context = Context {
+            numeric_mode: mode,
+            ..Context::default()
+        };
+        for source in sources {
+            let run = |statements: Vec<StatementNode>| {
+                let mut environment = Environment::default();
+                let mut last = Ok(Value::Int(0));
+                for statement in &statements {
+                    last = statement.execute(&context, &mut environment);
+                }
+                last
+            };
+            let types = TypeEnvironment::default();
+            let mut optimizer = Optimizer::new(&context, &types);
+            let optimized = statements(source).into_iter().map(|statement| optimizer.statement(statement)).collect();
+            assert_eq!(run(optimized), run(statements(source)), "{} in {:?}", source, mode);
+        }
+    }
+}
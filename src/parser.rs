@@ -1,144 +1,1179 @@
-use std::iter::Peekable;
+use std::{cell::Cell, iter::Peekable, rc::Rc};
 
-use crate::lexer::{
-    source::CharactersSource,
-    token::{Token, TokenKind},
-    TokenIterator,
+use crate::{
+    lexer::{
+        source::CharactersSource,
+        token::{Position, Span, Token, TokenKind},
+        TokenIterator,
+    },
+    numeric::bigint::BigInt,
 };
 
-use self::syntax_tree::{ExpressionNode, FactorNode, InvalidExpressionNode, TermNode};
+use self::syntax_tree::{
+    ComparisonOperator, ExpressionNode, FactorNode, InvalidExpressionNode, ProgramNode, StatementNode, TermNode,
+};
+
+pub mod export;
+mod printer;
+pub mod syntax_tree;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Associativity {
+    Left,
+    Right,
+}
 
-mod syntax_tree;
+/// Unary minus applies to a whole power, so `-2 ** 2` is `-(2 ** 2)`.
+const POWER_PRECEDENCE: u8 = 6;
+
+/// Precedence and associativity of every binary operator, the higher
+/// the precedence the tighter the operator binds.
+fn binary_operator_info(kind: &TokenKind) -> Option<(u8, Associativity)> {
+    match kind {
+        TokenKind::OrOperator => Some((1, Associativity::Left)),
+        TokenKind::AndOperator => Some((2, Associativity::Left)),
+        TokenKind::EqualOperator
+        | TokenKind::NotEqualOperator
+        | TokenKind::LessOperator
+        | TokenKind::LessEqualOperator
+        | TokenKind::GreaterOperator
+        | TokenKind::GreaterEqualOperator => Some((3, Associativity::Left)),
+        TokenKind::AddOperator | TokenKind::SubOperator => Some((4, Associativity::Left)),
+        TokenKind::MulOperator | TokenKind::DivOperator | TokenKind::IntDivOperator | TokenKind::ModOperator => {
+            Some((5, Associativity::Left))
+        }
+        TokenKind::PowOperator => Some((POWER_PRECEDENCE, Associativity::Right)),
+        _ => None,
+    }
+}
 
+/// Precedence of a binary operator, for code that lays out source text.
+pub(crate) fn binary_precedence(kind: &TokenKind) -> Option<u8> {
+    binary_operator_info(kind).map(|(precedence, _)| precedence)
+}
+
+/// Syntax errors do not stop the parser: every error is recorded, the tokens
+/// up to the next closing parenthesis or statement boundary are skipped and
+/// an error node stands in for the part that could not be parsed.
 pub struct Parser<TSource: CharactersSource> {
     tokens: Peekable<TokenIterator<TSource>>,
+    errors: Vec<InvalidExpressionNode>,
+    // parentheses opened by the constructs being parsed, a `)` only ends
+    // error recovery when it can close one of them
+    open_parentheses: usize,
+    // a `;` inside parentheses ended the statement while recovering, so
+    // the `)` closing them is still to come
+    cut_short: bool,
+    // column of the first token of the statement, a line starting with an
+    // operator only continues the statement when indented past it
+    statement_column: u32,
+    previous_end: Position,
 }
 
 impl<TSource: CharactersSource> Parser<TSource> {
     pub fn from_tokens(tokens: TokenIterator<TSource>) -> Self {
         Parser {
             tokens: tokens.peekable(),
+            errors: Vec::new(),
+            open_parentheses: 0,
+            cut_short: false,
+            statement_column: 1,
+            previous_end: Position { column: 1, row: 1, offset: 0 },
         }
     }
 
-    pub fn parse(&mut self) -> Result<ExpressionNode, InvalidExpressionNode> {
-        let result = self.match_expression()?;
-        match self.tokens.peek() {
-            None => Ok(result),
-            Some(token) => Err(InvalidExpressionNode { expected: TokenKind::EOF, got: Some(token.clone()) })
+    /// Parses a single statement, failing with the first syntax error.
+    pub fn parse(&mut self) -> Result<StatementNode, InvalidExpressionNode> {
+        let statement = self.match_statement_recovering();
+        if let Some(token) = self.tokens.peek() {
+            self.errors.push(InvalidExpressionNode {
+                expected: TokenKind::Eof,
+                got: Some(token.clone()),
+            });
+        }
+        match self.errors.drain(..).next() {
+            Some(error) => Err(error),
+            None => Ok(statement),
         }
     }
 
-    fn match_expression(&mut self) -> Result<ExpressionNode, InvalidExpressionNode> {
-        let left = match self.tokens.peek() {
-            Some(Token {
-                kind: TokenKind::IntLiteral(_) | TokenKind::ParenthesisOpen | TokenKind::SubOperator,
-                ..
-            }) => self.match_term(),
-            Some(token) => {
-                return Err(InvalidExpressionNode {
-                    expected: TokenKind::IntLiteral(0),
-                    got: Some(token.clone()),
-                })
+    /// Parses statements separated by `;` or line breaks up to the end of
+    /// input, returning the syntax tree together with all syntax errors found
+    /// on the way. Parts that could not be parsed are replaced by error nodes.
+    ///
+    /// A statement continues on the next line as long as it is incomplete or
+    /// the line starts with a binary operator indented past the start of the
+    /// statement, so `x\n  - 1` is `x - 1` while `x\n-1` is two statements.
+    pub fn parse_program(&mut self) -> (ProgramNode, Vec<InvalidExpressionNode>) {
+        let mut statements = Vec::new();
+        loop {
+            while self.next_is(TokenKind::Semicolon) {
+                self.advance();
+            }
+            // the rest of a statement cut short, its error is reported already
+            if std::mem::take(&mut self.cut_short) && self.next_is(TokenKind::ParenthesisClose) {
+                self.skip(&[]);
+                continue;
+            }
+            if self.tokens.peek().is_none() {
+                break;
+            }
+
+            statements.push(self.match_statement_recovering());
+            if self.next_starts_line() {
+                continue;
+            }
+            match self.tokens.peek() {
+                None
+                | Some(Token {
+                    kind: TokenKind::Semicolon,
+                    ..
+                }) => (),
+                Some(token) => {
+                    let error = InvalidExpressionNode {
+                        expected: TokenKind::Semicolon,
+                        got: Some(token.clone()),
+                    };
+                    self.recover(error, &[]);
+                }
             }
-            None => Err(InvalidExpressionNode {
-                expected: TokenKind::IntLiteral(0),
-                got: None,
+        }
+        (ProgramNode { statements }, std::mem::take(&mut self.errors))
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.next()?;
+        self.previous_end = token.end_position;
+        Some(token)
+    }
+
+    /// Whether the next token is the first one on its line.
+    fn next_starts_line(&mut self) -> bool {
+        let row = self.previous_end.row;
+        self.tokens.peek().is_some_and(|token| token.start_position.row > row)
+    }
+
+    /// Whether the next token starts a line that is not indented past the
+    /// statement, outside of parentheses.
+    fn next_ends_statement(&mut self) -> bool {
+        let column = self.statement_column;
+        self.open_parentheses == 0
+            && self.next_starts_line()
+            && self.tokens.peek().is_some_and(|token| token.start_position.column <= column)
+    }
+
+    fn next_is(&mut self, kind: TokenKind) -> bool {
+        self.tokens.peek().is_some_and(|token| token.kind == kind)
+    }
+
+    /// Consumes the next token if it is of the given kind.
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, InvalidExpressionNode> {
+        match self.tokens.peek() {
+            Some(token) if token.kind == kind => Ok(self.advance().unwrap()),
+            token => Err(InvalidExpressionNode {
+                expected: kind,
+                got: token.cloned(),
             }),
-        }?;
-
-        if let Some(&Token {kind: TokenKind::ParenthesisClose, ..}) = self.tokens.peek() {
-            return Ok(ExpressionNode::SingleTermNode(left));
-        }
-
-        match self.tokens.next() {
-            Some(token) => match token.kind {
-                TokenKind::AddOperator => Ok(ExpressionNode::AdditionTermNode {
-                    left: left,
-                    right: Box::new(self.match_expression()?),
-                }),
-                TokenKind::SubOperator => Ok(ExpressionNode::SubstractionTermNode {
-                    left: left,
-                    right: Box::new(self.match_expression()?),
-                }),
-                _ => {
-                    return Err(InvalidExpressionNode {
-                        expected: TokenKind::AddOperator,
-                        got: Some(token),
-                    })
+        }
+    }
+
+    /// Like `expect`, but when the token is missing the error is recorded
+    /// and the parser skips ahead to the token, consuming it if found.
+    fn expect_recovering(&mut self, kind: TokenKind) -> Option<Token> {
+        match self.expect(kind.clone()) {
+            Ok(token) => Some(token),
+            Err(error) => {
+                self.recover(error, std::slice::from_ref(&kind));
+                match self.next_is(kind.clone()) {
+                    true => self.expect(kind).ok(),
+                    false => None,
                 }
+            }
+        }
+    }
+
+    /// Records the error, unless its token has one already, and skips tokens
+    /// until one of `stop` at the current nesting depth, the end of the
+    /// statement or a `)` closing an enclosing construct. Outside of parentheses a line break ends the statement too,
+    /// once at least one token has been skipped. The stopping token is not
+    /// consumed. Returns the span of the skipped tokens, or an empty span
+    /// where the error occurred.
+    fn recover(&mut self, error: InvalidExpressionNode, stop: &[TokenKind]) -> Span {
+        let mut span = match &error.got {
+            Some(token) => Span {
+                start: token.start_position,
+                end: token.start_position,
             },
-            None => return Ok(ExpressionNode::SingleTermNode(left)),
+            None => Span {
+                start: self.previous_end,
+                end: self.previous_end,
+            },
+        };
+        // every enclosing construct would report the token again, or the
+        // end of input once it has run out
+        let repeated = self.errors.last().is_some_and(|last| match (&last.got, &error.got) {
+            (Some(last), Some(token)) => last.start_position == token.start_position,
+            (last, token) => last.is_none() && token.is_none(),
+        });
+        if !repeated {
+            self.errors.push(error);
+        }
+        if let Some(end) = self.skip(stop) {
+            span.end = end;
         }
+        span
     }
 
-    fn match_term(&mut self) -> Result<TermNode, InvalidExpressionNode> {
-        let left = match self.tokens.peek() {
+    /// Skips tokens like `recover` does, returning where the last skipped
+    /// one ends.
+    fn skip(&mut self, stop: &[TokenKind]) -> Option<Position> {
+        let mut end = None;
+        let mut depth = 0usize;
+        loop {
+            let at_line_start = end.is_some() && self.open_parentheses == 0 && self.next_starts_line();
+            let Some(token) = self.tokens.peek() else {
+                break;
+            };
+            let at_stop = depth == 0
+                && (stop.contains(&token.kind)
+                    || token.kind == TokenKind::Semicolon
+                    || (token.kind == TokenKind::ParenthesisClose && self.open_parentheses > 0)
+                    || at_line_start);
+            if at_stop {
+                if token.kind == TokenKind::Semicolon && self.open_parentheses > 0 {
+                    self.cut_short = true;
+                }
+                break;
+            }
+            match token.kind {
+                TokenKind::ParenthesisOpen => depth += 1,
+                TokenKind::ParenthesisClose => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            end = Some(self.advance().unwrap().end_position);
+        }
+        end
+    }
+
+    fn match_statement_recovering(&mut self) -> StatementNode {
+        if let Some(token) = self.tokens.peek() {
+            self.statement_column = token.start_position.column;
+        }
+        self.match_statement().unwrap_or_else(|error| StatementNode::ErrorNode {
+            span: self.recover(error, &[]),
+        })
+    }
+
+    /// Parses an expression that has to be followed by one of `terminators`,
+    /// an error node takes the place of an expression that fails to parse.
+    fn match_expression_until(&mut self, terminators: &[TokenKind]) -> ExpressionNode {
+        self.match_expression().unwrap_or_else(|error| {
+            ExpressionNode::from(FactorNode::ErrorNode {
+                span: self.recover(error, terminators),
+            })
+        })
+    }
+
+    fn match_statement(&mut self) -> Result<StatementNode, InvalidExpressionNode> {
+        match self.tokens.peek() {
             Some(Token {
-                kind: TokenKind::IntLiteral(_) | TokenKind::ParenthesisOpen | TokenKind::SubOperator,
+                kind: TokenKind::LetKeyword,
                 ..
-            }) => self.match_factor(),
-            Some(token) => {
-                return Err(InvalidExpressionNode {
-                    expected: TokenKind::IntLiteral(0),
-                    got: Some(token.clone()),
-                })
+            }) => self.match_let(),
+            Some(Token {
+                kind: TokenKind::FnKeyword,
+                ..
+            }) => self.match_function(),
+            _ => Ok(StatementNode::ExpressionStatementNode(self.match_expression()?)),
+        }
+    }
+
+    fn match_function(&mut self) -> Result<StatementNode, InvalidExpressionNode> {
+        self.expect(TokenKind::FnKeyword)?;
+        let name = self.expect(TokenKind::Identifier)?;
+        self.expect(TokenKind::ParenthesisOpen)?;
+
+        let mut parameters = Vec::new();
+        if !self.next_is(TokenKind::ParenthesisClose) {
+            loop {
+                parameters.push(self.expect(TokenKind::Identifier)?.lexem);
+                if !self.next_is(TokenKind::Comma) {
+                    break;
+                }
+                self.advance();
             }
-            None => Err(InvalidExpressionNode {
-                expected: TokenKind::IntLiteral(0),
-                got: None,
-            }),
-        }?;
+        }
+        self.expect(TokenKind::ParenthesisClose)?;
+        self.expect(TokenKind::Assign)?;
+        let body = self.match_expression()?;
+
+        Ok(StatementNode::FunctionNode {
+            name_span: name.span(),
+            name: name.lexem,
+            parameters,
+            body: Rc::new(body),
+        })
+    }
+
+    fn match_let(&mut self) -> Result<StatementNode, InvalidExpressionNode> {
+        self.expect(TokenKind::LetKeyword)?;
+        let name = self.expect(TokenKind::Identifier)?;
+        self.expect(TokenKind::Assign)?;
+        let value = self.match_expression()?;
+
+        Ok(StatementNode::LetNode {
+            name_span: name.span(),
+            name: name.lexem,
+            value,
+        })
+    }
+
+    fn match_expression(&mut self) -> Result<ExpressionNode, InvalidExpressionNode> {
+        self.match_binary(0)
+    }
+
+    /// Precedence climbing: parses a factor and then keeps folding binary
+    /// operators binding at least as tight as `min_precedence` into it.
+    fn match_binary(&mut self, min_precedence: u8) -> Result<ExpressionNode, InvalidExpressionNode> {
+        let mut left = ExpressionNode::from(self.match_factor()?);
+
+        while !self.next_ends_statement() {
+            let Some(token) = self.tokens.peek() else {
+                break;
+            };
+            let operator = token.kind.clone();
+            let operator_span = token.span();
+            let (precedence, associativity) = match binary_operator_info(&operator) {
+                Some((precedence, associativity)) if precedence >= min_precedence => (precedence, associativity),
+                _ => break,
+            };
+            self.advance();
+
+            let right_min_precedence = match associativity {
+                Associativity::Left => precedence + 1,
+                Associativity::Right => precedence,
+            };
+            let right = self.match_binary(right_min_precedence)?;
+            left = build_binary_node(operator, operator_span, left, right);
+        }
+
+        Ok(left)
+    }
 
+    fn match_factor(&mut self) -> Result<FactorNode, InvalidExpressionNode> {
         match self.tokens.peek() {
-            Some(token) => match token.kind {
-                TokenKind::MulOperator => {
-                    self.tokens.next();
-                    Ok(TermNode::MultiplicationFactorNode {
-                        left: left,
-                        right: Box::new(self.match_term()?),
-                    })
+            Some(Token {
+                kind: TokenKind::SubOperator,
+                ..
+            }) => {
+                let operator = self.advance().unwrap();
+                Ok(FactorNode::NegativeExpressionNode {
+                    operand: Box::new(self.match_binary(POWER_PRECEDENCE)?.into_factor()),
+                    operator_span: operator.span(),
+                })
+            }
+            Some(Token {
+                kind: TokenKind::NotOperator,
+                ..
+            }) => {
+                let operator = self.advance().unwrap();
+                Ok(FactorNode::NotExpressionNode {
+                    operand: Box::new(self.match_factor()?),
+                    operator_span: operator.span(),
+                })
+            }
+            _ => self.match_call(),
+        }
+    }
+
+    /// Parses a primary factor followed by any number of applications, either
+    /// parenthesised argument lists `f(a, b)` or juxtaposed arguments `f a`.
+    /// Outside parentheses arguments have to start on the line of the
+    /// function, a new line starts a new statement instead.
+    fn match_call(&mut self) -> Result<FactorNode, InvalidExpressionNode> {
+        let mut factor = self.match_primary()?;
+
+        while self.open_parentheses > 0 || !self.next_starts_line() {
+            let (arguments, arguments_span) = match self.tokens.peek() {
+                Some(Token {
+                    kind: TokenKind::ParenthesisOpen,
+                    ..
+                }) => self.match_arguments()?,
+                // literals can never be functions, so `2 3` stays an error
+                Some(token) if starts_argument(&token.kind) && can_be_applied(&factor) => {
+                    let argument = self.match_primary()?;
+                    let span = argument.span();
+                    (vec![ExpressionNode::from(argument)], span)
                 }
-                TokenKind::DivOperator => {
-                    self.tokens.next();
-                    Ok(TermNode::DivisionFactorNode {
-                        left: left,
-                        right: Box::new(self.match_term()?),
-                    })
+                _ => break,
+            };
+
+            factor = FactorNode::CallNode {
+                callee: Box::new(factor),
+                arguments,
+                arguments_span,
+            };
+        }
+
+        Ok(factor)
+    }
+
+    fn match_arguments(&mut self) -> Result<(Vec<ExpressionNode>, Span), InvalidExpressionNode> {
+        let open = self.expect(TokenKind::ParenthesisOpen)?;
+        self.open_parentheses += 1;
+        let mut arguments = Vec::new();
+        if !self.next_is(TokenKind::ParenthesisClose) {
+            loop {
+                arguments.push(self.match_expression_until(&[TokenKind::Comma, TokenKind::ParenthesisClose]));
+                if !self.next_is(TokenKind::Comma) {
+                    break;
                 }
-                TokenKind::AddOperator => Ok(TermNode::SingleFactorNode(left)),
-                TokenKind::SubOperator => Ok(TermNode::SingleFactorNode(left)),
-                TokenKind::ParenthesisClose => Ok(TermNode::SingleFactorNode(left)),
-                _ => Err(InvalidExpressionNode {
-                    expected: TokenKind::MulOperator,
-                    got: Some(token.clone()),
-                }),
+                self.advance();
+            }
+        }
+        self.expect_recovering(TokenKind::ParenthesisClose);
+        self.open_parentheses -= 1;
+
+        Ok((
+            arguments,
+            Span {
+                start: open.start_position,
+                end: self.previous_end,
+            },
+        ))
+    }
+
+    fn match_lambda(&mut self, fun_span: Span) -> Result<FactorNode, InvalidExpressionNode> {
+        let parameter = self.expect(TokenKind::Identifier)?;
+        self.expect(TokenKind::Arrow)?;
+        let body = self.match_expression()?;
+
+        Ok(FactorNode::LambdaNode {
+            fun_span,
+            parameter_span: parameter.span(),
+            parameter: parameter.lexem,
+            body: Rc::new(body),
+        })
+    }
+
+    fn match_if(&mut self, if_span: Span) -> Result<FactorNode, InvalidExpressionNode> {
+        let condition = self.match_expression()?;
+        self.expect(TokenKind::ThenKeyword)?;
+        let consequence = self.match_expression()?;
+        let else_token = self.expect(TokenKind::ElseKeyword)?;
+        let alternative = self.match_expression()?;
+
+        Ok(FactorNode::IfNode {
+            condition: Box::new(condition),
+            consequence: Box::new(consequence),
+            alternative: Box::new(alternative),
+            if_span,
+            else_span: else_token.span(),
+            widen: Cell::new(false),
+        })
+    }
+
+    /// Parses the expression inside parentheses, the opening one has
+    /// already been consumed.
+    fn match_parenthesised(&mut self, open_span: Span) -> FactorNode {
+        self.open_parentheses += 1;
+        let exp = self.match_expression_until(&[TokenKind::ParenthesisClose]);
+        self.expect_recovering(TokenKind::ParenthesisClose);
+        self.open_parentheses -= 1;
+        FactorNode::ExpressionNode {
+            expression: Box::new(exp),
+            span: Span {
+                start: open_span.start,
+                end: self.previous_end,
             },
-            None => Ok(TermNode::SingleFactorNode(left)),
         }
     }
 
-    fn match_factor(&mut self) -> Result<FactorNode, InvalidExpressionNode> {
-        match self.tokens.next() {
-            Some(token) => match token.kind {
-                TokenKind::IntLiteral(value) => Ok(FactorNode::LiteralNode(value)),
-                TokenKind::ParenthesisOpen => {
-                    let exp = self.match_expression()?;
-                    match self.tokens.next() {
-                        Some(Token { kind: TokenKind::ParenthesisClose, .. }) => (),
-                        token => Err(InvalidExpressionNode {
-                            expected: TokenKind::ParenthesisClose,
-                            got: token})?,
-                        };
-                    Ok(FactorNode::ExpressionNode(Box::new(exp)))
-                },
-                TokenKind::SubOperator => Ok(FactorNode::NegativeExpressionNode(Box::new(self.match_factor()?))),
-                _ => Err(InvalidExpressionNode {
-                    expected: TokenKind::IntLiteral(0),
-                    got: Some(token.clone()),
-                }),
+    fn match_primary(&mut self) -> Result<FactorNode, InvalidExpressionNode> {
+        // a token that cannot start an expression is left for error recovery
+        let token = match self.tokens.peek() {
+            Some(token) if starts_primary(&token.kind) => self.advance().unwrap(),
+            token => {
+                return Err(InvalidExpressionNode {
+                    expected: TokenKind::IntLiteral(BigInt::zero()),
+                    got: token.cloned(),
+                })
+            }
+        };
+
+        let span = token.span();
+        match token.kind {
+            TokenKind::IntLiteral(value) => Ok(FactorNode::LiteralNode { value, span }),
+            TokenKind::FloatLiteral(value) => Ok(FactorNode::FloatLiteralNode { value, span }),
+            TokenKind::BoolLiteral(value) => Ok(FactorNode::BoolLiteralNode { value, span }),
+            TokenKind::Identifier => Ok(FactorNode::VariableNode { name: token.lexem, span }),
+            TokenKind::FunKeyword => self.match_lambda(span),
+            TokenKind::IfKeyword => self.match_if(span),
+            TokenKind::ParenthesisOpen => Ok(self.match_parenthesised(span)),
+            _ => unreachable!("{:?} cannot start an expression", token.kind),
+        }
+    }
+}
+
+fn starts_primary(kind: &TokenKind) -> bool {
+    starts_argument(kind) || *kind == TokenKind::ParenthesisOpen
+}
+
+/// Tokens that can begin a juxtaposed argument, parenthesised arguments
+/// are handled as an argument list instead.
+fn starts_argument(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::IntLiteral(_)
+            | TokenKind::FloatLiteral(_)
+            | TokenKind::BoolLiteral(_)
+            | TokenKind::Identifier
+            | TokenKind::FunKeyword
+            | TokenKind::IfKeyword
+    )
+}
+
+fn can_be_applied(factor: &FactorNode) -> bool {
+    !matches!(
+        factor,
+        FactorNode::LiteralNode { .. } | FactorNode::FloatLiteralNode { .. } | FactorNode::BoolLiteralNode { .. }
+    )
+}
+
+fn comparison_operator(kind: &TokenKind) -> ComparisonOperator {
+    match kind {
+        TokenKind::EqualOperator => ComparisonOperator::Equal,
+        TokenKind::NotEqualOperator => ComparisonOperator::NotEqual,
+        TokenKind::LessOperator => ComparisonOperator::Less,
+        TokenKind::LessEqualOperator => ComparisonOperator::LessEqual,
+        TokenKind::GreaterOperator => ComparisonOperator::Greater,
+        TokenKind::GreaterEqualOperator => ComparisonOperator::GreaterEqual,
+        _ => unreachable!("{:?} is not a comparison operator", kind),
+    }
+}
+
+fn build_binary_node(
+    operator: TokenKind,
+    operator_span: Span,
+    left: ExpressionNode,
+    right: ExpressionNode,
+) -> ExpressionNode {
+    match operator {
+        TokenKind::OrOperator => ExpressionNode::OrNode {
+            left: Box::new(left),
+            right: Box::new(right),
+            operator_span,
+        },
+        TokenKind::AndOperator => ExpressionNode::AndNode {
+            left: Box::new(left),
+            right: Box::new(right),
+            operator_span,
+        },
+        TokenKind::EqualOperator
+        | TokenKind::NotEqualOperator
+        | TokenKind::LessOperator
+        | TokenKind::LessEqualOperator
+        | TokenKind::GreaterOperator
+        | TokenKind::GreaterEqualOperator => ExpressionNode::ComparisonNode {
+            operator: comparison_operator(&operator),
+            left: Box::new(left),
+            right: Box::new(right),
+            operator_span,
+        },
+        TokenKind::AddOperator => ExpressionNode::AdditionTermNode {
+            left: Box::new(left),
+            right: right.into_term(),
+            operator_span,
+        },
+        TokenKind::SubOperator => ExpressionNode::SubstractionTermNode {
+            left: Box::new(left),
+            right: right.into_term(),
+            operator_span,
+        },
+        TokenKind::MulOperator => ExpressionNode::from(TermNode::MultiplicationFactorNode {
+            left: Box::new(left.into_term()),
+            right: right.into_factor(),
+            operator_span,
+        }),
+        TokenKind::DivOperator => ExpressionNode::from(TermNode::DivisionFactorNode {
+            left: Box::new(left.into_term()),
+            right: right.into_factor(),
+            operator_span,
+        }),
+        TokenKind::IntDivOperator => ExpressionNode::from(TermNode::IntegerDivisionFactorNode {
+            left: Box::new(left.into_term()),
+            right: right.into_factor(),
+            operator_span,
+        }),
+        TokenKind::ModOperator => ExpressionNode::from(TermNode::ModuloFactorNode {
+            left: Box::new(left.into_term()),
+            right: right.into_factor(),
+            operator_span,
+        }),
+        TokenKind::PowOperator => ExpressionNode::from(FactorNode::PowerNode {
+            base: Box::new(left.into_factor()),
+            exponent: Box::new(right.into_factor()),
+            operator_span,
+        }),
+        _ => unreachable!("{:?} is not a binary operator", operator),
+    }
+}
+
+#[cfg(test)]
+use crate::evaluator::{
+    context::{Context, NumericMode},
+    environment::Environment,
+    error::EvalError,
+    value::Value,
+};
+#[cfg(test)]
+use crate::lexer::source_map::SourceFile;
+
+#[cfg(test)]
+fn parse_str(string: &str) -> Result<StatementNode, InvalidExpressionNode> {
+    use crate::lexer::Lexer;
+
+    Parser::from_tokens(Lexer::from_str(string).into_tokens()).parse()
+}
+
+/// Statement of a source that has to parse without errors.
+#[cfg(test)]
+pub(crate) fn parse_valid_statement(string: &str) -> StatementNode {
+    parse_str(string).unwrap_or_else(|error| panic!("{} does not parse: {:?}", string, error))
+}
+
+#[cfg(test)]
+pub(crate) fn evaluate_str_in_mode(string: &str, numeric_mode: NumericMode) -> Result<Value, EvalError> {
+    let context = Context {
+        numeric_mode,
+        ..Context::default()
+    };
+    parse_valid_statement(string).execute(&context, &mut Environment::default())
+}
+
+#[cfg(test)]
+pub(crate) fn evaluate_str(string: &str) -> Result<Value, EvalError> {
+    evaluate_str_in_mode(string, NumericMode::Machine)
+}
+
+/// Span between two columns of the first line of a source.
+#[cfg(test)]
+pub(crate) fn span(start_column: u32, end_column: u32) -> Span {
+    Span {
+        start: Position { column: start_column, row: 1, offset: start_column as usize - 1 },
+        end: Position { column: end_column, row: 1, offset: end_column as usize - 1 },
+    }
+}
+
+#[test]
+fn subtraction_is_left_associative() {
+    assert_eq!(evaluate_str("8 - 3 - 2"), Ok(Value::Int(3)));
+    assert_eq!(evaluate_str("10 - 4 - 3 - 2"), Ok(Value::Int(1)));
+}
+
+#[test]
+fn division_is_left_associative() {
+    assert_eq!(evaluate_str("8 / 4 / 2"), Ok(Value::Int(1)));
+    assert_eq!(evaluate_str("100 / 10 / 5 / 2"), Ok(Value::Int(1)));
+}
+
+#[test]
+fn mixed_operators_are_left_associative() {
+    assert_eq!(evaluate_str("8 - 3 + 2"), Ok(Value::Int(7)));
+    assert_eq!(evaluate_str("8 / 4 * 2"), Ok(Value::Int(4)));
+    assert_eq!(evaluate_str("2 * 3 - 4 / 2 - 1"), Ok(Value::Int(3)));
+}
+
+#[test]
+fn multiplication_binds_tighter_than_addition() {
+    assert_eq!(evaluate_str("2 + 3 * 4"), Ok(Value::Int(14)));
+    assert_eq!(evaluate_str("(2 + 3) * 4"), Ok(Value::Int(20)));
+    assert_eq!(evaluate_str("-2 * -(3 - 5)"), Ok(Value::Int(-4)));
+}
+
+#[test]
+fn chained_subtraction_tree_shape() {
+    use crate::lexer::token::Position;
+
+    let tree = parse_str("8 - 3 - 2").unwrap();
+    let span = |column| Span {
+        start: Position { column, row: 1, offset: column as usize - 1 },
+        end: Position { column: column + 1, row: 1, offset: column as usize },
+    };
+    let literal = |value, column| {
+        TermNode::SingleFactorNode(FactorNode::LiteralNode {
+            value: BigInt::from(value),
+            span: span(column),
+        })
+    };
+
+    assert_eq!(
+        tree,
+        StatementNode::ExpressionStatementNode(ExpressionNode::SubstractionTermNode {
+            left: Box::new(ExpressionNode::SubstractionTermNode {
+                left: Box::new(ExpressionNode::SingleTermNode(literal(8, 1))),
+                right: literal(3, 5),
+                operator_span: span(3),
+            }),
+            right: literal(2, 9),
+            operator_span: span(7),
+        })
+    );
+}
+
+#[test]
+fn parse_errors() {
+    let error = parse_str("2 3").unwrap_err();
+    assert_eq!(error.expected, TokenKind::Eof);
+    assert_eq!(error.got.unwrap().kind, TokenKind::IntLiteral(BigInt::from(3)));
+
+    let error = parse_str("(2 + 3").unwrap_err();
+    assert_eq!(error.expected, TokenKind::ParenthesisClose);
+    assert!(error.got.is_none());
+
+    let error = parse_str("2 * * 3").unwrap_err();
+    assert_eq!(error.expected, TokenKind::IntLiteral(BigInt::zero()));
+    assert_eq!(error.got.unwrap().kind, TokenKind::MulOperator);
+}
+
+#[test]
+fn parse_let() {
+    use crate::lexer::token::Position;
+
+    let statement = parse_str("let x = y").unwrap();
+    assert_eq!(
+        statement,
+        StatementNode::LetNode {
+            name: "x".to_string(),
+            name_span: Span {
+                start: Position { column: 5, row: 1, offset: 4 },
+                end: Position { column: 6, row: 1, offset: 5 },
             },
-            None => Err(InvalidExpressionNode { expected: TokenKind::IntLiteral(0), got: None }),
+            value: ExpressionNode::from(FactorNode::VariableNode {
+                name: "y".to_string(),
+                span: Span {
+                    start: Position { column: 9, row: 1, offset: 8 },
+                    end: Position { column: 10, row: 1, offset: 9 },
+                },
+            }),
+        }
+    );
+
+    let error = parse_str("let 5 = 3").unwrap_err();
+    assert_eq!(error.expected, TokenKind::Identifier);
+
+    let error = parse_str("let x 3").unwrap_err();
+    assert_eq!(error.expected, TokenKind::Assign);
+
+    let error = parse_str("1 + let").unwrap_err();
+    assert_eq!(error.got.unwrap().kind, TokenKind::LetKeyword);
+}
+
+#[test]
+fn parse_function_definition() {
+    let statement = parse_str("fn add(a, b) = a + b").unwrap();
+    match statement {
+        StatementNode::FunctionNode { name, parameters, .. } => {
+            assert_eq!(name, "add");
+            assert_eq!(parameters, vec!["a".to_string(), "b".to_string()]);
         }
+        other => panic!("expected a function definition, got {:?}", other),
     }
+
+    match parse_str("fn answer() = 42").unwrap() {
+        StatementNode::FunctionNode { parameters, .. } => assert!(parameters.is_empty()),
+        other => panic!("expected a function definition, got {:?}", other),
+    }
+
+    let error = parse_str("fn add(a b) = a").unwrap_err();
+    assert_eq!(error.expected, TokenKind::ParenthesisClose);
+
+    let error = parse_str("fn add(a, 1) = a").unwrap_err();
+    assert_eq!(error.expected, TokenKind::Identifier);
 }
+
+#[test]
+fn parse_call() {
+    let statement = parse_str("-f(1, 2 + 3)(4)").unwrap();
+    let StatementNode::ExpressionStatementNode(ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(
+        FactorNode::NegativeExpressionNode { operand, .. },
+    ))) = statement
+    else {
+        panic!("expected a negated call");
+    };
+    let FactorNode::CallNode { callee, arguments, .. } = *operand else {
+        panic!("expected a call");
+    };
+    assert_eq!(arguments.len(), 1);
+    let FactorNode::CallNode { callee, arguments, .. } = *callee else {
+        panic!("expected a nested call");
+    };
+    assert_eq!(arguments.len(), 2);
+    assert!(matches!(*callee, FactorNode::VariableNode { .. }));
+
+    let error = parse_str("f(1, 2").unwrap_err();
+    assert_eq!(error.expected, TokenKind::ParenthesisClose);
+    assert!(error.got.is_none());
+}
+
+#[test]
+fn parse_lambda() {
+    let statement = parse_str("fun x -> x + 1").unwrap();
+    let StatementNode::ExpressionStatementNode(ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(
+        FactorNode::LambdaNode { parameter, body, .. },
+    ))) = statement
+    else {
+        panic!("expected a lambda");
+    };
+    assert_eq!(parameter, "x");
+    assert!(matches!(*body, ExpressionNode::AdditionTermNode { .. }));
+
+    let error = parse_str("fun -> 1").unwrap_err();
+    assert_eq!(error.expected, TokenKind::Identifier);
+
+    let error = parse_str("fun x 1").unwrap_err();
+    assert_eq!(error.expected, TokenKind::Arrow);
+}
+
+#[test]
+fn parse_juxtaposition() {
+    let statement = parse_str("f x 2 * g(y)").unwrap();
+    let StatementNode::ExpressionStatementNode(ExpressionNode::SingleTermNode(TermNode::MultiplicationFactorNode {
+        left,
+        right,
+        ..
+    })) = statement
+    else {
+        panic!("expected a multiplication");
+    };
+
+    let TermNode::SingleFactorNode(FactorNode::CallNode { callee, arguments, .. }) = *left else {
+        panic!("expected a call");
+    };
+    assert_eq!(arguments.len(), 1);
+    assert!(matches!(*callee, FactorNode::CallNode { .. }));
+    assert!(matches!(right, FactorNode::CallNode { .. }));
+
+    // a minus after a function is a subtraction, not a negative argument
+    let statement = parse_str("f - 1").unwrap();
+    assert!(matches!(
+        statement,
+        StatementNode::ExpressionStatementNode(ExpressionNode::SubstractionTermNode { .. })
+    ));
+}
+
+#[test]
+fn logical_operators_bind_looser_than_comparisons() {
+    let statement = parse_str("a || !b && 1 + 2 < 4 == c").unwrap();
+    let StatementNode::ExpressionStatementNode(ExpressionNode::OrNode { right, .. }) = statement else {
+        panic!("expected a disjunction");
+    };
+    let ExpressionNode::AndNode { left, right, .. } = *right else {
+        panic!("expected a conjunction");
+    };
+    assert!(matches!(
+        *left,
+        ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(FactorNode::NotExpressionNode { .. }))
+    ));
+    let ExpressionNode::ComparisonNode { operator, left, .. } = *right else {
+        panic!("expected a comparison");
+    };
+    assert_eq!(operator, ComparisonOperator::Equal);
+    let ExpressionNode::ComparisonNode { operator, left, .. } = *left else {
+        panic!("expected a nested comparison");
+    };
+    assert_eq!(operator, ComparisonOperator::Less);
+    assert!(matches!(*left, ExpressionNode::AdditionTermNode { .. }));
+
+    assert_eq!(evaluate_str("1 < 2 && 2 < 1 || 3 >= 3"), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn parse_if() {
+    let statement = parse_str("1 + if a then b else c * 2").unwrap();
+    let StatementNode::ExpressionStatementNode(ExpressionNode::AdditionTermNode { right, .. }) = statement else {
+        panic!("expected an addition");
+    };
+    let TermNode::SingleFactorNode(FactorNode::IfNode { alternative, .. }) = right else {
+        panic!("expected a conditional");
+    };
+    // the else branch extends as far to the right as possible
+    assert!(matches!(
+        *alternative,
+        ExpressionNode::SingleTermNode(TermNode::MultiplicationFactorNode { .. })
+    ));
+
+    let error = parse_str("if a b else c").unwrap_err();
+    assert_eq!(error.expected, TokenKind::ThenKeyword);
+
+    let error = parse_str("if a then b").unwrap_err();
+    assert_eq!(error.expected, TokenKind::ElseKeyword);
+    assert!(error.got.is_none());
+}
+
+#[test]
+fn power_is_right_associative_and_binds_tighter_than_minus() {
+    assert_eq!(evaluate_str("2 ** 3 ** 2"), Ok(Value::Int(512)));
+    assert_eq!(evaluate_str("2 ^ 3 ^ 2"), Ok(Value::Int(512)));
+    assert_eq!(evaluate_str("-2 ** 2"), Ok(Value::Int(-4)));
+    assert_eq!(evaluate_str("(-2) ** 2"), Ok(Value::Int(4)));
+    assert_eq!(evaluate_str("2 * 3 ** 2"), Ok(Value::Int(18)));
+    assert_eq!(evaluate_str("2.0 ** -1"), Ok(Value::Float(0.5)));
+
+    let statement = parse_str("-a ** b").unwrap();
+    let StatementNode::ExpressionStatementNode(ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(
+        FactorNode::NegativeExpressionNode { operand, .. },
+    ))) = statement
+    else {
+        panic!("expected a negation");
+    };
+    assert!(matches!(*operand, FactorNode::PowerNode { .. }));
+}
+
+#[test]
+fn modulo_and_integer_division_bind_like_multiplication() {
+    assert_eq!(evaluate_str("1 + 7 % 4 * 2"), Ok(Value::Int(7)));
+    assert_eq!(evaluate_str("20 // 3 // 2"), Ok(Value::Int(3)));
+    assert_eq!(evaluate_str("10 - 7 // 2"), Ok(Value::Int(7)));
+}
+
+#[cfg(test)]
+pub(crate) fn parse_program_str(string: &str) -> (ProgramNode, Vec<InvalidExpressionNode>) {
+    use crate::lexer::Lexer;
+
+    Parser::from_tokens(Lexer::from_str(string).into_tokens()).parse_program()
+}
+
+/// Parses a file of a source map, so that the spans are offset like the
+/// engine's.
+#[cfg(test)]
+pub(crate) fn parse_program_file(file: &SourceFile) -> (ProgramNode, Vec<InvalidExpressionNode>) {
+    use crate::lexer::Lexer;
+
+    Parser::from_tokens(Lexer::from_source_file(file).into_tokens()).parse_program()
+}
+
+/// Program of a source that has to parse without errors.
+#[cfg(test)]
+pub(crate) fn parse_valid_program(string: &str) -> ProgramNode {
+    let (program, errors) = parse_program_str(string);
+    assert!(errors.is_empty(), "{} does not parse: {:?}", string, errors);
+    program
+}
+
+#[test]
+fn parse_program() {
+    let (program, errors) = parse_program_str("let x = 1; x + 2;; fn f(a) = a");
+    assert!(errors.is_empty());
+    assert_eq!(program.statements.len(), 3);
+    assert_eq!(program.statements[0].bound_name(), Some("x"));
+    assert_eq!(program.statements[2].bound_name(), Some("f"));
+
+    let (program, errors) = parse_program_str("");
+    assert!(program.statements.is_empty());
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn recover_from_errors_in_parentheses_and_arguments() {
+    use crate::lexer::token::Position;
+
+    let (program, errors) = parse_program_str("f(1, * 2, 3) + (4 +) * (5 6)");
+    let kinds = errors
+        .iter()
+        .map(|error| (error.expected.clone(), error.got.as_ref().map(|token| token.kind.clone())))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            (TokenKind::IntLiteral(BigInt::zero()), Some(TokenKind::MulOperator)),
+            (TokenKind::IntLiteral(BigInt::zero()), Some(TokenKind::ParenthesisClose)),
+            (TokenKind::ParenthesisClose, Some(TokenKind::IntLiteral(BigInt::from(6)))),
+        ]
+    );
+
+    // the valid parts are kept, the bad argument is replaced by an error node
+    let [StatementNode::ExpressionStatementNode(ExpressionNode::AdditionTermNode { left, .. })] =
+        program.statements.as_slice()
+    else {
+        panic!("expected a single addition");
+    };
+    let ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(FactorNode::CallNode { arguments, .. })) = &**left
+    else {
+        panic!("expected a call");
+    };
+    assert_eq!(arguments.len(), 3);
+    assert_eq!(
+        arguments[1],
+        ExpressionNode::from(FactorNode::ErrorNode {
+            span: Span {
+                start: Position { column: 6, row: 1, offset: 5 },
+                end: Position { column: 9, row: 1, offset: 8 },
+            }
+        })
+    );
+    assert_eq!(
+        arguments[2],
+        ExpressionNode::from(FactorNode::LiteralNode {
+            value: BigInt::from(3),
+            span: Span {
+                start: Position { column: 11, row: 1, offset: 10 },
+                end: Position { column: 12, row: 1, offset: 11 },
+            }
+        })
+    );
+}
+
+#[test]
+fn recover_at_statement_boundaries() {
+    let (program, errors) = parse_program_str("let = 1; 2 * * 3; let y = (1 + 2; y + 3 4");
+    assert_eq!(errors.len(), 4);
+    assert_eq!(errors[0].expected, TokenKind::Identifier);
+    assert_eq!(errors[1].got.as_ref().unwrap().kind, TokenKind::MulOperator);
+    assert_eq!(errors[2].expected, TokenKind::ParenthesisClose);
+    assert_eq!(errors[2].got.as_ref().unwrap().kind, TokenKind::Semicolon);
+    assert_eq!(errors[3].expected, TokenKind::Semicolon);
+    assert_eq!(errors[3].got.as_ref().unwrap().kind, TokenKind::IntLiteral(BigInt::from(4)));
+
+    assert_eq!(program.statements.len(), 4);
+    assert!(matches!(program.statements[0], StatementNode::ErrorNode { .. }));
+
+    let (_, errors) = parse_program_str("f((1 +");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].got.is_none());
+    assert_eq!(errors[0].expected, TokenKind::IntLiteral(BigInt::zero()));
+    assert!(matches!(program.statements[1], StatementNode::ErrorNode { .. }));
+    // a missing parenthesis keeps the expression it would have closed
+    assert_eq!(program.statements[2].bound_name(), Some("y"));
+    assert!(matches!(
+        program.statements[3],
+        StatementNode::ExpressionStatementNode(ExpressionNode::AdditionTermNode { .. })
+    ));
+}
+
+#[test]
+fn report_a_token_only_once() {
+    // the missing operand is reported, not the `)` it leaves missing as well
+    let (_, errors) = parse_program_str("(2 * ;");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].expected, TokenKind::IntLiteral(BigInt::zero()));
+    assert_eq!(errors[0].got.as_ref().unwrap().kind, TokenKind::Semicolon);
+
+    // nor the rest of the statement the `;` cut short
+    let (program, errors) = parse_program_str("(2 * ;); f(1, ;) + 2\n3");
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors.iter().all(|error| error.got.as_ref().unwrap().kind == TokenKind::Semicolon));
+    assert_eq!(program.statements.len(), 3);
+}
+
+#[test]
+fn every_node_spans_its_source() {
+    let source = "-(1 + 2) * f(x, 3) ** 2 < if b then fun y -> y else g 4";
+    let tree = match parse_str(source).unwrap() {
+        StatementNode::ExpressionStatementNode(exp) => exp,
+        statement => panic!("expected an expression, got {:?}", statement),
+    };
+    let text = |span: Span| &source[span.start.offset..span.end.offset];
+
+    assert_eq!(text(tree.span()), source);
+    let ExpressionNode::ComparisonNode {
+        left: product,
+        right: condition,
+        ..
+    } = &tree
+    else {
+        panic!("expected a comparison, got {:?}", tree);
+    };
+    assert_eq!(text(product.span()), "-(1 + 2) * f(x, 3) ** 2");
+    assert_eq!(text(condition.span()), "if b then fun y -> y else g 4");
+
+    let ExpressionNode::SingleTermNode(TermNode::MultiplicationFactorNode { left, right, .. }) = product.as_ref() else {
+        panic!("expected a multiplication, got {:?}", product);
+    };
+    assert_eq!(text(left.span()), "-(1 + 2)");
+    assert_eq!(text(right.span()), "f(x, 3) ** 2");
+    let TermNode::SingleFactorNode(FactorNode::NegativeExpressionNode { operand, .. }) = left.as_ref() else {
+        panic!("expected a negation, got {:?}", left);
+    };
+    assert_eq!(text(operand.span()), "(1 + 2)");
+    let FactorNode::PowerNode { base, .. } = right else {
+        panic!("expected a power, got {:?}", right);
+    };
+    assert_eq!(text(base.span()), "f(x, 3)");
+
+    let ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(FactorNode::IfNode {
+        consequence, alternative, ..
+    })) = condition.as_ref()
+    else {
+        panic!("expected an if, got {:?}", condition);
+    };
+    assert_eq!(text(consequence.span()), "fun y -> y");
+    assert_eq!(text(alternative.span()), "g 4");
+
+    // offsets count bytes, columns count characters
+    let StatementNode::ExpressionStatementNode(ExpressionNode::AdditionTermNode { right, .. }) =
+        parse_str("żółw + 1").unwrap()
+    else {
+        panic!("expected an addition");
+    };
+    assert_eq!(right.span().start.offset, 10);
+    assert_eq!(right.span().start.column, 8);
+}
+
+#[test]
+fn line_breaks_separate_statements() {
+    let (program, errors) = parse_program_str("let x = 1\nfn f(a) =\n  a * 2\nf(x)\n  + 1; f\n(x)");
+    assert!(errors.is_empty());
+    assert_eq!(program.statements.len(), 5);
+    assert_eq!(program.statements[0].bound_name(), Some("x"));
+    assert_eq!(program.statements[1].bound_name(), Some("f"));
+    // a line starting with an operator continues the statement, one starting
+    // with an argument list does not
+    assert!(matches!(
+        program.statements[2],
+        StatementNode::ExpressionStatementNode(ExpressionNode::AdditionTermNode { .. })
+    ));
+    assert!(matches!(
+        program.statements[3],
+        StatementNode::ExpressionStatementNode(ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(
+            FactorNode::VariableNode { .. }
+        )))
+    ));
+    assert!(matches!(
+        program.statements[4],
+        StatementNode::ExpressionStatementNode(ExpressionNode::SingleTermNode(TermNode::SingleFactorNode(
+            FactorNode::ExpressionNode { .. }
+        )))
+    ));
+
+    // only an operator indented past the start of the statement continues it
+    let (program, errors) = parse_program_str("let a = 1\n-a\nfn f(x) =\n  x * 2\n  - 1\n+ 1");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].got.as_ref().unwrap().kind, TokenKind::AddOperator);
+    let printed = program.statements.iter().map(|statement| statement.to_string()).collect::<Vec<_>>();
+    assert_eq!(printed, ["let a = 1", "-a", "fn f(x) = x * 2 - 1", "<error>"]);
+
+    // recovery stops at the next line, which is parsed on its own
+    let (program, errors) = parse_program_str("1 2 3\n) 4\nlet y = 5");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].expected, TokenKind::Semicolon);
+    assert_eq!(errors[1].got.as_ref().unwrap().kind, TokenKind::ParenthesisClose);
+    assert_eq!(program.statements.len(), 3);
+    assert_eq!(program.statements[2].bound_name(), Some("y"));
+
+    // inside parentheses line breaks are insignificant
+    let (program, errors) = parse_program_str("f(1,\n2) * (3\n+ 4)");
+    assert!(errors.is_empty());
+    assert_eq!(program.statements.len(), 1);
+    let (program, errors) = parse_program_str("(f\n 3) + (g\n(4))");
+    assert!(errors.is_empty());
+    assert_eq!(program.statements.len(), 1);
+    let StatementNode::ExpressionStatementNode(ExpressionNode::AdditionTermNode { left, right, .. }) =
+        &program.statements[0]
+    else {
+        panic!("{:?} is not an addition", program.statements[0]);
+    };
+    assert_eq!(left.to_string(), "f(3)");
+    assert_eq!(right.to_string(), "g(4)");
+}
+
@@ -0,0 +1,484 @@
+//! Dumps syntax trees for inspection, as JSON with the span of every node,
+//! as S-expressions or as a Graphviz DOT graph. Nodes are named after their
+//! variants and keep the single child wrappers and parentheses, so the
+//! output shows the tree exactly as the parser built it.
+
+use std::fmt::Write;
+
+use crate::lexer::token::{Position, Span};
+
+use super::{
+    printer::comparison_symbol,
+    syntax_tree::{ExpressionNode, FactorNode, ProgramNode, StatementNode, TermNode},
+};
+
+/// Lines of S-expressions longer than this are broken, a child per line.
+const SEXPR_WIDTH: usize = 80;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Json,
+    SExpression,
+    Dot,
+}
+
+impl Format {
+    /// Format by its `--emit` name: `ast-json`, `sexpr` or `dot`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ast-json" => Some(Format::Json),
+            "sexpr" => Some(Format::SExpression),
+            "dot" => Some(Format::Dot),
+            _ => None,
+        }
+    }
+}
+
+enum Attribute {
+    Text(String),
+    /// A number written as is, integer literals can have any length.
+    Number(String),
+    Bool(bool),
+    Texts(Vec<String>),
+}
+
+enum Children {
+    One(Tree),
+    Many(Vec<Tree>),
+}
+
+/// Any node of a syntax tree, with its fields split into attributes and
+/// children so that every format can write all kinds of nodes alike.
+pub struct Tree {
+    kind: &'static str,
+    /// Statements and programs have no span of their own.
+    span: Option<Span>,
+    attributes: Vec<(&'static str, Attribute)>,
+    children: Vec<(&'static str, Children)>,
+}
+
+impl Tree {
+    fn new(kind: &'static str, span: Option<Span>) -> Self {
+        Tree {
+            kind,
+            span,
+            attributes: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn attribute(mut self, name: &'static str, value: Attribute) -> Self {
+        self.attributes.push((name, value));
+        self
+    }
+
+    fn child(mut self, name: &'static str, child: impl Into<Tree>) -> Self {
+        self.children.push((name, Children::One(child.into())));
+        self
+    }
+
+    fn children<T: Into<Tree>>(mut self, name: &'static str, children: impl IntoIterator<Item = T>) -> Self {
+        self.children
+            .push((name, Children::Many(children.into_iter().map(Into::into).collect())));
+        self
+    }
+
+    fn text(self, name: &'static str, text: &str) -> Self {
+        self.attribute(name, Attribute::Text(text.to_string()))
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        let mut out = String::new();
+        match format {
+            Format::Json => {
+                self.write_json(&mut out, 0);
+                out.push('\n');
+            }
+            Format::SExpression => {
+                self.write_sexpr(&mut out, 0);
+                out.push('\n');
+            }
+            Format::Dot => {
+                out.push_str("digraph ast {\n    node [shape=box];\n");
+                self.write_dot(&mut out, &mut 0);
+                out.push_str("}\n");
+            }
+        }
+        out
+    }
+
+    /// An object per node, with the kind, the span, the attributes and the
+    /// children in this order. Every child is on a line of its own.
+    fn write_json(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth + 1);
+        let _ = write!(out, "{{\n{}\"node\": {}", indent, json_string(self.kind));
+        if let Some(span) = self.span {
+            let _ = write!(
+                out,
+                ",\n{}\"span\": {{\"start\": {}, \"end\": {}}}",
+                indent,
+                json_position(span.start),
+                json_position(span.end)
+            );
+        }
+        for (name, value) in &self.attributes {
+            let value = match value {
+                Attribute::Text(text) => json_string(text),
+                Attribute::Number(number) => number.clone(),
+                Attribute::Bool(value) => value.to_string(),
+                Attribute::Texts(texts) => {
+                    format!("[{}]", texts.iter().map(|text| json_string(text)).collect::<Vec<_>>().join(", "))
+                }
+            };
+            let _ = write!(out, ",\n{}\"{}\": {}", indent, name, value);
+        }
+        for (name, children) in &self.children {
+            let _ = write!(out, ",\n{}\"{}\": ", indent, name);
+            match children {
+                Children::One(child) => child.write_json(out, depth + 1),
+                Children::Many(children) if children.is_empty() => out.push_str("[]"),
+                Children::Many(children) => {
+                    out.push('[');
+                    for (index, child) in children.iter().enumerate() {
+                        out.push_str(if index == 0 { "\n" } else { ",\n" });
+                        out.push_str(&"  ".repeat(depth + 2));
+                        child.write_json(out, depth + 2);
+                    }
+                    let _ = write!(out, "\n{}]", indent);
+                }
+            }
+        }
+        let _ = write!(out, "\n{}}}", "  ".repeat(depth));
+    }
+
+    /// `(Kind attributes... children...)`, with lists of names in their own
+    /// parentheses and lists of nodes spliced in. Spans are left out.
+    fn write_sexpr(&self, out: &mut String, depth: usize) {
+        let flat = self.sexpr();
+        if depth * 2 + flat.len() <= SEXPR_WIDTH {
+            out.push_str(&flat);
+            return;
+        }
+        out.push('(');
+        out.push_str(&self.sexpr_head());
+        for child in self.child_nodes() {
+            let _ = write!(out, "\n{}", "  ".repeat(depth + 1));
+            child.write_sexpr(out, depth + 1);
+        }
+        out.push(')');
+    }
+
+    fn sexpr(&self) -> String {
+        let mut parts = vec![self.sexpr_head()];
+        parts.extend(self.child_nodes().map(Tree::sexpr));
+        format!("({})", parts.join(" "))
+    }
+
+    fn sexpr_head(&self) -> String {
+        let mut parts = vec![self.kind.to_string()];
+        parts.extend(self.attributes.iter().map(|(_, value)| match value {
+            Attribute::Text(text) | Attribute::Number(text) => text.clone(),
+            Attribute::Bool(value) => value.to_string(),
+            Attribute::Texts(texts) => format!("({})", texts.join(" ")),
+        }));
+        parts.join(" ")
+    }
+
+    fn child_nodes(&self) -> impl Iterator<Item = &Tree> {
+        self.children.iter().flat_map(|(_, children)| match children {
+            Children::One(child) => std::slice::from_ref(child),
+            Children::Many(children) => children.as_slice(),
+        })
+    }
+
+    /// A box per node labelled with its kind, attributes and span, and an
+    /// edge per child labelled with the field it is in. Returns the id of
+    /// the node, ids are handed out from `next`.
+    fn write_dot(&self, out: &mut String, next: &mut usize) -> usize {
+        let id = *next;
+        *next += 1;
+
+        let mut label = vec![self.kind.to_string()];
+        label.extend(self.attributes.iter().map(|(name, value)| match value {
+            Attribute::Text(text) | Attribute::Number(text) => format!("{}: {}", name, text),
+            Attribute::Bool(value) => format!("{}: {}", name, value),
+            Attribute::Texts(texts) => format!("{}: {}", name, texts.join(", ")),
+        }));
+        if let Some(span) = self.span {
+            label.push(format!(
+                "{}:{}-{}:{}",
+                span.start.row, span.start.column, span.end.row, span.end.column
+            ));
+        }
+        let label = label.iter().map(|line| dot_escape(line)).collect::<Vec<_>>().join("\\n");
+        let _ = writeln!(out, "    n{} [label=\"{}\"];", id, label);
+
+        for (name, children) in &self.children {
+            match children {
+                Children::One(child) => {
+                    let child = child.write_dot(out, next);
+                    let _ = writeln!(out, "    n{} -> n{} [label=\"{}\"];", id, child, name);
+                }
+                Children::Many(children) => {
+                    for (index, child) in children.iter().enumerate() {
+                        let child = child.write_dot(out, next);
+                        let _ = writeln!(out, "    n{} -> n{} [label=\"{}[{}]\"];", id, child, name, index);
+                    }
+                }
+            }
+        }
+        id
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn json_position(position: Position) -> String {
+    format!(
+        "{{\"row\": {}, \"column\": {}, \"offset\": {}}}",
+        position.row, position.column, position.offset
+    )
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl From<&ExpressionNode> for Tree {
+    fn from(expression: &ExpressionNode) -> Self {
+        let span = Some(expression.span());
+        match expression {
+            ExpressionNode::SingleTermNode(term) => Tree::new("SingleTermNode", span).child("term", term),
+            ExpressionNode::OrNode { left, right, .. } => Tree::new("OrNode", span)
+                .child("left", &**left)
+                .child("right", &**right),
+            ExpressionNode::AndNode { left, right, .. } => Tree::new("AndNode", span)
+                .child("left", &**left)
+                .child("right", &**right),
+            ExpressionNode::ComparisonNode {
+                left, operator, right, ..
+            } => Tree::new("ComparisonNode", span)
+                .text("operator", comparison_symbol(*operator))
+                .child("left", &**left)
+                .child("right", &**right),
+            ExpressionNode::AdditionTermNode { left, right, .. } => Tree::new("AdditionTermNode", span)
+                .child("left", &**left)
+                .child("right", right),
+            ExpressionNode::SubstractionTermNode { left, right, .. } => Tree::new("SubstractionTermNode", span)
+                .child("left", &**left)
+                .child("right", right),
+        }
+    }
+}
+
+impl From<&TermNode> for Tree {
+    fn from(term: &TermNode) -> Self {
+        let span = Some(term.span());
+        let (kind, left, right) = match term {
+            TermNode::SingleFactorNode(factor) => return Tree::new("SingleFactorNode", span).child("factor", factor),
+            TermNode::MultiplicationFactorNode { left, right, .. } => ("MultiplicationFactorNode", left, right),
+            TermNode::DivisionFactorNode { left, right, .. } => ("DivisionFactorNode", left, right),
+            TermNode::IntegerDivisionFactorNode { left, right, .. } => ("IntegerDivisionFactorNode", left, right),
+            TermNode::ModuloFactorNode { left, right, .. } => ("ModuloFactorNode", left, right),
+        };
+        Tree::new(kind, span).child("left", &**left).child("right", right)
+    }
+}
+
+impl From<&FactorNode> for Tree {
+    fn from(factor: &FactorNode) -> Self {
+        let span = Some(factor.span());
+        match factor {
+            FactorNode::LiteralNode { value, .. } => {
+                Tree::new("LiteralNode", span).attribute("value", Attribute::Number(value.to_string()))
+            }
+            FactorNode::FloatLiteralNode { value, .. } => {
+                // JSON has no infinity, a literal too large for a float is kept as text
+                let value = match value.is_finite() {
+                    true => Attribute::Number(format!("{:?}", value)),
+                    false => Attribute::Text(value.to_string()),
+                };
+                Tree::new("FloatLiteralNode", span).attribute("value", value)
+            }
+            FactorNode::BoolLiteralNode { value, .. } => {
+                Tree::new("BoolLiteralNode", span).attribute("value", Attribute::Bool(*value))
+            }
+            FactorNode::VariableNode { name, .. } => Tree::new("VariableNode", span).text("name", name),
+            FactorNode::LambdaNode { parameter, body, .. } => Tree::new("LambdaNode", span)
+                .text("parameter", parameter)
+                .child("body", &**body),
+            FactorNode::IfNode {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => Tree::new("IfNode", span)
+                .child("condition", &**condition)
+                .child("consequence", &**consequence)
+                .child("alternative", &**alternative),
+            FactorNode::CallNode { callee, arguments, .. } => Tree::new("CallNode", span)
+                .child("callee", &**callee)
+                .children("arguments", arguments),
+            FactorNode::ExpressionNode { expression, .. } => {
+                Tree::new("ExpressionNode", span).child("expression", &**expression)
+            }
+            FactorNode::PowerNode { base, exponent, .. } => Tree::new("PowerNode", span)
+                .child("base", &**base)
+                .child("exponent", &**exponent),
+            FactorNode::NegativeExpressionNode { operand, .. } => {
+                Tree::new("NegativeExpressionNode", span).child("operand", &**operand)
+            }
+            FactorNode::NotExpressionNode { operand, .. } => {
+                Tree::new("NotExpressionNode", span).child("operand", &**operand)
+            }
+            FactorNode::ErrorNode { .. } => Tree::new("ErrorNode", span),
+        }
+    }
+}
+
+impl From<&StatementNode> for Tree {
+    fn from(statement: &StatementNode) -> Self {
+        match statement {
+            StatementNode::LetNode { name, value, .. } => Tree::new("LetNode", None).text("name", name).child("value", value),
+            StatementNode::FunctionNode {
+                name, parameters, body, ..
+            } => Tree::new("FunctionNode", None)
+                .text("name", name)
+                .attribute("parameters", Attribute::Texts(parameters.clone()))
+                .child("body", &**body),
+            StatementNode::ExpressionStatementNode(expression) => {
+                Tree::new("ExpressionStatementNode", None).child("expression", expression)
+            }
+            StatementNode::ErrorNode { span } => Tree::new("ErrorNode", Some(*span)),
+        }
+    }
+}
+
+impl From<&ProgramNode> for Tree {
+    fn from(program: &ProgramNode) -> Self {
+        Tree::new("ProgramNode", None).children("statements", &program.statements)
+    }
+}
+
+#[cfg(test)]
+fn parse_tree(source: &str) -> Tree {
+    Tree::from(&crate::parser::parse_valid_program(source))
+}
+
+#[test]
+fn emit_json() {
+    assert_eq!(
+        parse_tree("-x").render(Format::Json),
+        r#"{
+  "node": "ProgramNode",
+  "statements": [
+    {
+      "node": "ExpressionStatementNode",
+      "expression": {
+        "node": "SingleTermNode",
+        "span": {"start": {"row": 1, "column": 1, "offset": 0}, "end": {"row": 1, "column": 3, "offset": 2}},
+        "term": {
+          "node": "SingleFactorNode",
+          "span": {"start": {"row": 1, "column": 1, "offset": 0}, "end": {"row": 1, "column": 3, "offset": 2}},
+          "factor": {
+            "node": "NegativeExpressionNode",
+            "span": {"start": {"row": 1, "column": 1, "offset": 0}, "end": {"row": 1, "column": 3, "offset": 2}},
+            "operand": {
+              "node": "VariableNode",
+              "span": {"start": {"row": 1, "column": 2, "offset": 1}, "end": {"row": 1, "column": 3, "offset": 2}},
+              "name": "x"
+            }
+          }
+        }
+      }
+    }
+  ]
+}
+"#
+    );
+    assert_eq!(
+        parse_tree("fn f() = 1e999").render(Format::Json).lines().filter(|line| !line.contains("span")).collect::<Vec<_>>(),
+        [
+            "{",
+            "  \"node\": \"ProgramNode\",",
+            "  \"statements\": [",
+            "    {",
+            "      \"node\": \"FunctionNode\",",
+            "      \"name\": \"f\",",
+            "      \"parameters\": [],",
+            "      \"body\": {",
+            "        \"node\": \"SingleTermNode\",",
+            "        \"term\": {",
+            "          \"node\": \"SingleFactorNode\",",
+            "          \"factor\": {",
+            "            \"node\": \"FloatLiteralNode\",",
+            "            \"value\": \"inf\"",
+            "          }",
+            "        }",
+            "      }",
+            "    }",
+            "  ]",
+            "}",
+        ]
+    );
+}
+
+#[test]
+fn emit_sexpr() {
+    assert_eq!(
+        parse_tree("let a = f(1, 2 > b); fn g(x, y) = true").render(Format::SExpression),
+        "\
+(ProgramNode
+  (LetNode a
+    (SingleTermNode
+      (SingleFactorNode
+        (CallNode
+          (VariableNode f)
+          (SingleTermNode (SingleFactorNode (LiteralNode 1)))
+          (ComparisonNode >
+            (SingleTermNode (SingleFactorNode (LiteralNode 2)))
+            (SingleTermNode (SingleFactorNode (VariableNode b))))))))
+  (FunctionNode g (x y)
+    (SingleTermNode (SingleFactorNode (BoolLiteralNode true)))))
+"
+    );
+}
+
+#[test]
+fn emit_dot() {
+    assert_eq!(
+        parse_tree("x ** 2").render(Format::Dot),
+        r#"digraph ast {
+    node [shape=box];
+    n0 [label="ProgramNode"];
+    n1 [label="ExpressionStatementNode"];
+    n2 [label="SingleTermNode\n1:1-1:7"];
+    n3 [label="SingleFactorNode\n1:1-1:7"];
+    n4 [label="PowerNode\n1:1-1:7"];
+    n5 [label="VariableNode\nname: x\n1:1-1:2"];
+    n4 -> n5 [label="base"];
+    n6 [label="LiteralNode\nvalue: 2\n1:6-1:7"];
+    n4 -> n6 [label="exponent"];
+    n3 -> n4 [label="factor"];
+    n2 -> n3 [label="term"];
+    n1 -> n2 [label="expression"];
+    n0 -> n1 [label="statements[0]"];
+}
+"#
+    );
+}
@@ -0,0 +1,523 @@
+//! Prints syntax trees back as source. The output is canonical: operators
+//! are spaced, calls use argument lists and parentheses appear only where
+//! leaving them out would change the tree. Parsing the output gives the
+//! same tree again, with different spans and parentheses only where the
+//! original tree had redundant ones.
+
+use std::fmt;
+
+use super::syntax_tree::{ComparisonOperator, ExpressionNode, FactorNode, ProgramNode, StatementNode, TermNode};
+
+/// What comes right after a node. `fun` and `if` extend as far to the
+/// right as they can, and a unary minus takes a following `**` into its
+/// operand, so those need parentheses when something follows them.
+#[derive(Clone, Copy, PartialEq)]
+enum Follow {
+    Nothing,
+    Power,
+    Operator,
+}
+
+/// Any node of the tree, so that parentheses can be looked through
+/// whatever kind of node they are in.
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Expression(&'a ExpressionNode),
+    Term(&'a TermNode),
+    Factor(&'a FactorNode),
+}
+
+impl Node<'_> {
+    /// The node itself, without the parentheses and single child wrappers
+    /// around it.
+    fn unwrap(self) -> Self {
+        match self {
+            Node::Expression(ExpressionNode::SingleTermNode(term)) => Node::Term(term).unwrap(),
+            Node::Term(TermNode::SingleFactorNode(factor)) => Node::Factor(factor).unwrap(),
+            Node::Factor(FactorNode::ExpressionNode { expression, .. }) => Node::Expression(expression).unwrap(),
+            node => node,
+        }
+    }
+
+    /// How tightly the node binds, following the parser's precedences.
+    fn level(self) -> u8 {
+        match self.unwrap() {
+            Node::Expression(expression) => match expression {
+                ExpressionNode::OrNode { .. } => 1,
+                ExpressionNode::AndNode { .. } => 2,
+                ExpressionNode::ComparisonNode { .. } => 3,
+                _ => 4,
+            },
+            Node::Term(_) => 5,
+            Node::Factor(factor) => match factor {
+                FactorNode::NegativeExpressionNode { .. } => 6,
+                FactorNode::PowerNode { .. } => 7,
+                FactorNode::NotExpressionNode { .. } => 8,
+                FactorNode::CallNode { .. } => 9,
+                _ => 10,
+            },
+        }
+    }
+
+    fn is_open_to(self, follow: Follow) -> bool {
+        match self.unwrap() {
+            Node::Factor(FactorNode::LambdaNode { .. } | FactorNode::IfNode { .. }) => follow != Follow::Nothing,
+            Node::Factor(FactorNode::NegativeExpressionNode { .. }) => follow == Follow::Power,
+            _ => false,
+        }
+    }
+
+    /// Writes the node, in parentheses if it binds looser than `level` or
+    /// would take in what follows it.
+    fn write_operand(self, f: &mut fmt::Formatter<'_>, level: u8, follow: Follow) -> fmt::Result {
+        match self.level() < level || self.is_open_to(follow) {
+            true => {
+                write!(f, "(")?;
+                self.write(f, Follow::Nothing)?;
+                write!(f, ")")
+            }
+            false => self.write(f, follow),
+        }
+    }
+
+    fn write_binary(
+        f: &mut fmt::Formatter<'_>,
+        (left, operator, right): (Node, &str, Node),
+        level: u8,
+        follow: Follow,
+    ) -> fmt::Result {
+        // every binary operator but `**` is left associative
+        left.write_operand(f, level, Follow::Operator)?;
+        write!(f, " {} ", operator)?;
+        right.write_operand(f, level + 1, follow)
+    }
+
+    fn write(self, f: &mut fmt::Formatter<'_>, follow: Follow) -> fmt::Result {
+        let node = self.unwrap();
+        let level = node.level();
+        match node {
+            Node::Expression(expression) => {
+                let (left, operator, right) = match expression {
+                    ExpressionNode::OrNode { left, right, .. } => (left, "||", Node::Expression(right)),
+                    ExpressionNode::AndNode { left, right, .. } => (left, "&&", Node::Expression(right)),
+                    ExpressionNode::ComparisonNode {
+                        operator, left, right, ..
+                    } => (left, comparison_symbol(*operator), Node::Expression(right)),
+                    ExpressionNode::AdditionTermNode { left, right, .. } => (left, "+", Node::Term(right)),
+                    ExpressionNode::SubstractionTermNode { left, right, .. } => (left, "-", Node::Term(right)),
+                    ExpressionNode::SingleTermNode(_) => unreachable!("unwrapped above"),
+                };
+                Self::write_binary(f, (Node::Expression(left), operator, right), level, follow)
+            }
+            Node::Term(term) => {
+                let (left, operator, right) = match term {
+                    TermNode::MultiplicationFactorNode { left, right, .. } => (left, "*", right),
+                    TermNode::DivisionFactorNode { left, right, .. } => (left, "/", right),
+                    TermNode::IntegerDivisionFactorNode { left, right, .. } => (left, "//", right),
+                    TermNode::ModuloFactorNode { left, right, .. } => (left, "%", right),
+                    TermNode::SingleFactorNode(_) => unreachable!("unwrapped above"),
+                };
+                Self::write_binary(f, (Node::Term(left), operator, Node::Factor(right)), level, follow)
+            }
+            Node::Factor(factor) => match factor {
+                FactorNode::LiteralNode { value, .. } => write!(f, "{}", value),
+                FactorNode::FloatLiteralNode { value, .. } => write_float(f, *value),
+                FactorNode::BoolLiteralNode { value, .. } => write!(f, "{}", value),
+                FactorNode::VariableNode { name, .. } => write!(f, "{}", name),
+                FactorNode::LambdaNode { parameter, body, .. } => {
+                    write!(f, "fun {} -> ", parameter)?;
+                    Node::Expression(body).write(f, Follow::Nothing)
+                }
+                FactorNode::IfNode {
+                    condition,
+                    consequence,
+                    alternative,
+                    ..
+                } => {
+                    write!(f, "if ")?;
+                    Node::Expression(condition).write(f, Follow::Nothing)?;
+                    write!(f, " then ")?;
+                    Node::Expression(consequence).write(f, Follow::Nothing)?;
+                    write!(f, " else ")?;
+                    Node::Expression(alternative).write(f, Follow::Nothing)
+                }
+                FactorNode::CallNode { callee, arguments, .. } => {
+                    Node::Factor(callee).write_operand(f, level, Follow::Operator)?;
+                    write!(f, "(")?;
+                    for (index, argument) in arguments.iter().enumerate() {
+                        if index > 0 {
+                            write!(f, ", ")?;
+                        }
+                        Node::Expression(argument).write(f, Follow::Nothing)?;
+                    }
+                    write!(f, ")")
+                }
+                FactorNode::PowerNode { base, exponent, .. } => {
+                    // right associative, and a negated base has to be in parentheses
+                    Node::Factor(base).write_operand(f, level + 1, Follow::Power)?;
+                    write!(f, " ** ")?;
+                    Node::Factor(exponent).write_operand(f, level - 1, follow)
+                }
+                FactorNode::NegativeExpressionNode { operand, .. } => {
+                    write!(f, "-")?;
+                    Node::Factor(operand).write_operand(f, level, follow)
+                }
+                FactorNode::NotExpressionNode { operand, .. } => {
+                    write!(f, "!")?;
+                    // `!` applies to a single factor, but `!-x` still negates `-x`
+                    let operand = Node::Factor(operand);
+                    let level = match operand.level() {
+                        6 => 6,
+                        _ => level,
+                    };
+                    operand.write_operand(f, level, follow)
+                }
+                FactorNode::ErrorNode { .. } => write!(f, "<error>"),
+                FactorNode::ExpressionNode { .. } => unreachable!("unwrapped above"),
+            },
+        }
+    }
+}
+
+pub(super) fn comparison_symbol(operator: ComparisonOperator) -> &'static str {
+    match operator {
+        ComparisonOperator::Equal => "==",
+        ComparisonOperator::NotEqual => "!=",
+        ComparisonOperator::Less => "<",
+        ComparisonOperator::LessEqual => "<=",
+        ComparisonOperator::Greater => ">",
+        ComparisonOperator::GreaterEqual => ">=",
+    }
+}
+
+/// Floats always get a point or an exponent, so they read back as floats.
+/// Infinity can only come from a literal too large for a float.
+fn write_float(f: &mut fmt::Formatter<'_>, value: f64) -> fmt::Result {
+    match value.is_infinite() {
+        true => write!(f, "{}1e999", if value < 0.0 { "-" } else { "" }),
+        false => write!(f, "{:?}", value),
+    }
+}
+
+impl fmt::Display for ExpressionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Node::Expression(self).write(f, Follow::Nothing)
+    }
+}
+
+impl fmt::Display for TermNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Node::Term(self).write(f, Follow::Nothing)
+    }
+}
+
+impl fmt::Display for FactorNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Node::Factor(self).write(f, Follow::Nothing)
+    }
+}
+
+/// Statements print on a single line each, a syntax error as `<error>`.
+impl fmt::Display for StatementNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LetNode { name, value, .. } => write!(f, "let {} = {}", name, value),
+            Self::FunctionNode {
+                name, parameters, body, ..
+            } => write!(f, "fn {}({}) = {}", name, parameters.join(", "), body),
+            Self::ExpressionStatementNode(exp) => write!(f, "{}", exp),
+            Self::ErrorNode { .. } => write!(f, "<error>"),
+        }
+    }
+}
+
+/// A statement per line, with a `;` after it where the next starts with a
+/// `-`, which would read as continuing it.
+impl fmt::Display for ProgramNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let statements = self.statements.iter().map(ToString::to_string).collect::<Vec<_>>();
+        for (index, statement) in statements.iter().enumerate() {
+            let keep_separator = statements.get(index + 1).is_some_and(|next| next.starts_with('-'));
+            writeln!(f, "{}{}", statement, if keep_separator { ";" } else { "" })?;
+        }
+        Ok(())
+    }
+}
+
+/// The tree as an S-expression without spans and parentheses, two trees
+/// are the same up to those when their shapes are equal.
+#[cfg(test)]
+fn shape(node: Node) -> String {
+    let node = node.unwrap();
+    let binary = |operator: &str, left: Node, right: Node| format!("({} {} {})", operator, shape(left), shape(right));
+    match node {
+        Node::Expression(expression) => match expression {
+            ExpressionNode::OrNode { left, right, .. } => binary("||", Node::Expression(left), Node::Expression(right)),
+            ExpressionNode::AndNode { left, right, .. } => binary("&&", Node::Expression(left), Node::Expression(right)),
+            ExpressionNode::ComparisonNode {
+                operator, left, right, ..
+            } => binary(comparison_symbol(*operator), Node::Expression(left), Node::Expression(right)),
+            ExpressionNode::AdditionTermNode { left, right, .. } => binary("+", Node::Expression(left), Node::Term(right)),
+            ExpressionNode::SubstractionTermNode { left, right, .. } => binary("-", Node::Expression(left), Node::Term(right)),
+            ExpressionNode::SingleTermNode(_) => unreachable!(),
+        },
+        Node::Term(term) => match term {
+            TermNode::MultiplicationFactorNode { left, right, .. } => binary("*", Node::Term(left), Node::Factor(right)),
+            TermNode::DivisionFactorNode { left, right, .. } => binary("/", Node::Term(left), Node::Factor(right)),
+            TermNode::IntegerDivisionFactorNode { left, right, .. } => binary("//", Node::Term(left), Node::Factor(right)),
+            TermNode::ModuloFactorNode { left, right, .. } => binary("%", Node::Term(left), Node::Factor(right)),
+            TermNode::SingleFactorNode(_) => unreachable!(),
+        },
+        Node::Factor(factor) => match factor {
+            FactorNode::LiteralNode { value, .. } => value.to_string(),
+            FactorNode::FloatLiteralNode { value, .. } => format!("{:?}", value),
+            FactorNode::BoolLiteralNode { value, .. } => value.to_string(),
+            FactorNode::VariableNode { name, .. } => name.clone(),
+            FactorNode::LambdaNode { parameter, body, .. } => format!("(fun {} {})", parameter, shape(Node::Expression(body))),
+            FactorNode::IfNode {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => format!(
+                "(if {} {} {})",
+                shape(Node::Expression(condition)),
+                shape(Node::Expression(consequence)),
+                shape(Node::Expression(alternative))
+            ),
+            FactorNode::CallNode { callee, arguments, .. } => {
+                let arguments = arguments.iter().map(|argument| format!(" {}", shape(Node::Expression(argument))));
+                format!("(call {}{})", shape(Node::Factor(callee)), arguments.collect::<String>())
+            }
+            FactorNode::PowerNode { base, exponent, .. } => binary("**", Node::Factor(base), Node::Factor(exponent)),
+            FactorNode::NegativeExpressionNode { operand, .. } => format!("(neg {})", shape(Node::Factor(operand))),
+            FactorNode::NotExpressionNode { operand, .. } => format!("(not {})", shape(Node::Factor(operand))),
+            FactorNode::ErrorNode { .. } => "error".to_string(),
+            FactorNode::ExpressionNode { .. } => unreachable!(),
+        },
+    }
+}
+
+#[cfg(test)]
+fn statement_shape(statement: &StatementNode) -> String {
+    match statement {
+        StatementNode::LetNode { name, value, .. } => format!("(let {} {})", name, shape(Node::Expression(value))),
+        StatementNode::FunctionNode {
+            name, parameters, body, ..
+        } => format!("(fn {} ({}) {})", name, parameters.join(" "), shape(Node::Expression(body))),
+        StatementNode::ExpressionStatementNode(expression) => shape(Node::Expression(expression)),
+        StatementNode::ErrorNode { .. } => "error".to_string(),
+    }
+}
+
+#[cfg(test)]
+fn parse_expression(source: &str) -> ExpressionNode {
+    match crate::parser::parse_valid_program(source).statements.as_slice() {
+        [StatementNode::ExpressionStatementNode(expression)] => expression.clone(),
+        statements => panic!("{} does not parse as an expression: {:?}", source, statements),
+    }
+}
+
+#[test]
+fn print_with_minimal_parentheses() {
+    for (source, printed) in [
+        ("((1)) + (2 * 3)", "1 + 2 * 3"),
+        ("(1 + 2) * 3", "(1 + 2) * 3"),
+        ("1 - (2 - 3) - 4", "1 - (2 - 3) - 4"),
+        ("(2 ** 3) ** 2", "(2 ** 3) ** 2"),
+        ("2 ** (3 ** 2)", "2 ** 3 ** 2"),
+        ("-2 ** 2", "-2 ** 2"),
+        ("(-2) ** 2", "(-2) ** 2"),
+        ("2 ** -x", "2 ** -x"),
+        ("!(-x) ** 2", "!(-x) ** 2"),
+        ("!(x ** 2)", "!(x ** 2)"),
+        ("!-x", "!-x"),
+        ("- -x * y", "--x * y"),
+        ("a || (b && c) || !(d == e)", "a || b && c || !(d == e)"),
+        ("(a || b) && (1 < 2) == true", "(a || b) && 1 < 2 == true"),
+        ("a == (b == c)", "a == (b == c)"),
+        ("f 1 (2) (g x)", "f(1)(2)(g(x))"),
+        ("(fun x -> x) + 1", "(fun x -> x) + 1"),
+        ("1 + (fun x -> x)", "1 + fun x -> x"),
+        ("(if a then 1 else 2)(3)", "(if a then 1 else 2)(3)"),
+        ("if fun x -> x then (fun y -> y) else fun z -> z + 1", "if fun x -> x then fun y -> y else fun z -> z + 1"),
+        ("-(f(x))", "-f(x)"),
+        ("(-f)(x)", "(-f)(x)"),
+        ("2.0 * .5 + 3e-4", "2.0 * 0.5 + 0.0003"),
+    ] {
+        let expression = parse_expression(source);
+        assert_eq!(expression.to_string(), printed, "{}", source);
+        assert_eq!(shape(Node::Expression(&parse_expression(printed))), shape(Node::Expression(&expression)));
+    }
+}
+
+#[test]
+fn print_statements() {
+    let (program, _) = crate::parser::parse_program_str("let  x=(1)\nfn f(a,b)=a*b;f(x , 2)\n1 +");
+    assert_eq!(program.to_string(), "let x = 1\nfn f(a, b) = a * b\nf(x, 2)\n<error>\n");
+
+    // a leading `-` on its own line would read as continuing the statement before
+    let program = crate::parser::parse_valid_program("a; -1; let b = 2; -b; fn f(x) = x; - f(1)");
+    assert_eq!(program.to_string(), "a;\n-1\nlet b = 2;\n-b\nfn f(x) = x;\n-f(1)\n");
+}
+
+/// A xorshift generator, so that every run checks the same trees.
+#[cfg(test)]
+struct Random(u64);
+
+#[cfg(test)]
+impl Random {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+
+    fn boxed<T>(&mut self, depth: usize, into: impl Fn(ExpressionNode) -> T) -> Box<T> {
+        Box::new(into(self.expression(depth)))
+    }
+
+    /// A tree of at most `depth` levels, with every kind of node.
+    fn expression(&mut self, depth: usize) -> ExpressionNode {
+        use crate::lexer::token::{Position, Span};
+
+        let position = Position { column: 1, row: 1, offset: 0 };
+        let span = Span { start: position, end: position };
+        let names = ["x", "y", "f"];
+        let kind = match depth {
+            0 => self.below(4),
+            _ => self.below(22),
+        };
+        let depth = depth.saturating_sub(1);
+        let factor = match kind {
+            0 => FactorNode::LiteralNode {
+                value: (self.below(1000) as i32).into(),
+                span,
+            },
+            1 => FactorNode::FloatLiteralNode {
+                value: [0.5, 2.0, 1e-7, 1.5e300, 3.25][self.below(5)],
+                span,
+            },
+            2 => FactorNode::BoolLiteralNode {
+                value: self.below(2) == 0,
+                span,
+            },
+            3 => FactorNode::VariableNode {
+                name: names[self.below(3)].to_string(),
+                span,
+            },
+            4 => FactorNode::LambdaNode {
+                parameter: names[self.below(3)].to_string(),
+                fun_span: span,
+                parameter_span: span,
+                body: self.expression(depth).into(),
+            },
+            5 => FactorNode::IfNode {
+                condition: self.boxed(depth, |e| e),
+                consequence: self.boxed(depth, |e| e),
+                alternative: self.boxed(depth, |e| e),
+                if_span: span,
+                else_span: span,
+                widen: Default::default(),
+            },
+            6 => FactorNode::CallNode {
+                callee: self.boxed(depth, ExpressionNode::into_factor),
+                arguments: (0..self.below(3)).map(|_| self.expression(depth)).collect(),
+                arguments_span: span,
+            },
+            7 => FactorNode::PowerNode {
+                base: self.boxed(depth, ExpressionNode::into_factor),
+                exponent: self.boxed(depth, ExpressionNode::into_factor),
+                operator_span: span,
+            },
+            8 => FactorNode::NegativeExpressionNode {
+                operand: self.boxed(depth, ExpressionNode::into_factor),
+                operator_span: span,
+            },
+            9 => FactorNode::NotExpressionNode {
+                operand: self.boxed(depth, ExpressionNode::into_factor),
+                operator_span: span,
+            },
+            10 => FactorNode::parenthesised(self.expression(depth)),
+            11..=14 => {
+                let (left, right) = (self.boxed(depth, ExpressionNode::into_term), self.expression(depth).into_factor());
+                let operator_span = span;
+                let term = match kind {
+                    11 => TermNode::MultiplicationFactorNode { left, right, operator_span },
+                    12 => TermNode::DivisionFactorNode { left, right, operator_span },
+                    13 => TermNode::IntegerDivisionFactorNode { left, right, operator_span },
+                    _ => TermNode::ModuloFactorNode { left, right, operator_span },
+                };
+                return term.into();
+            }
+            _ => {
+                let (left, right) = (self.boxed(depth, |e| e), self.expression(depth));
+                let operator_span = span;
+                return match kind {
+                    15 => ExpressionNode::OrNode { left, right: right.into(), operator_span },
+                    16 => ExpressionNode::AndNode { left, right: right.into(), operator_span },
+                    17 => ExpressionNode::AdditionTermNode { left, right: right.into_term(), operator_span },
+                    18 => ExpressionNode::SubstractionTermNode { left, right: right.into_term(), operator_span },
+                    _ => ExpressionNode::ComparisonNode {
+                        operator: [ComparisonOperator::Less, ComparisonOperator::Equal, ComparisonOperator::GreaterEqual]
+                            [kind - 19],
+                        left,
+                        right: right.into(),
+                        operator_span,
+                    },
+                };
+            }
+        };
+        factor.into()
+    }
+
+    fn statement(&mut self, depth: usize) -> StatementNode {
+        use crate::lexer::token::{Position, Span};
+
+        let position = Position { column: 1, row: 1, offset: 0 };
+        let name_span = Span { start: position, end: position };
+        let name = ["x", "y", "f"][self.below(3)].to_string();
+        match self.below(4) {
+            0 => StatementNode::LetNode {
+                name,
+                name_span,
+                value: self.expression(depth),
+            },
+            1 => StatementNode::FunctionNode {
+                name,
+                name_span,
+                parameters: ["a", "b"][..self.below(3)].iter().map(ToString::to_string).collect(),
+                body: self.expression(depth).into(),
+            },
+            _ => StatementNode::ExpressionStatementNode(self.expression(depth)),
+        }
+    }
+}
+
+#[test]
+fn printed_trees_parse_back_to_themselves() {
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    for _ in 0..10000 {
+        let expression = random.expression(5);
+        let printed = expression.to_string();
+        let parsed = parse_expression(&printed);
+        assert_eq!(shape(Node::Expression(&parsed)), shape(Node::Expression(&expression)), "{}", printed);
+        assert_eq!(parsed.to_string(), printed);
+    }
+}
+
+#[test]
+fn printed_programs_parse_back_to_themselves() {
+    let mut random = Random(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..2000 {
+        let program = ProgramNode {
+            statements: (0..1 + random.below(4)).map(|_| random.statement(3)).collect(),
+        };
+        let printed = program.to_string();
+        let parsed = crate::parser::parse_valid_program(&printed);
+        let shapes = |program: &ProgramNode| program.statements.iter().map(statement_shape).collect::<Vec<_>>();
+        assert_eq!(shapes(&parsed), shapes(&program), "{}", printed);
+        assert_eq!(parsed.to_string(), printed);
+    }
+}
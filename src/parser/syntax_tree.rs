@@ -1,77 +1,304 @@
-use crate::lexer::token::{Token, TokenKind};
+use std::{cell::Cell, rc::Rc};
 
+use crate::{
+    lexer::token::{Span, Token, TokenKind},
+    numeric::bigint::BigInt,
+};
+
+#[derive(Debug)]
 pub struct InvalidExpressionNode {
     pub expected: TokenKind,
     pub got: Option<Token>,
 }
 
 impl InvalidExpressionNode {
-    pub fn describe(&self) -> String {
-        if let Some(token) = &self.got {
-            return format!("Expected {:?}, got: {:?} ({:?}).", self.expected, token.kind, token.start_position);
+    /// Span of the unexpected token, `None` when the input ended early.
+    pub fn span(&self) -> Option<Span> {
+        self.got.as_ref().map(Token::span)
+    }
+
+    pub fn code(&self) -> &'static str {
+        match &self.got {
+            Some(token) if token.kind == TokenKind::ReadError => "E0003",
+            Some(_) => "E0001",
+            None => "E0002",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match &self.got {
+            Some(token) if token.kind == TokenKind::ReadError => format!("Could not read the input: {}", token.lexem),
+            Some(token) => format!("Expected {}, found {}", self.expected.describe(), token.describe()),
+            None => format!("Unexpected end of input, expected {}", self.expected.describe()),
         }
-        else {
-            return format!("Unexpected file end - expected {:?}.", self.expected);
+    }
+}
+
+/// Statements of a whole input, in order.
+#[derive(Debug, PartialEq)]
+pub struct ProgramNode {
+    pub statements: Vec<StatementNode>,
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatementNode {
+    LetNode {
+        name: String,
+        name_span: Span,
+        value: ExpressionNode,
+    },
+    FunctionNode {
+        name: String,
+        name_span: Span,
+        parameters: Vec<String>,
+        body: Rc<ExpressionNode>,
+    },
+    ExpressionStatementNode(ExpressionNode),
+    /// Stands in for a statement that could not be parsed.
+    ErrorNode {
+        span: Span,
+    },
+}
+
+impl StatementNode {
+    /// Name the statement binds in the environment, if any.
+    pub fn bound_name(&self) -> Option<&str> {
+        match self {
+            Self::LetNode { name, .. } | Self::FunctionNode { name, .. } => Some(name),
+            Self::ExpressionStatementNode(_) | Self::ErrorNode { .. } => None,
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ComparisonOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ExpressionNode {
     SingleTermNode(TermNode),
-    AdditionTermNode {
-        left: TermNode,
+    /// `left || right`, the right side is only evaluated when the left one is false.
+    OrNode {
+        left: Box<ExpressionNode>,
         right: Box<ExpressionNode>,
+        operator_span: Span,
     },
-    SubstractionTermNode {
-        left: TermNode,
+    /// `left && right`, the right side is only evaluated when the left one is true.
+    AndNode {
+        left: Box<ExpressionNode>,
+        right: Box<ExpressionNode>,
+        operator_span: Span,
+    },
+    ComparisonNode {
+        operator: ComparisonOperator,
+        left: Box<ExpressionNode>,
         right: Box<ExpressionNode>,
+        operator_span: Span,
+    },
+    AdditionTermNode {
+        left: Box<ExpressionNode>,
+        right: TermNode,
+        operator_span: Span,
+    },
+    SubstractionTermNode {
+        left: Box<ExpressionNode>,
+        right: TermNode,
+        operator_span: Span,
     },
 }
 
 impl ExpressionNode {
-    pub fn evaluate(&self) -> i32 {
+    /// Narrows the expression down to a term, wrapping it in parentheses
+    /// if it is not a single term already.
+    pub fn into_term(self) -> TermNode {
+        match self {
+            Self::SingleTermNode(t) => t,
+            exp => TermNode::SingleFactorNode(FactorNode::parenthesised(exp)),
+        }
+    }
+
+    /// Narrows the expression down to a factor, wrapping it in parentheses
+    /// if it is not a single factor already.
+    pub fn into_factor(self) -> FactorNode {
         match self {
-            Self::SingleTermNode(t) => t.evaluate(),
-            Self::AdditionTermNode { left, right } => left.evaluate() + right.evaluate(),
-            Self::SubstractionTermNode { left, right } => left.evaluate() - right.evaluate(),
+            Self::SingleTermNode(t) => t.into_factor(),
+            exp => FactorNode::parenthesised(exp),
         }
     }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Self::SingleTermNode(term) => term.span(),
+            Self::OrNode { left, right, .. }
+            | Self::AndNode { left, right, .. }
+            | Self::ComparisonNode { left, right, .. } => left.span().to(right.span()),
+            Self::AdditionTermNode { left, right, .. } | Self::SubstractionTermNode { left, right, .. } => {
+                left.span().to(right.span())
+            }
+        }
+    }
+}
+
+impl From<TermNode> for ExpressionNode {
+    fn from(term: TermNode) -> Self {
+        Self::SingleTermNode(term)
+    }
+}
+
+impl From<FactorNode> for ExpressionNode {
+    fn from(factor: FactorNode) -> Self {
+        Self::SingleTermNode(TermNode::SingleFactorNode(factor))
+    }
 }
 
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum TermNode {
     SingleFactorNode(FactorNode),
     MultiplicationFactorNode {
-        left: FactorNode,
-        right: Box<TermNode>,
+        left: Box<TermNode>,
+        right: FactorNode,
+        operator_span: Span,
     },
     DivisionFactorNode {
-        left: FactorNode,
-        right: Box<TermNode>,
+        left: Box<TermNode>,
+        right: FactorNode,
+        operator_span: Span,
+    },
+    IntegerDivisionFactorNode {
+        left: Box<TermNode>,
+        right: FactorNode,
+        operator_span: Span,
+    },
+    ModuloFactorNode {
+        left: Box<TermNode>,
+        right: FactorNode,
+        operator_span: Span,
     },
 }
 
 impl TermNode {
-    pub fn evaluate(&self) -> i32 {
+    pub fn into_factor(self) -> FactorNode {
+        match self {
+            Self::SingleFactorNode(f) => f,
+            term => FactorNode::parenthesised(ExpressionNode::SingleTermNode(term)),
+        }
+    }
+
+    pub fn span(&self) -> Span {
         match self {
-            Self::SingleFactorNode(t) => t.evaluate(),
-            Self::MultiplicationFactorNode { left, right } => left.evaluate() * right.evaluate(),
-            Self::DivisionFactorNode { left, right } => left.evaluate() / right.evaluate(),
+            Self::SingleFactorNode(factor) => factor.span(),
+            Self::MultiplicationFactorNode { left, right, .. }
+            | Self::DivisionFactorNode { left, right, .. }
+            | Self::IntegerDivisionFactorNode { left, right, .. }
+            | Self::ModuloFactorNode { left, right, .. } => left.span().to(right.span()),
         }
     }
 }
 
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum FactorNode {
-    LiteralNode(i32),
-    ExpressionNode(Box<ExpressionNode>),
-    NegativeExpressionNode(Box<FactorNode>),
+    LiteralNode {
+        value: BigInt,
+        span: Span,
+    },
+    FloatLiteralNode {
+        value: f64,
+        span: Span,
+    },
+    BoolLiteralNode {
+        value: bool,
+        span: Span,
+    },
+    VariableNode {
+        name: String,
+        span: Span,
+    },
+    LambdaNode {
+        parameter: String,
+        fun_span: Span,
+        parameter_span: Span,
+        body: Rc<ExpressionNode>,
+    },
+    /// `if condition then consequence else alternative`, only the selected
+    /// branch is evaluated.
+    IfNode {
+        condition: Box<ExpressionNode>,
+        consequence: Box<ExpressionNode>,
+        alternative: Box<ExpressionNode>,
+        if_span: Span,
+        else_span: Span,
+        /// Set by the type checker when one branch is an integer and the
+        /// other a float, the value is then converted to a float.
+        widen: Cell<bool>,
+    },
+    CallNode {
+        callee: Box<FactorNode>,
+        arguments: Vec<ExpressionNode>,
+        arguments_span: Span,
+    },
+    /// An expression in parentheses, the span includes the parentheses.
+    ExpressionNode {
+        expression: Box<ExpressionNode>,
+        span: Span,
+    },
+    /// `base ** exponent`, binds tighter than a unary minus on its left.
+    PowerNode {
+        base: Box<FactorNode>,
+        exponent: Box<FactorNode>,
+        operator_span: Span,
+    },
+    NegativeExpressionNode {
+        operand: Box<FactorNode>,
+        operator_span: Span,
+    },
+    NotExpressionNode {
+        operand: Box<FactorNode>,
+        operator_span: Span,
+    },
+    /// Stands in for an expression that could not be parsed.
+    ErrorNode {
+        span: Span,
+    },
 }
 
 impl FactorNode {
-    pub fn evaluate(&self) -> i32 {
+    /// Wraps an expression in parentheses that do not appear in the source.
+    pub fn parenthesised(expression: ExpressionNode) -> Self {
+        Self::ExpressionNode {
+            span: expression.span(),
+            expression: Box::new(expression),
+        }
+    }
+
+    pub fn span(&self) -> Span {
         match self {
-            Self::LiteralNode(value) => value.clone(),
-            Self::ExpressionNode(exp) => exp.evaluate(),
-            Self::NegativeExpressionNode(exp) => -exp.evaluate(),
+            Self::LiteralNode { span, .. }
+            | Self::FloatLiteralNode { span, .. }
+            | Self::BoolLiteralNode { span, .. }
+            | Self::VariableNode { span, .. }
+            | Self::ExpressionNode { span, .. }
+            | Self::ErrorNode { span } => *span,
+            Self::LambdaNode { fun_span, body, .. } => fun_span.to(body.span()),
+            Self::IfNode {
+                if_span, alternative, ..
+            } => if_span.to(alternative.span()),
+            Self::CallNode {
+                callee, arguments_span, ..
+            } => callee.span().to(*arguments_span),
+            Self::PowerNode { base, exponent, .. } => base.span().to(exponent.span()),
+            Self::NegativeExpressionNode { operand, operator_span } | Self::NotExpressionNode { operand, operator_span } => {
+                operator_span.to(operand.span())
+            }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,381 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
+
+use self::{editor::Input, history::History};
+use crate::{
+    diagnostics::Style,
+    engine::{Engine, Error},
+    lexer::{source_map::SourceMap, Lexer, KEYWORDS},
+    parser::{
+        syntax_tree::{ProgramNode, StatementNode},
+        Parser,
+    },
+    typechecker::types::TypeScheme,
+    vm,
+};
+
+pub mod editor;
+pub mod history;
+
+/// Name the result of the last expression statement is bound to.
+const LAST_RESULT: &str = "_";
+
+const COMMANDS: [&str; 10] = [":tokens", ":ast", ":opt", ":type", ":bytecode", ":load", ":reset", ":history", ":help", ":quit"];
+
+const HELP: &str = "\
+:tokens INPUT    show the tokens of the input
+:ast INPUT       show the syntax tree of the input
+:opt INPUT       show the input before and after optimizing it
+:type INPUT      show the type of the input without running it
+:bytecode INPUT  show the compiled bytecode of the input
+:load FILE       run a script in this session
+:reset           forget all bindings
+:history         show the inputs entered so far
+:help            show this message
+:quit            leave the session";
+
+/// An engine driven by text commands, shared by the REPL and script runs.
+/// Results are written to `out`, errors to `err`.
+pub struct Session<Out: Write, Err: Write> {
+    engine: Engine,
+    style: Style,
+    out: Out,
+    err: Err,
+}
+
+impl<Out: Write, Err: Write> Session<Out, Err> {
+    pub fn new(engine: Engine, style: Style, out: Out, err: Err) -> Self {
+        Self {
+            engine,
+            style,
+            out,
+            err,
+        }
+    }
+
+    /// Parses the source as a program and runs its statements, stopping at
+    /// the first error. Returns whether all of them ran.
+    pub fn run_source(&mut self, name: String, text: String) -> io::Result<bool> {
+        match self.parse(name, text)? {
+            Some(program) => self.run_program(&program),
+            None => Ok(false),
+        }
+    }
+
+    fn run_program(&mut self, program: &ProgramNode) -> io::Result<bool> {
+        for statement in &program.statements {
+            match self.engine.execute(statement) {
+                Ok((value, scheme)) => {
+                    if let StatementNode::ExpressionStatementNode(_) = statement {
+                        self.engine.bind(LAST_RESULT, value.clone(), scheme.clone());
+                    }
+                    let value = value.format(self.engine.context().rational_format);
+                    match statement.bound_name() {
+                        Some(name) => writeln!(self.out, "{} : {} = {}", name, scheme, value)?,
+                        None => writeln!(self.out, "Expression evaluated to: {} : {}", value, scheme)?,
+                    }
+                }
+                Err(error) => {
+                    self.report(&error)?;
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Runs a script file in this session.
+    pub fn load(&mut self, path: &Path) -> io::Result<bool> {
+        match File::open(path) {
+            Ok(file) => match self.engine.parse_reader(path.display().to_string(), file) {
+                Ok(program) => self.run_program(&program),
+                Err(error) => self.report(&error).map(|_| false),
+            },
+            Err(error) => {
+                writeln!(self.err, "Could not read {}: {}", path.display(), error)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Runs a line starting with `:`. Returns false once the session should end.
+    pub fn command(&mut self, line: &str) -> io::Result<bool> {
+        let line = line.trim();
+        let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+        match command {
+            ":tokens" => {
+                // the lexer alone never fails, so the input is not parsed
+                let mut sources = SourceMap::default();
+                let file = sources.add("", argument);
+                for token in Lexer::from_source_file(file).into_tokens() {
+                    // the lexem already shows the value of a literal
+                    let kind = format!("{:?}", token.kind);
+                    let kind = kind.split('(').next().unwrap_or_default();
+                    writeln!(
+                        self.out,
+                        "{}:{} {} `{}`",
+                        token.start_position.row, token.start_position.column, kind, token.lexem
+                    )?;
+                }
+            }
+            ":ast" => {
+                let name = self.engine.next_input_name();
+                if let Some(program) = self.parse(name, argument.to_string())? {
+                    for statement in &program.statements {
+                        writeln!(self.out, "{:#?}", statement)?;
+                    }
+                }
+            }
+            ":opt" => {
+                let name = self.engine.next_input_name();
+                if let Some(program) = self.parse(name, argument.to_string())? {
+                    // only code that type checks is optimized
+                    if let Err(error) = self.engine.check(&program.statements) {
+                        return self.report(&error).map(|_| true);
+                    }
+                    let optimized = self.engine.optimize(program.statements.clone());
+                    for (before, after) in program.statements.iter().zip(&optimized) {
+                        writeln!(self.out, "before: {}\nafter:  {}", before, after)?;
+                    }
+                }
+            }
+            ":type" => {
+                let name = self.engine.next_input_name();
+                if let Some(program) = self.parse(name, argument.to_string())? {
+                    match self.engine.check(&program.statements) {
+                        Ok(schemes) => {
+                            for (statement, scheme) in program.statements.iter().zip(schemes) {
+                                writeln!(self.out, "{}", describe(statement, &scheme))?;
+                            }
+                        }
+                        Err(error) => self.report(&error)?,
+                    }
+                }
+            }
+            ":bytecode" => {
+                let name = self.engine.next_input_name();
+                if let Some(program) = self.parse(name, argument.to_string())? {
+                    for statement in &program.statements {
+                        // a function shows its body, compiled on its first call
+                        let expression = match statement {
+                            StatementNode::LetNode { value, .. } => value,
+                            StatementNode::FunctionNode { body, .. } => body,
+                            StatementNode::ExpressionStatementNode(exp) => exp,
+                            StatementNode::ErrorNode { .. } => continue,
+                        };
+                        if let Some(name) = statement.bound_name() {
+                            writeln!(self.out, "{}:", name)?;
+                        }
+                        write!(self.out, "{}", vm::compile(expression, self.engine.context()))?;
+                    }
+                }
+            }
+            ":load" if !argument.is_empty() => {
+                self.load(Path::new(argument))?;
+            }
+            ":reset" => {
+                self.engine.reset();
+                writeln!(self.out, "All bindings forgotten.")?;
+            }
+            ":help" => writeln!(self.out, "{}", HELP)?,
+            ":quit" => return Ok(false),
+            _ => writeln!(self.err, "Unknown command: {}, try :help.", line)?,
+        }
+        Ok(true)
+    }
+
+    /// Keywords, meta-commands and bound names, for completion.
+    pub fn completion_words(&self) -> Vec<String> {
+        KEYWORDS
+            .iter()
+            .chain(COMMANDS.iter())
+            .copied()
+            .chain(self.engine.names())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Parses the source, reporting syntax errors.
+    fn parse(&mut self, name: String, text: String) -> io::Result<Option<ProgramNode>> {
+        match self.engine.parse(name, text) {
+            Ok(program) => Ok(Some(program)),
+            Err(error) => self.report(&error).map(|_| None),
+        }
+    }
+
+    fn report(&mut self, error: &Error) -> io::Result<()> {
+        writeln!(self.err, "{}", self.engine.render(error, self.style))
+    }
+}
+
+/// `name : type` for bindings, the bare type for expressions.
+fn describe(statement: &StatementNode, scheme: &TypeScheme) -> String {
+    match statement.bound_name() {
+        Some(name) => format!("{} : {}", name, scheme),
+        None => scheme.to_string(),
+    }
+}
+
+/// Whether the parser ran out of input, so that more lines could complete it.
+pub fn is_incomplete(text: &str) -> bool {
+    let (_, errors) = Parser::from_tokens(Lexer::from_str(text).into_tokens()).parse_program();
+    errors.iter().any(|error| error.got.is_none())
+}
+
+/// Reads inputs until the end of `input` or `:quit`. An input that is
+/// incomplete continues on the next line, an empty line ends it regardless
+/// and Ctrl-C abandons it.
+pub fn run<Out: Write, Err: Write>(
+    session: &mut Session<Out, Err>,
+    input: &mut impl Input,
+    history: &mut History,
+) -> io::Result<()> {
+    let mut buffer = String::new();
+    loop {
+        let prompt = if buffer.is_empty() { "> " } else { "... " };
+        let words = session.completion_words();
+        let line = match input.read_line(&mut session.out, prompt, history.entries(), &words) {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {
+                buffer.clear();
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+
+        if buffer.is_empty() && line.trim_start().starts_with(':') {
+            history.add(line.trim());
+            if line.trim() == ":history" {
+                for entry in history.entries() {
+                    writeln!(session.out, "{}", entry)?;
+                }
+            } else if !session.command(&line)? {
+                return Ok(());
+            }
+            continue;
+        }
+
+        let ends_input = line.trim().is_empty();
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        buffer.push_str(&line);
+        if buffer.trim().is_empty() || (!ends_input && is_incomplete(&buffer)) {
+            if buffer.trim().is_empty() {
+                buffer.clear();
+            }
+            continue;
+        }
+
+        let entry = std::mem::take(&mut buffer);
+        history.add(entry.trim_end());
+        let name = session.engine.next_input_name();
+        session.run_source(name, entry)?;
+    }
+
+    // whatever was left unfinished gets its errors reported
+    if !buffer.trim().is_empty() {
+        let name = session.engine.next_input_name();
+        session.run_source(name, buffer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+fn run_lines(lines: &str) -> (String, String) {
+    let mut session = Session::new(Engine::default(), Style::Plain, Vec::new(), Vec::new());
+    let mut input = editor::PlainInput::new(lines.as_bytes(), false);
+    run(&mut session, &mut input, &mut History::default()).unwrap();
+    (
+        String::from_utf8(session.out).unwrap(),
+        String::from_utf8(session.err).unwrap(),
+    )
+}
+
+#[test]
+fn incomplete_input() {
+    assert!(is_incomplete("(1 +"));
+    assert!(is_incomplete("fn f(x) ="));
+    assert!(is_incomplete("if x then 1"));
+    assert!(!is_incomplete("1 + 2"));
+    assert!(!is_incomplete("1 + )"));
+    assert!(!is_incomplete(""));
+}
+
+#[test]
+fn continue_incomplete_input_on_the_next_line() {
+    let (out, err) = run_lines("fn f(x) =\n  x * (2\n  + 1)\nf(2)\n(1 +\n\n");
+    assert_eq!(
+        out,
+        "f : int -> int = <fn f(x)>\nExpression evaluated to: 6 : int\n"
+    );
+    assert!(err.starts_with("error[E0002]: Unexpected end of input"), "{}", err);
+}
+
+#[test]
+fn write_prompts_to_the_session_output() {
+    let mut session = Session::new(Engine::default(), Style::Plain, Vec::new(), Vec::new());
+    let mut input = editor::PlainInput::new("(1 +\n 2)\n".as_bytes(), true);
+    run(&mut session, &mut input, &mut History::default()).unwrap();
+    assert_eq!(String::from_utf8(session.out).unwrap(), "> ... Expression evaluated to: 3 : int\n> ");
+}
+
+#[test]
+fn bind_the_last_result() {
+    let (out, err) = run_lines("2 * 3\n_ + 1\nlet x = 10\n_\n1 / 0\n_");
+    assert_eq!(err.matches("error[E0201]").count(), 1);
+    assert_eq!(
+        out,
+        "Expression evaluated to: 6 : int\nExpression evaluated to: 7 : int\nx : int = 10\n\
+         Expression evaluated to: 7 : int\nExpression evaluated to: 7 : int\n"
+    );
+}
+
+#[test]
+fn meta_commands() {
+    let (out, err) = run_lines(":tokens x >= 1\n:type fun x -> x\nlet y = 2.5\n:type let z = y\nz\n:reset\ny\n:nope");
+    assert_eq!(
+        out,
+        "1:1 Identifier `x`\n1:3 GreaterEqualOperator `>=`\n1:6 IntLiteral `1`\n'a -> 'a\n\
+         y : float = 2.5\nz : float\nAll bindings forgotten.\n"
+    );
+    let errors = err.lines().filter(|line| line.starts_with("error") || line.starts_with("Unknown")).collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            "error[E0105]: Undefined variable z",
+            "error[E0105]: Undefined variable y",
+            "Unknown command: :nope, try :help."
+        ]
+    );
+
+    let (out, _) = run_lines(":bytecode fn f(x) = -x; 1\n:bytecode");
+    assert_eq!(out, "f:\n0000  load           0  x\n0001  negate\n0002  return\n0000  int            1\n0001  return\n");
+
+    let (out, err) = run_lines("let x = 4\n:opt fn f(y) = (x * 1 + y) * (2 ** 3)\n:opt true + 1");
+    assert_eq!(out, "x : int = 4\nbefore: fn f(y) = (x * 1 + y) * 2 ** 3\nafter:  fn f(y) = (x + y) * 8\n");
+    assert!(err.starts_with("error[E0103]"), "{}", err);
+
+    let (out, _) = run_lines(":ast -1\n:quit\n1");
+    assert!(out.starts_with("ExpressionStatementNode(\n    SingleTermNode(\n        SingleFactorNode(\n            NegativeExpressionNode {"));
+    assert!(!out.contains("Expression evaluated to"));
+}
+
+#[test]
+fn load_scripts() {
+    let path = std::env::temp_dir().join(format!("mlor_load_test_{}.mlor", std::process::id()));
+    std::fs::write(&path, "let a = 4\nfn double(x) = 2 * x").unwrap();
+
+    let (out, err) = run_lines(&format!(":load {}\ndouble(a)\n:load /nonexistent.mlor", path.display()));
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(
+        out,
+        "a : int = 4\ndouble : int -> int = <fn double(x)>\nExpression evaluated to: 8 : int\n"
+    );
+    assert!(err.starts_with("Could not read /nonexistent.mlor"));
+}
@@ -0,0 +1,441 @@
+use std::io::{self, BufRead, Read, Write};
+
+use self::raw_mode::RawMode;
+
+mod raw_mode;
+
+/// Source of REPL input lines.
+pub trait Input {
+    /// Reads a line without its line break, `None` at the end of input. A
+    /// line abandoned with Ctrl-C is an `Interrupted` error. The prompt and
+    /// any echo go to `output`, `history` is offered for recall and `words`
+    /// for completion, oldest first.
+    fn read_line(
+        &mut self,
+        output: &mut dyn Write,
+        prompt: &str,
+        history: &[String],
+        words: &[String],
+    ) -> io::Result<Option<String>>;
+}
+
+/// Reads lines as they come, used when stdin is not a terminal or the
+/// terminal cannot be switched to raw mode.
+pub struct PlainInput<R: BufRead> {
+    reader: R,
+    prompt: bool,
+}
+
+impl<R: BufRead> PlainInput<R> {
+    pub fn new(reader: R, prompt: bool) -> Self {
+        Self { reader, prompt }
+    }
+}
+
+impl<R: BufRead> Input for PlainInput<R> {
+    fn read_line(&mut self, output: &mut dyn Write, prompt: &str, _: &[String], _: &[String]) -> io::Result<Option<String>> {
+        if self.prompt {
+            write!(output, "{}", prompt)?;
+            output.flush()?;
+        }
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let length = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(length);
+        Ok(Some(line))
+    }
+}
+
+/// Line editor for an interactive terminal: cursor movement, history
+/// recall and tab completion. The terminal is in raw mode only while a
+/// line is being read.
+pub struct LineEditor;
+
+impl LineEditor {
+    /// Checks that the terminal can be switched to raw mode.
+    pub fn new() -> io::Result<Self> {
+        RawMode::enable().map(|_| Self)
+    }
+}
+
+impl Input for LineEditor {
+    fn read_line(
+        &mut self,
+        mut output: &mut dyn Write,
+        prompt: &str,
+        history: &[String],
+        words: &[String],
+    ) -> io::Result<Option<String>> {
+        let _raw_mode = RawMode::enable()?;
+        let mut input = io::stdin().lock();
+        let mut line = EditedLine::new(history);
+        line.draw(&mut output, prompt)?;
+        loop {
+            let Some(key) = read_key(&mut input)? else {
+                write!(output, "\r\n")?;
+                return Ok(None);
+            };
+            match line.apply(key, words) {
+                Action::Redraw => (),
+                Action::Accept(text) => {
+                    write!(output, "\r\n")?;
+                    return Ok(Some(text));
+                }
+                Action::Cancel => {
+                    write!(output, "^C\r\n")?;
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                Action::EndOfInput => {
+                    write!(output, "\r\n")?;
+                    return Ok(None);
+                }
+                Action::List(candidates) => write!(output, "\r\n{}\r\n", candidates.join("  "))?,
+            }
+            line.draw(&mut output, prompt)?;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    Up,
+    Down,
+    Tab,
+    KillToStart,
+    KillToEnd,
+    Interrupt,
+    EndOfInput,
+    Unknown,
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Reads a key press, decoding control characters, the escape sequences
+/// of cursor keys and UTF-8 encoded characters.
+fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+    let key = match byte {
+        0x01 => Key::Home,
+        0x02 => Key::Left,
+        0x03 => Key::Interrupt,
+        0x04 => Key::EndOfInput,
+        0x05 => Key::End,
+        0x06 => Key::Right,
+        0x08 | 0x7f => Key::Backspace,
+        b'\t' => Key::Tab,
+        b'\r' | b'\n' => Key::Enter,
+        0x0b => Key::KillToEnd,
+        0x0e => Key::Down,
+        0x10 => Key::Up,
+        0x15 => Key::KillToStart,
+        0x1b => read_escape_sequence(input)?,
+        0x20..=0x7e => Key::Char(byte as char),
+        0xc0..=0xff => {
+            let width = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                _ => 4,
+            };
+            let mut bytes = vec![byte];
+            for _ in 1..width {
+                bytes.extend(read_byte(input)?);
+            }
+            match std::str::from_utf8(&bytes) {
+                Ok(text) => text.chars().next().map_or(Key::Unknown, Key::Char),
+                Err(_) => Key::Unknown,
+            }
+        }
+        _ => Key::Unknown,
+    };
+    Ok(Some(key))
+}
+
+/// Decodes `ESC [ A`-style and `ESC [ 3 ~`-style sequences, the escape
+/// itself has been read already.
+fn read_escape_sequence(input: &mut impl Read) -> io::Result<Key> {
+    if !matches!(read_byte(input)?, Some(b'[' | b'O')) {
+        return Ok(Key::Unknown);
+    }
+    let mut number = None;
+    loop {
+        let key = match read_byte(input)? {
+            Some(digit @ b'0'..=b'9') => {
+                number = Some(number.unwrap_or(0) * 10 + (digit - b'0') as u32);
+                continue;
+            }
+            Some(b'A') => Key::Up,
+            Some(b'B') => Key::Down,
+            Some(b'C') => Key::Right,
+            Some(b'D') => Key::Left,
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            Some(b'~') => match number {
+                Some(1 | 7) => Key::Home,
+                Some(3) => Key::Delete,
+                Some(4 | 8) => Key::End,
+                _ => Key::Unknown,
+            },
+            _ => Key::Unknown,
+        };
+        return Ok(key);
+    }
+}
+
+#[derive(PartialEq, Debug)]
+enum Action {
+    Redraw,
+    Accept(String),
+    Cancel,
+    EndOfInput,
+    /// Completion candidates to show above the line.
+    List(Vec<String>),
+}
+
+/// The line being edited, with its place in the history.
+struct EditedLine<'a> {
+    characters: Vec<char>,
+    cursor: usize,
+    history: &'a [String],
+    /// Entry shown from the history, `history.len()` for the line being typed.
+    recalled: usize,
+    /// The line being typed, kept while history entries are shown.
+    typed: Vec<char>,
+}
+
+impl<'a> EditedLine<'a> {
+    fn new(history: &'a [String]) -> Self {
+        Self {
+            characters: Vec::new(),
+            cursor: 0,
+            history,
+            recalled: history.len(),
+            typed: Vec::new(),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.characters.iter().collect()
+    }
+
+    fn draw(&self, output: &mut impl Write, prompt: &str) -> io::Result<()> {
+        write!(output, "\r{}{}\x1b[K", prompt, self.text())?;
+        let after_cursor = self.characters.len() - self.cursor;
+        if after_cursor > 0 {
+            write!(output, "\x1b[{}D", after_cursor)?;
+        }
+        output.flush()
+    }
+
+    fn apply(&mut self, key: Key, words: &[String]) -> Action {
+        match key {
+            Key::Char(c) => {
+                self.characters.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            Key::Enter => return Action::Accept(self.text()),
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.characters.remove(self.cursor);
+            }
+            Key::Delete if self.cursor < self.characters.len() => {
+                self.characters.remove(self.cursor);
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.characters.len()),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.characters.len(),
+            Key::Up if self.recalled > 0 => self.recall(self.recalled - 1),
+            Key::Down if self.recalled < self.history.len() => self.recall(self.recalled + 1),
+            Key::Tab => return self.complete(words),
+            Key::KillToStart => {
+                self.characters.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::KillToEnd => self.characters.truncate(self.cursor),
+            Key::Interrupt => return Action::Cancel,
+            Key::EndOfInput if self.characters.is_empty() => return Action::EndOfInput,
+            Key::EndOfInput if self.cursor < self.characters.len() => {
+                self.characters.remove(self.cursor);
+            }
+            _ => (),
+        }
+        Action::Redraw
+    }
+
+    fn recall(&mut self, entry: usize) {
+        if self.recalled == self.history.len() {
+            self.typed = std::mem::take(&mut self.characters);
+        }
+        self.recalled = entry;
+        self.characters = match self.history.get(entry) {
+            // entries continued over several lines are edited as one
+            Some(text) => text.chars().map(|c| if c == '\n' { ' ' } else { c }).collect(),
+            None => std::mem::take(&mut self.typed),
+        };
+        self.cursor = self.characters.len();
+    }
+
+    /// Completes the word before the cursor as far as all the words
+    /// starting with it agree, or lists them when they disagree right away.
+    fn complete(&mut self, words: &[String]) -> Action {
+        let start = self.characters[..self.cursor]
+            .iter()
+            .rposition(|c| !(c.is_alphanumeric() || *c == '_' || *c == ':'))
+            .map_or(0, |index| index + 1);
+        let prefix = self.characters[start..self.cursor].iter().collect::<String>();
+        if prefix.is_empty() {
+            return Action::Redraw;
+        }
+
+        let mut candidates = words
+            .iter()
+            .filter(|word| word.starts_with(&prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+        let Some(first) = candidates.first() else {
+            return Action::Redraw;
+        };
+        let common = candidates.iter().fold(first.as_str(), |common, candidate| {
+            let length = common
+                .char_indices()
+                .zip(candidate.chars())
+                .take_while(|((_, a), b)| a == b)
+                .last()
+                .map_or(0, |((index, c), _)| index + c.len_utf8());
+            &common[..length]
+        });
+
+        if common.len() > prefix.len() {
+            let completion = common[prefix.len()..].chars().collect::<Vec<_>>();
+            self.cursor += completion.len();
+            self.characters.splice(self.cursor - completion.len()..self.cursor - completion.len(), completion);
+            Action::Redraw
+        } else if candidates.len() > 1 {
+            Action::List(candidates)
+        } else {
+            Action::Redraw
+        }
+    }
+}
+
+#[cfg(test)]
+fn keys(bytes: &[u8]) -> Vec<Key> {
+    let mut input = bytes;
+    std::iter::from_fn(|| read_key(&mut input).unwrap()).collect()
+}
+
+#[cfg(test)]
+fn type_keys<'a>(line: &mut EditedLine<'a>, keys: &[Key]) -> Action {
+    let words = ["let", "letter", "fn", "fun", ":load"].map(String::from);
+    keys.iter().fold(Action::Redraw, |_, key| line.apply(*key, &words))
+}
+
+#[test]
+fn read_keys() {
+    assert_eq!(
+        keys(b"a\x1b[A\x1b[B\x1b[C\x1b[D\x1bOH\x1b[F\x1b[3~\x1b[1~\x1b[4~\x7f\t\r\x03\x04\x01\x05\x0b\x15"),
+        [
+            Key::Char('a'),
+            Key::Up,
+            Key::Down,
+            Key::Right,
+            Key::Left,
+            Key::Home,
+            Key::End,
+            Key::Delete,
+            Key::Home,
+            Key::End,
+            Key::Backspace,
+            Key::Tab,
+            Key::Enter,
+            Key::Interrupt,
+            Key::EndOfInput,
+            Key::Home,
+            Key::End,
+            Key::KillToEnd,
+            Key::KillToStart,
+        ]
+    );
+    assert_eq!(keys("żółw".as_bytes()), "żółw".chars().map(Key::Char).collect::<Vec<_>>());
+    assert_eq!(keys(b"\x1b[9~\x1bx"), [Key::Unknown, Key::Unknown]);
+}
+
+#[test]
+fn edit_the_line() {
+    let mut line = EditedLine::new(&[]);
+    let typed = "1 + 3".chars().map(Key::Char).collect::<Vec<_>>();
+    type_keys(&mut line, &typed);
+    type_keys(&mut line, &[Key::Left, Key::Backspace, Key::Backspace, Key::Char('*')]);
+    assert_eq!(line.text(), "1 *3");
+    assert_eq!(line.cursor, 3);
+
+    type_keys(&mut line, &[Key::Home, Key::Delete, Key::Char('2'), Key::End, Key::Char('!')]);
+    assert_eq!(line.text(), "2 *3!");
+    type_keys(&mut line, &[Key::Left, Key::Left, Key::KillToEnd]);
+    assert_eq!(line.text(), "2 *");
+    type_keys(&mut line, &[Key::Left, Key::KillToStart]);
+    assert_eq!((line.text().as_str(), line.cursor), ("*", 0));
+
+    assert_eq!(type_keys(&mut line, &[Key::EndOfInput]), Action::Redraw);
+    assert_eq!(type_keys(&mut line, &[Key::EndOfInput]), Action::EndOfInput);
+    assert_eq!(type_keys(&mut line, &[Key::Char('x'), Key::Interrupt]), Action::Cancel);
+    assert_eq!(type_keys(&mut line, &[Key::Enter]), Action::Accept("x".to_string()));
+}
+
+#[test]
+fn recall_history() {
+    let history = ["let x = 1".to_string(), "fn f(a) =\n  a".to_string()];
+    let mut line = EditedLine::new(&history);
+    type_keys(&mut line, &[Key::Char('x'), Key::Up]);
+    assert_eq!(line.text(), "fn f(a) =   a");
+    type_keys(&mut line, &[Key::Up, Key::Up]);
+    assert_eq!(line.text(), "let x = 1");
+    type_keys(&mut line, &[Key::Down, Key::Down]);
+    assert_eq!(line.text(), "x");
+    assert_eq!(type_keys(&mut line, &[Key::Down, Key::Enter]), Action::Accept("x".to_string()));
+}
+
+#[test]
+fn complete_words() {
+    let mut line = EditedLine::new(&[]);
+    type_keys(&mut line, &[Key::Char('('), Key::Char('f'), Key::Char('u'), Key::Tab]);
+    assert_eq!(line.text(), "(fun");
+
+    let mut line = EditedLine::new(&[]);
+    type_keys(&mut line, &[Key::Char('l'), Key::Tab]);
+    assert_eq!(line.text(), "let");
+    assert_eq!(
+        type_keys(&mut line, &[Key::Tab]),
+        Action::List(vec!["let".to_string(), "letter".to_string()])
+    );
+
+    let mut line = EditedLine::new(&[]);
+    type_keys(&mut line, &[Key::Char(':'), Key::Char('l'), Key::Char(' '), Key::Left, Key::Tab]);
+    assert_eq!((line.text().as_str(), line.cursor), (":load ", 5));
+    assert_eq!(type_keys(&mut line, &[Key::Char('q'), Key::Tab]), Action::Redraw);
+    assert_eq!(line.text(), ":loadq ");
+}
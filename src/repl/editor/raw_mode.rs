@@ -0,0 +1,147 @@
+//! Raw mode of the terminal on stdin: bytes are passed on as they are typed,
+//! without echo or line editing by the terminal. The previous settings come
+//! back when the [`RawMode`] is dropped.
+
+pub use self::imp::RawMode;
+
+/// Settings read and written with the `TCGETS` and `TCSETS` ioctls, which
+/// the C library the standard library links to already provides, so no
+/// bindings crate is needed. The layout and request numbers are the ones
+/// shared by these architectures.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64", target_arch = "riscv64")
+))]
+mod imp {
+    use std::{
+        ffi::{c_int, c_ulong},
+        io,
+        os::fd::AsRawFd,
+    };
+
+    const TCGETS: c_ulong = 0x5401;
+    const TCSETS: c_ulong = 0x5402;
+
+    // input flags
+    const IGNBRK: u32 = 0o1;
+    const BRKINT: u32 = 0o2;
+    const PARMRK: u32 = 0o10;
+    const ISTRIP: u32 = 0o40;
+    const INLCR: u32 = 0o100;
+    const IGNCR: u32 = 0o200;
+    const ICRNL: u32 = 0o400;
+    const IXON: u32 = 0o2000;
+    // output flags
+    const OPOST: u32 = 0o1;
+    // control flags
+    const CSIZE: u32 = 0o60;
+    const CS8: u32 = 0o60;
+    const PARENB: u32 = 0o400;
+    // local flags
+    const ISIG: u32 = 0o1;
+    const ICANON: u32 = 0o2;
+    const ECHO: u32 = 0o10;
+    const ECHONL: u32 = 0o100;
+    const IEXTEN: u32 = 0o100000;
+    // control characters
+    const VTIME: usize = 5;
+    const VMIN: usize = 6;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct Termios {
+        input_flags: u32,
+        output_flags: u32,
+        control_flags: u32,
+        local_flags: u32,
+        line_discipline: u8,
+        control_characters: [u8; 19],
+    }
+
+    extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+
+    fn call(request: c_ulong, termios: *mut Termios) -> io::Result<()> {
+        // SAFETY: both requests read or write a single `Termios`, which the
+        // pointer refers to for the whole call
+        match unsafe { ioctl(io::stdin().as_raw_fd(), request, termios) } {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    pub struct RawMode {
+        saved: Termios,
+    }
+
+    impl RawMode {
+        /// Switches the terminal the way `cfmakeraw` does, fails when stdin
+        /// is not a terminal.
+        pub fn enable() -> io::Result<Self> {
+            let mut saved = Termios::default();
+            call(TCGETS, &mut saved)?;
+
+            let mut raw = saved;
+            raw.input_flags &= !(IGNBRK | BRKINT | PARMRK | ISTRIP | INLCR | IGNCR | ICRNL | IXON);
+            raw.output_flags &= !OPOST;
+            raw.control_flags = raw.control_flags & !(CSIZE | PARENB) | CS8;
+            raw.local_flags &= !(ECHO | ECHONL | ICANON | ISIG | IEXTEN);
+            raw.control_characters[VMIN] = 1;
+            raw.control_characters[VTIME] = 0;
+            call(TCSETS, &mut raw)?;
+            Ok(Self { saved })
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            let _ = call(TCSETS, &mut self.saved);
+        }
+    }
+}
+
+/// Raw mode set up by `stty`, on systems whose terminal settings have a
+/// layout of their own.
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64", target_arch = "riscv64")
+)))]
+mod imp {
+    use std::{
+        io,
+        process::{Command, Stdio},
+    };
+
+    pub struct RawMode {
+        saved: String,
+    }
+
+    impl RawMode {
+        pub fn enable() -> io::Result<Self> {
+            let saved = stty(&["-g"])?;
+            stty(&["raw", "-echo"])?;
+            Ok(Self {
+                saved: saved.trim().to_string(),
+            })
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            let _ = stty(&[&self.saved]);
+        }
+    }
+
+    fn stty(arguments: &[&str]) -> io::Result<String> {
+        let output = Command::new("stty")
+            .args(arguments)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .output()?;
+        match output.status.success() {
+            true => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+            false => Err(io::Error::other("stty could not set up the terminal")),
+        }
+    }
+}
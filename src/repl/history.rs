@@ -0,0 +1,126 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// How many of the most recent entries are kept, both loaded and saved.
+const MAX_ENTRIES: usize = 1000;
+
+/// Inputs entered in earlier sessions and this one, oldest first. Every
+/// entry takes a single line of the history file, with line breaks and
+/// backslashes escaped.
+#[derive(Default)]
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// History kept in `$MLOR_HISTORY`, or `~/.mlor_history` by default.
+    pub fn from_environment() -> Self {
+        let path = env::var_os("MLOR_HISTORY")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".mlor_history")));
+        match path {
+            Some(path) => Self::load(path),
+            None => Self::default(),
+        }
+    }
+
+    /// Loads the history file, a missing or unreadable one starts an empty history.
+    pub fn load(path: PathBuf) -> Self {
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+        let mut entries = contents.lines().map(unescape).collect::<Vec<_>>();
+        entries.drain(..entries.len().saturating_sub(MAX_ENTRIES));
+        Self {
+            entries,
+            path: Some(path),
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Records an entry and appends it to the history file, which is
+    /// rewritten with the most recent entries once there are too many.
+    /// Repeating the previous entry does not record it again.
+    pub fn add(&mut self, entry: &str) {
+        if entry.trim().is_empty() || self.entries.last().is_some_and(|last| last == entry) {
+            return;
+        }
+        self.entries.push(entry.to_string());
+        let trimmed = self.entries.len() > MAX_ENTRIES;
+        if trimmed {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+        // history is a convenience, failing to save it must not stop the session
+        if let Some(path) = &self.path {
+            let _ = match trimmed {
+                true => fs::write(path, self.entries.iter().map(|entry| escape(entry) + "\n").collect::<String>()),
+                false => fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .and_then(|mut file| writeln!(file, "{}", escape(entry))),
+            };
+        }
+    }
+}
+
+fn escape(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut entry = String::with_capacity(line.len());
+    let mut characters = line.chars();
+    while let Some(c) = characters.next() {
+        match (c, characters.clone().next()) {
+            ('\\', Some('n')) => {
+                entry.push('\n');
+                characters.next();
+            }
+            ('\\', Some('\\')) => {
+                entry.push('\\');
+                characters.next();
+            }
+            (c, _) => entry.push(c),
+        }
+    }
+    entry
+}
+
+#[test]
+fn entries_survive_a_round_trip_through_the_file() {
+    let path = env::temp_dir().join(format!("mlor_history_test_{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut history = History::load(path.clone());
+    history.add("let x = 1");
+    history.add("let x = 1");
+    history.add("  ");
+    history.add("fn f(a) =\n  a \\ 2");
+    assert_eq!(history.entries(), ["let x = 1", "fn f(a) =\n  a \\ 2"]);
+
+    let reloaded = History::load(path.clone());
+    assert_eq!(reloaded.entries(), history.entries());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn keep_the_most_recent_entries_in_the_file() {
+    let path = env::temp_dir().join(format!("mlor_history_limit_test_{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut history = History::load(path.clone());
+    for index in 0..MAX_ENTRIES + 5 {
+        history.add(&index.to_string());
+    }
+    assert_eq!(history.entries().len(), MAX_ENTRIES);
+    assert_eq!(history.entries()[0], "5");
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), MAX_ENTRIES);
+    assert_eq!(History::load(path.clone()).entries(), history.entries());
+    fs::remove_file(path).unwrap();
+}
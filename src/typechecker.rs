@@ -0,0 +1,712 @@
+use std::collections::HashMap;
+
+use crate::evaluator::context::{Context, NumericMode};
+use crate::lexer::token::Span;
+use crate::parser::syntax_tree::{ComparisonOperator, ExpressionNode, FactorNode, StatementNode, TermNode};
+#[cfg(test)]
+use crate::parser::{parse_valid_statement, span};
+
+use self::{
+    environment::TypeEnvironment,
+    error::TypeError,
+    types::{Constraint, Type, TypeScheme, TypeVariable},
+};
+
+pub mod environment;
+pub mod error;
+pub mod types;
+
+/// State of a single inference run: the type each variable was unified with
+/// so far and the types the variable is restricted to.
+#[derive(Default)]
+struct Inference {
+    substitution: Vec<Option<Type>>,
+    constraints: Vec<Constraint>,
+    numeric_mode: NumericMode,
+}
+
+impl Inference {
+    fn fresh(&mut self, constraint: Constraint) -> Type {
+        self.substitution.push(None);
+        self.constraints.push(constraint);
+        Type::Variable((self.substitution.len() - 1) as TypeVariable)
+    }
+
+    /// Applies the substitution to the type until no bound variable is left.
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Variable(variable) => match self.substitution.get(*variable as usize) {
+                Some(Some(bound)) => self.resolve(bound),
+                _ => ty.clone(),
+            },
+            Type::Function(parameters, result) => Type::Function(
+                parameters.iter().map(|parameter| self.resolve(parameter)).collect(),
+                Box::new(self.resolve(result)),
+            ),
+            Type::Int | Type::Float | Type::Rational | Type::Bool => ty.clone(),
+        }
+    }
+
+    fn constraint(&self, variable: TypeVariable) -> Constraint {
+        self.constraints[variable as usize]
+    }
+
+    /// Restricts the variable to the types allowed by both its constraint
+    /// and the given one.
+    fn constrain(&mut self, variable: TypeVariable, constraint: Constraint) {
+        let current = &mut self.constraints[variable as usize];
+        *current = (*current).max(constraint);
+    }
+
+    fn bind(&mut self, variable: TypeVariable, ty: Type, span: Span) -> Result<(), TypeError> {
+        if ty == Type::Variable(variable) {
+            return Ok(());
+        }
+        if ty.occurs(variable) {
+            return Err(TypeError::InfiniteType {
+                variable: Type::Variable(variable),
+                found: ty,
+                span,
+            });
+        }
+        match (self.constraint(variable), &ty) {
+            (constraint, Type::Variable(other)) => self.constrain(*other, constraint),
+            (Constraint::Numeric, Type::Bool | Type::Function(..)) => {
+                return Err(TypeError::NotNumeric { found: ty, span })
+            }
+            (Constraint::Equality, Type::Function(..)) => return Err(TypeError::NotComparable { found: ty, span }),
+            _ => (),
+        }
+        self.substitution[variable as usize] = Some(ty);
+        Ok(())
+    }
+
+    fn unify(&mut self, expected: &Type, found: &Type, span: Span) -> Result<(), TypeError> {
+        let (expected, found) = (self.resolve(expected), self.resolve(found));
+        match (&expected, &found) {
+            (Type::Variable(variable), _) => self.bind(*variable, found, span),
+            (_, Type::Variable(variable)) => self.bind(*variable, expected, span),
+            (Type::Function(expected_parameters, expected_result), Type::Function(found_parameters, found_result))
+                if expected_parameters.len() == found_parameters.len() =>
+            {
+                for (expected_parameter, found_parameter) in expected_parameters.iter().zip(found_parameters) {
+                    self.unify(expected_parameter, found_parameter, span)
+                        .map_err(|error| self.mismatch(error, &expected, &found))?;
+                }
+                self.unify(expected_result, found_result, span)
+                    .map_err(|error| self.mismatch(error, &expected, &found))
+            }
+            _ if expected == found => Ok(()),
+            _ => Err(TypeError::Mismatch { expected, found, span }),
+        }
+    }
+
+    /// Reports a mismatch found inside a larger type as a mismatch of the
+    /// whole types, which is easier to relate to the source.
+    fn mismatch(&self, error: TypeError, expected: &Type, found: &Type) -> TypeError {
+        match error {
+            TypeError::Mismatch { span, .. } => TypeError::Mismatch {
+                expected: self.resolve(expected),
+                found: self.resolve(found),
+                span,
+            },
+            error => error,
+        }
+    }
+
+    /// Type of an arithmetic operation. An integer meeting a float widens to
+    /// a float like the evaluator promotes it, other operands have the same
+    /// numeric type, which is the type of the result too. Operands not known
+    /// yet are unified, as the type of a function like `fn add(a, b) = a + b`
+    /// could not say that either operand may be widened.
+    fn arithmetic(&mut self, left: &Type, right: &Type, span: Span) -> Result<Type, TypeError> {
+        let left = self.numeric_operand(left, span)?;
+        let right = self.numeric_operand(right, span)?;
+        if let Some(widened) = self.widened(&left, &right) {
+            return Ok(widened);
+        }
+        self.unify(&left, &right, span)?;
+        Ok(self.resolve(&left))
+    }
+
+    /// `float` when one type is known to be an integer and the other a float.
+    fn widened(&self, left: &Type, right: &Type) -> Option<Type> {
+        match (self.resolve(left), self.resolve(right)) {
+            (Type::Int | Type::Rational, Type::Float) | (Type::Float, Type::Int | Type::Rational) => Some(Type::Float),
+            _ => None,
+        }
+    }
+
+    fn numeric_operand(&mut self, operand: &Type, span: Span) -> Result<Type, TypeError> {
+        match self.resolve(operand) {
+            ty @ (Type::Int | Type::Float | Type::Rational) => Ok(ty),
+            Type::Variable(variable) => {
+                self.constrain(variable, Constraint::Numeric);
+                Ok(Type::Variable(variable))
+            }
+            found @ (Type::Bool | Type::Function(..)) => Err(TypeError::NotNumeric { found, span }),
+        }
+    }
+
+    fn equality_operand(&mut self, operand: &Type, span: Span) -> Result<(), TypeError> {
+        match self.resolve(operand) {
+            Type::Variable(variable) => {
+                self.constrain(variable, Constraint::Equality);
+                Ok(())
+            }
+            found @ Type::Function(..) => Err(TypeError::NotComparable { found, span }),
+            Type::Int | Type::Float | Type::Rational | Type::Bool => Ok(()),
+        }
+    }
+
+    /// Type of a comparison. Numbers can be ordered, other values but
+    /// functions can only be tested for equality with a value of the same
+    /// type. Integers and floats are compared like in arithmetic.
+    fn comparison(
+        &mut self,
+        operator: ComparisonOperator,
+        left: &Type,
+        right: &Type,
+        span: Span,
+    ) -> Result<Type, TypeError> {
+        match operator {
+            ComparisonOperator::Equal | ComparisonOperator::NotEqual => {
+                self.equality_operand(left, span)?;
+                self.equality_operand(right, span)?;
+                if self.widened(left, right).is_none() {
+                    self.unify(left, right, span)?;
+                }
+            }
+            _ => {
+                self.arithmetic(left, right, span)?;
+            }
+        }
+        Ok(Type::Bool)
+    }
+
+    fn instantiate(&mut self, scheme: &TypeScheme) -> Type {
+        let fresh = scheme
+            .variables
+            .iter()
+            .map(|(variable, constraint)| (*variable, self.fresh(*constraint)))
+            .collect::<HashMap<_, _>>();
+        substitute(&scheme.body, &fresh)
+    }
+
+    /// Quantifies the variables of `ty` that are not free in the environment,
+    /// renumbering them from zero so that schemes do not depend on the run
+    /// they were inferred in.
+    fn generalize(&self, ty: &Type, environment: &TypeEnvironment) -> TypeScheme {
+        let mut environment_variables = Vec::new();
+        for variable in environment.free_variables() {
+            self.resolve(&Type::Variable(variable)).free_variables(&mut environment_variables);
+        }
+
+        let ty = self.resolve(ty);
+        let mut variables = Vec::new();
+        ty.free_variables(&mut variables);
+        variables.retain(|variable| !environment_variables.contains(variable));
+
+        let renamed = variables
+            .iter()
+            .enumerate()
+            .map(|(index, variable)| (*variable, Type::Variable(index as TypeVariable)))
+            .collect::<HashMap<_, _>>();
+        TypeScheme {
+            variables: variables
+                .iter()
+                .enumerate()
+                .map(|(index, variable)| (index as TypeVariable, self.constraint(*variable)))
+                .collect(),
+            body: substitute(&ty, &renamed),
+        }
+    }
+}
+
+fn substitute(ty: &Type, replacements: &HashMap<TypeVariable, Type>) -> Type {
+    match ty {
+        Type::Variable(variable) => replacements.get(variable).cloned().unwrap_or_else(|| ty.clone()),
+        Type::Function(parameters, result) => Type::Function(
+            parameters.iter().map(|parameter| substitute(parameter, replacements)).collect(),
+            Box::new(substitute(result, replacements)),
+        ),
+        Type::Int | Type::Float | Type::Rational | Type::Bool => ty.clone(),
+    }
+}
+
+impl StatementNode {
+    /// Infers the type of the statement, a `let` or `fn` extends the
+    /// environment with the generalized type of its binding. Returns the
+    /// type of the expression or of the bound value. Integers have the type
+    /// the context's numeric mode evaluates them to.
+    pub fn check(&self, context: &Context, environment: &mut TypeEnvironment) -> Result<TypeScheme, TypeError> {
+        let mut inference = Inference {
+            numeric_mode: context.numeric_mode,
+            ..Inference::default()
+        };
+        match self {
+            Self::LetNode { name, value, .. } => {
+                let ty = value.infer(&mut inference, environment)?;
+                let scheme = inference.generalize(&ty, environment);
+                *environment = environment.extend(name.clone(), scheme.clone());
+                Ok(scheme)
+            }
+            Self::FunctionNode {
+                name,
+                parameters,
+                body,
+                name_span,
+            } => {
+                let parameter_types = parameters.iter().map(|_| inference.fresh(Constraint::Any)).collect::<Vec<_>>();
+                let result = inference.fresh(Constraint::Any);
+                let function = Type::Function(parameter_types.clone(), Box::new(result.clone()));
+
+                // the function is monomorphic inside its own body
+                let mut frame = environment.extend(name.clone(), TypeScheme::monomorphic(function.clone()));
+                for (parameter, ty) in parameters.iter().zip(parameter_types) {
+                    frame = frame.extend(parameter.clone(), TypeScheme::monomorphic(ty));
+                }
+                let body_type = body.infer(&mut inference, &frame)?;
+                inference.unify(&result, &body_type, *name_span)?;
+
+                let scheme = inference.generalize(&function, environment);
+                *environment = environment.extend(name.clone(), scheme.clone());
+                Ok(scheme)
+            }
+            Self::ExpressionStatementNode(exp) => {
+                let ty = exp.infer(&mut inference, environment)?;
+                Ok(inference.generalize(&ty, environment))
+            }
+            Self::ErrorNode { .. } => Ok(TypeScheme::monomorphic(inference.fresh(Constraint::Any))),
+        }
+    }
+}
+
+impl ExpressionNode {
+    fn infer(&self, inference: &mut Inference, environment: &TypeEnvironment) -> Result<Type, TypeError> {
+        match self {
+            Self::SingleTermNode(t) => t.infer(inference, environment),
+            Self::OrNode {
+                left,
+                right,
+                operator_span,
+            }
+            | Self::AndNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                let left = left.infer(inference, environment)?;
+                inference.unify(&Type::Bool, &left, *operator_span)?;
+                let right = right.infer(inference, environment)?;
+                inference.unify(&Type::Bool, &right, *operator_span)?;
+                Ok(Type::Bool)
+            }
+            Self::ComparisonNode {
+                operator,
+                left,
+                right,
+                operator_span,
+            } => {
+                let left = left.infer(inference, environment)?;
+                let right = right.infer(inference, environment)?;
+                inference.comparison(*operator, &left, &right, *operator_span)
+            }
+            Self::AdditionTermNode {
+                left,
+                right,
+                operator_span,
+            }
+            | Self::SubstractionTermNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                let left = left.infer(inference, environment)?;
+                let right = right.infer(inference, environment)?;
+                inference.arithmetic(&left, &right, *operator_span)
+            }
+        }
+    }
+}
+
+impl TermNode {
+    fn infer(&self, inference: &mut Inference, environment: &TypeEnvironment) -> Result<Type, TypeError> {
+        match self {
+            Self::SingleFactorNode(t) => t.infer(inference, environment),
+            Self::MultiplicationFactorNode {
+                left,
+                right,
+                operator_span,
+            }
+            | Self::DivisionFactorNode {
+                left,
+                right,
+                operator_span,
+            }
+            | Self::IntegerDivisionFactorNode {
+                left,
+                right,
+                operator_span,
+            }
+            | Self::ModuloFactorNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                let left = left.infer(inference, environment)?;
+                let right = right.infer(inference, environment)?;
+                inference.arithmetic(&left, &right, *operator_span)
+            }
+        }
+    }
+}
+
+impl FactorNode {
+    fn infer(&self, inference: &mut Inference, environment: &TypeEnvironment) -> Result<Type, TypeError> {
+        match self {
+            Self::LiteralNode { .. } => Ok(Type::integer(inference.numeric_mode)),
+            Self::FloatLiteralNode { .. } => Ok(Type::Float),
+            Self::BoolLiteralNode { .. } => Ok(Type::Bool),
+            Self::VariableNode { name, span } => match environment.lookup(name) {
+                Some(scheme) => Ok(inference.instantiate(scheme)),
+                None => Err(TypeError::UndefinedVariable {
+                    name: name.clone(),
+                    span: *span,
+                }),
+            },
+            Self::LambdaNode { parameter, body, .. } => {
+                let parameter_type = inference.fresh(Constraint::Any);
+                let frame = environment.extend(parameter.clone(), TypeScheme::monomorphic(parameter_type.clone()));
+                let body_type = body.infer(inference, &frame)?;
+                Ok(Type::Function(vec![parameter_type], Box::new(body_type)))
+            }
+            Self::IfNode {
+                condition,
+                consequence,
+                alternative,
+                if_span,
+                else_span,
+                widen,
+            } => {
+                let condition = condition.infer(inference, environment)?;
+                inference.unify(&Type::Bool, &condition, *if_span)?;
+                let consequence = consequence.infer(inference, environment)?;
+                let alternative = alternative.infer(inference, environment)?;
+                // an integer branch is converted when the conditional runs
+                if let Some(widened) = inference.widened(&consequence, &alternative) {
+                    widen.set(true);
+                    return Ok(widened);
+                }
+                inference.unify(&consequence, &alternative, *else_span)?;
+                Ok(inference.resolve(&consequence))
+            }
+            Self::CallNode {
+                callee,
+                arguments,
+                arguments_span,
+            } => {
+                let callee = callee.infer(inference, environment)?;
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.infer(inference, environment))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match inference.resolve(&callee) {
+                    Type::Function(parameters, result) => {
+                        if parameters.len() != arguments.len() {
+                            return Err(TypeError::ArityMismatch {
+                                expected: parameters.len(),
+                                got: arguments.len(),
+                                span: *arguments_span,
+                            });
+                        }
+                        for (parameter, argument) in parameters.iter().zip(&arguments) {
+                            inference
+                                .unify(parameter, argument, *arguments_span)
+                                .map_err(|error| inference.mismatch(error, parameter, argument))?;
+                        }
+                        Ok(*result)
+                    }
+                    Type::Variable(_) => {
+                        let result = inference.fresh(Constraint::Any);
+                        let function = Type::Function(arguments, Box::new(result.clone()));
+                        inference.unify(&callee, &function, *arguments_span)?;
+                        Ok(result)
+                    }
+                    found @ (Type::Int | Type::Float | Type::Rational | Type::Bool) => Err(TypeError::NotAFunction {
+                        found,
+                        span: *arguments_span,
+                    }),
+                }
+            }
+            Self::ExpressionNode { expression: exp, .. } => exp.infer(inference, environment),
+            Self::PowerNode {
+                base,
+                exponent,
+                operator_span,
+            } => {
+                let base = base.infer(inference, environment)?;
+                let exponent = exponent.infer(inference, environment)?;
+                inference.arithmetic(&base, &exponent, *operator_span)
+            }
+            Self::NegativeExpressionNode { operand, operator_span } => {
+                let operand = operand.infer(inference, environment)?;
+                inference.numeric_operand(&operand, *operator_span)
+            }
+            Self::NotExpressionNode { operand, operator_span } => {
+                let operand = operand.infer(inference, environment)?;
+                inference.unify(&Type::Bool, &operand, *operator_span)?;
+                Ok(Type::Bool)
+            }
+            // an unparsable expression could have any type, which lets the rest be checked
+            Self::ErrorNode { .. } => Ok(inference.fresh(Constraint::Any)),
+        }
+    }
+}
+
+#[cfg(test)]
+fn check_lines(lines: &[&str]) -> Result<String, TypeError> {
+    let mut environment = TypeEnvironment::default();
+    let mut result = Ok(String::new());
+    for line in lines {
+        let scheme = parse_valid_statement(line).check(&Context::default(), &mut environment);
+        result = scheme.map(|scheme| scheme.to_string());
+    }
+    result
+}
+
+#[test]
+fn infer_arithmetic() {
+    assert_eq!(check_lines(&["1 + 2 * 3"]), Ok("int".to_string()));
+    assert_eq!(check_lines(&["-(2.0 - 7.0) / 3.5"]), Ok("float".to_string()));
+    assert_eq!(check_lines(&["let x = 4", "x * x"]), Ok("int".to_string()));
+}
+
+#[test]
+fn widen_integers_meeting_floats() {
+    assert_eq!(check_lines(&["1.5 * 2"]), Ok("float".to_string()));
+    assert_eq!(check_lines(&["1 + 2.0"]), Ok("float".to_string()));
+    assert_eq!(check_lines(&["2 ** 0.5"]), Ok("float".to_string()));
+    assert_eq!(check_lines(&["let x = 1", "x * 1.5"]), Ok("float".to_string()));
+    assert_eq!(check_lines(&["1 < 2.5 && 1 == 1.0"]), Ok("bool".to_string()));
+    assert_eq!(check_lines(&["if true then 1 else 2.0"]), Ok("float".to_string()));
+    assert_eq!(check_lines(&["fn f(c) = if c then 1 else 2.0"]), Ok("bool -> float".to_string()));
+
+    // unknown operands are not widened, so parameters keep a single type
+    assert_eq!(check_lines(&["fn scale(x) = x * 1.5"]), Ok("float -> float".to_string()));
+    assert_eq!(
+        check_lines(&["fn scale(x) = x * 1.5", "scale(2)"]).map_err(|error| error.message()),
+        Err("Type mismatch - expected float, found int".to_string())
+    );
+}
+
+#[test]
+fn infer_functions() {
+    assert_eq!(check_lines(&["fn add(a, b) = a + b"]), Ok("num 'a => ('a, 'a) -> 'a".to_string()));
+    assert_eq!(check_lines(&["fn inc(x) = x + 1"]), Ok("int -> int".to_string()));
+    assert_eq!(check_lines(&["fn half(x) = x / 2.0"]), Ok("float -> float".to_string()));
+    assert_eq!(check_lines(&["fn answer() = 42"]), Ok("() -> int".to_string()));
+    assert_eq!(check_lines(&["fn twice(f, x) = f(f(x))"]), Ok("('a -> 'a, 'a) -> 'a".to_string()));
+    assert_eq!(check_lines(&["fun x -> fun y -> x"]), Ok("'a -> 'b -> 'a".to_string()));
+    assert_eq!(check_lines(&["fn adder(n) = fun x -> x + n", "adder 1"]), Ok("int -> int".to_string()));
+    assert_eq!(check_lines(&["fn forever(n) = forever(n)"]), Ok("'a -> 'b".to_string()));
+}
+
+#[test]
+fn infer_let_polymorphism() {
+    assert_eq!(check_lines(&["let id = fun x -> x", "id"]), Ok("'a -> 'a".to_string()));
+    assert_eq!(check_lines(&["let id = fun x -> x", "id(id)(1) < 2 && id(true)"]), Ok("bool".to_string()));
+    assert_eq!(
+        check_lines(&["fn double(x) = x + x", "double(1) > 0 && double(2.5) > 0.0"]),
+        Ok("bool".to_string())
+    );
+}
+
+#[test]
+fn infer_errors() {
+    let function = |parameters, result| Type::Function(parameters, Box::new(result));
+
+    assert_eq!(
+        check_lines(&["fn f(x) = x", "f + 1"]),
+        Err(TypeError::NotNumeric {
+            found: function(vec![Type::Variable(0)], Type::Variable(0)),
+            span: span(3, 4)
+        })
+    );
+    assert_eq!(
+        check_lines(&["fn add(a, b) = a + b", "add(1)"]),
+        Err(TypeError::ArityMismatch {
+            expected: 2,
+            got: 1,
+            span: span(4, 7)
+        })
+    );
+    assert_eq!(
+        check_lines(&["fn f(g) = g(1) + g(2.5)"]),
+        Err(TypeError::Mismatch {
+            expected: Type::Int,
+            found: Type::Float,
+            span: span(19, 24)
+        })
+    );
+    assert_eq!(
+        check_lines(&["fn add(a, b) = a + b", "add(1, 2.5)"]).map_err(|error| error.message()),
+        Err("Type mismatch - expected int, found float".to_string())
+    );
+    assert_eq!(
+        check_lines(&["1 + y"]),
+        Err(TypeError::UndefinedVariable {
+            name: "y".to_string(),
+            span: span(5, 6)
+        })
+    );
+    assert_eq!(
+        check_lines(&["fun x -> x x"]).map_err(|error| (error.message(), error.span())),
+        Err(("Infinite type - 'a occurs in 'a -> 'b".to_string(), span(12, 13)))
+    );
+    assert_eq!(
+        check_lines(&["fn f() = 1", "f()()"]).map_err(|error| (error.message(), error.span())),
+        Err(("Expected a function, found int".to_string(), span(4, 6)))
+    );
+}
+
+#[test]
+fn infer_booleans() {
+    assert_eq!(check_lines(&["1 < 2 && !false"]), Ok("bool".to_string()));
+    assert_eq!(check_lines(&["fn positive(x) = x > 0"]), Ok("int -> bool".to_string()));
+    assert_eq!(check_lines(&["fn less(a, b) = a < b"]), Ok("num 'a => ('a, 'a) -> bool".to_string()));
+    assert_eq!(check_lines(&["fn same(a, b) = a == b"]), Ok("eq 'a => ('a, 'a) -> bool".to_string()));
+    assert_eq!(check_lines(&["fn both(a, b) = a && b"]), Ok("(bool, bool) -> bool".to_string()));
+
+    assert_eq!(
+        check_lines(&["1 && true"]),
+        Err(TypeError::Mismatch {
+            expected: Type::Bool,
+            found: Type::Int,
+            span: span(3, 5)
+        })
+    );
+    assert_eq!(
+        check_lines(&["true == 1"]),
+        Err(TypeError::Mismatch {
+            expected: Type::Bool,
+            found: Type::Int,
+            span: span(6, 8)
+        })
+    );
+    assert_eq!(
+        check_lines(&["true < false"]),
+        Err(TypeError::NotNumeric {
+            found: Type::Bool,
+            span: span(6, 7)
+        })
+    );
+    assert_eq!(
+        check_lines(&["fun x -> x == x"]).map_err(|error| error.message()),
+        Ok("eq 'a => 'a -> bool".to_string())
+    );
+    assert_eq!(
+        check_lines(&["fn f(x) = x", "f == f"]).map_err(|error| (error.message(), error.code())),
+        Err(("Values of type 'a -> 'a cannot be compared".to_string(), "E0107"))
+    );
+    assert_eq!(
+        check_lines(&[
+            "let same = fun a -> fun b -> a == b",
+            "same (fun x -> x) (fun x -> x)"
+        ])
+        .map_err(|error| error.message()),
+        Err("Values of type 'a -> 'a cannot be compared".to_string())
+    );
+    assert_eq!(
+        check_lines(&["fn less(a, b) = a < b", "less(true, false)"]),
+        Err(TypeError::NotNumeric {
+            found: Type::Bool,
+            span: span(5, 18)
+        })
+    );
+}
+
+#[test]
+fn infer_conditionals() {
+    assert_eq!(check_lines(&["if true then 1 else 2"]), Ok("int".to_string()));
+    assert_eq!(
+        check_lines(&["fn fact(n) = if n <= 1 then 1 else n * fact(n - 1)"]),
+        Ok("int -> int".to_string())
+    );
+    assert_eq!(check_lines(&["fn choose(c, a, b) = if c then a else b"]), Ok("(bool, 'a, 'a) -> 'a".to_string()));
+
+    assert_eq!(
+        check_lines(&["if 1 then 2 else 3"]),
+        Err(TypeError::Mismatch {
+            expected: Type::Bool,
+            found: Type::Int,
+            span: span(1, 3)
+        })
+    );
+    assert_eq!(
+        check_lines(&["if true then 1 else false"]),
+        Err(TypeError::Mismatch {
+            expected: Type::Int,
+            found: Type::Bool,
+            span: span(16, 20)
+        })
+    );
+}
+
+#[test]
+fn infer_power_and_modulo() {
+    assert_eq!(check_lines(&["2 ** 3 % 5 // 2"]), Ok("int".to_string()));
+    assert_eq!(check_lines(&["2.0 ** 0.5"]), Ok("float".to_string()));
+    assert_eq!(check_lines(&["fn square(x) = x ^ 2"]), Ok("int -> int".to_string()));
+    assert_eq!(
+        check_lines(&["true % 2"]),
+        Err(TypeError::NotNumeric {
+            found: Type::Bool,
+            span: span(6, 7)
+        })
+    );
+}
+
+#[test]
+fn infer_numeric_modes() {
+    let check = |numeric_mode, line: &str| {
+        let context = Context { numeric_mode, ..Context::default() };
+        let scheme = parse_valid_statement(line).check(&context, &mut TypeEnvironment::default());
+        scheme.map(|scheme| scheme.to_string())
+    };
+
+    // integer division truncates unless integers are fractions
+    assert_eq!(check(NumericMode::Machine, "7 / 2"), Ok("int".to_string()));
+    assert_eq!(check(NumericMode::BigInt, "7 / 2"), Ok("int".to_string()));
+    assert_eq!(check(NumericMode::Rational, "7 / 2"), Ok("rational".to_string()));
+    assert_eq!(check(NumericMode::Rational, "fn half(x) = x / 2"), Ok("rational -> rational".to_string()));
+    assert_eq!(check(NumericMode::Rational, "1 / 3 + 0.5"), Ok("float".to_string()));
+    assert_eq!(
+        check(NumericMode::Rational, "if true then 1 / 3 else true"),
+        Err(TypeError::Mismatch {
+            expected: Type::Rational,
+            found: Type::Bool,
+            span: span(20, 24)
+        })
+    );
+}
+
+#[test]
+fn infer_around_error_nodes() {
+    let (program, errors) = crate::parser::parse_program_str("fn f(x) = x + (1 +); f(true)");
+    assert_eq!(errors.len(), 1);
+
+    // the valid parts of the definition still constrain its type
+    let mut environment = TypeEnvironment::default();
+    assert_eq!(
+        program.statements[0].check(&Context::default(), &mut environment).map(|scheme| scheme.to_string()),
+        Ok("num 'a => 'a -> 'a".to_string())
+    );
+    assert_eq!(
+        program.statements[1].check(&Context::default(), &mut environment),
+        Err(TypeError::NotNumeric {
+            found: Type::Bool,
+            span: span(23, 29)
+        })
+    );
+}
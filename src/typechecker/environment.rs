@@ -0,0 +1,71 @@
+use std::rc::Rc;
+
+use super::types::{TypeScheme, TypeVariable};
+
+struct Binding {
+    name: String,
+    scheme: TypeScheme,
+    parent: Option<Rc<Binding>>,
+}
+
+/// Name to type scheme bindings, shaped like the evaluator's environment so
+/// that the two can be extended side by side.
+#[derive(Clone, Default)]
+pub struct TypeEnvironment {
+    bindings: Option<Rc<Binding>>,
+}
+
+impl TypeEnvironment {
+    pub fn extend(&self, name: String, scheme: TypeScheme) -> Self {
+        Self {
+            bindings: Some(Rc::new(Binding {
+                name,
+                scheme,
+                parent: self.bindings.clone(),
+            })),
+        }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&TypeScheme> {
+        let mut binding = self.bindings.as_deref();
+        while let Some(current) = binding {
+            if current.name == name {
+                return Some(&current.scheme);
+            }
+            binding = current.parent.as_deref();
+        }
+        None
+    }
+
+    /// Bound names, each listed once.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        let mut binding = self.bindings.as_deref();
+        while let Some(current) = binding {
+            if !names.contains(&current.name.as_str()) {
+                names.push(current.name.as_str());
+            }
+            binding = current.parent.as_deref();
+        }
+        names
+    }
+
+    /// Type variables that are not quantified by the scheme they appear in.
+    /// Shadowed bindings are included, which can only make generalization
+    /// more conservative.
+    pub fn free_variables(&self) -> Vec<TypeVariable> {
+        let mut variables = Vec::new();
+        let mut binding = self.bindings.as_deref();
+        while let Some(current) = binding {
+            let mut scheme_variables = Vec::new();
+            current.scheme.body.free_variables(&mut scheme_variables);
+            variables.extend(
+                scheme_variables
+                    .into_iter()
+                    .filter(|variable| !current.scheme.variables.iter().any(|(quantified, _)| quantified == variable)),
+            );
+            binding = current.parent.as_deref();
+        }
+        variables
+    }
+}
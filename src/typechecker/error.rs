@@ -0,0 +1,65 @@
+use crate::lexer::token::Span;
+
+use super::types::{Type, TypePrinter};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TypeError {
+    Mismatch { expected: Type, found: Type, span: Span },
+    InfiniteType { variable: Type, found: Type, span: Span },
+    NotNumeric { found: Type, span: Span },
+    NotComparable { found: Type, span: Span },
+    NotAFunction { found: Type, span: Span },
+    UndefinedVariable { name: String, span: Span },
+    ArityMismatch { expected: usize, got: usize, span: Span },
+}
+
+impl TypeError {
+    /// Span of the operator or name whose type did not fit, or of the
+    /// arguments passed to a function of a different type.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Mismatch { span, .. }
+            | Self::InfiniteType { span, .. }
+            | Self::NotNumeric { span, .. }
+            | Self::NotComparable { span, .. }
+            | Self::NotAFunction { span, .. }
+            | Self::UndefinedVariable { span, .. }
+            | Self::ArityMismatch { span, .. } => *span,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Mismatch { .. } => "E0101",
+            Self::InfiniteType { .. } => "E0102",
+            Self::NotNumeric { .. } => "E0103",
+            Self::NotAFunction { .. } => "E0104",
+            Self::UndefinedVariable { .. } => "E0105",
+            Self::ArityMismatch { .. } => "E0106",
+            Self::NotComparable { .. } => "E0107",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        let mut printer = TypePrinter::default();
+        match self {
+            Self::Mismatch { expected, found, .. } => format!(
+                "Type mismatch - expected {}, found {}",
+                printer.print(expected),
+                printer.print(found)
+            ),
+            Self::InfiniteType { variable, found, .. } => format!(
+                "Infinite type - {} occurs in {}",
+                printer.print(variable),
+                printer.print(found)
+            ),
+            Self::NotNumeric { found, .. } => format!("Expected a number, found {}", printer.print(found)),
+            Self::NotComparable { found, .. } => format!("Values of type {} cannot be compared", printer.print(found)),
+            Self::NotAFunction { found, .. } => format!("Expected a function, found {}", printer.print(found)),
+            Self::UndefinedVariable { name, .. } => format!("Undefined variable {}", name),
+            Self::ArityMismatch { expected, got, .. } => {
+                format!("Function expects {} argument(s), got {}", expected, got)
+            }
+        }
+    }
+}
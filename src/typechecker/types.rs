@@ -0,0 +1,192 @@
+use std::{collections::HashMap, fmt};
+
+use crate::evaluator::context::NumericMode;
+
+pub type TypeVariable = u32;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Type {
+    Int,
+    Float,
+    Rational,
+    Bool,
+    Variable(TypeVariable),
+    Function(Vec<Type>, Box<Type>),
+}
+
+impl Type {
+    /// Type of integer literals, which are exact fractions in rational mode.
+    pub fn integer(numeric_mode: NumericMode) -> Self {
+        match numeric_mode {
+            NumericMode::Machine | NumericMode::BigInt => Self::Int,
+            NumericMode::Rational => Self::Rational,
+        }
+    }
+
+    pub fn occurs(&self, variable: TypeVariable) -> bool {
+        match self {
+            Self::Variable(v) => *v == variable,
+            Self::Function(parameters, result) => {
+                parameters.iter().any(|parameter| parameter.occurs(variable)) || result.occurs(variable)
+            }
+            Self::Int | Self::Float | Self::Rational | Self::Bool => false,
+        }
+    }
+
+    pub fn free_variables(&self, variables: &mut Vec<TypeVariable>) {
+        match self {
+            Self::Variable(v) => {
+                if !variables.contains(v) {
+                    variables.push(*v);
+                }
+            }
+            Self::Function(parameters, result) => {
+                parameters.iter().for_each(|parameter| parameter.free_variables(variables));
+                result.free_variables(variables);
+            }
+            Self::Int | Self::Float | Self::Rational | Self::Bool => (),
+        }
+    }
+}
+
+/// Types a variable can be instantiated with, each constraint allows
+/// fewer types than the ones before it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum Constraint {
+    #[default]
+    Any,
+    /// Types whose values can be tested for equality, every type but functions.
+    Equality,
+    /// `int`, `float` or `rational`.
+    Numeric,
+}
+
+/// A type quantified over some of its variables, e.g. `'a -> 'a`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TypeScheme {
+    pub variables: Vec<(TypeVariable, Constraint)>,
+    pub body: Type,
+}
+
+impl TypeScheme {
+    /// A scheme without quantified variables.
+    pub fn monomorphic(body: Type) -> Self {
+        Self {
+            variables: Vec::new(),
+            body,
+        }
+    }
+}
+
+/// Names type variables `'a`, `'b`, ... in the order they are first printed,
+/// so that types printed with the same printer share their names.
+#[derive(Default)]
+pub struct TypePrinter {
+    names: HashMap<TypeVariable, String>,
+}
+
+impl TypePrinter {
+    fn name(&mut self, variable: TypeVariable) -> String {
+        let count = self.names.len();
+        self.names
+            .entry(variable)
+            .or_insert_with(|| {
+                let letter = (b'a' + (count % 26) as u8) as char;
+                match count / 26 {
+                    0 => format!("'{}", letter),
+                    n => format!("'{}{}", letter, n),
+                }
+            })
+            .clone()
+    }
+
+    pub fn print(&mut self, ty: &Type) -> String {
+        match ty {
+            Type::Int => "int".to_string(),
+            Type::Float => "float".to_string(),
+            Type::Rational => "rational".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::Variable(variable) => self.name(*variable),
+            Type::Function(parameters, result) => {
+                let parameters = match parameters.as_slice() {
+                    [parameter @ Type::Function(..)] => format!("({})", self.print(parameter)),
+                    [parameter] => self.print(parameter),
+                    parameters => format!(
+                        "({})",
+                        parameters
+                            .iter()
+                            .map(|parameter| self.print(parameter))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                };
+                format!("{} -> {}", parameters, self.print(result))
+            }
+        }
+    }
+
+    /// Prints a scheme, listing its constrained variables up front:
+    /// `num 'a, eq 'b => ('a, 'b) -> 'a`.
+    pub fn print_scheme(&mut self, scheme: &TypeScheme) -> String {
+        let mut variables = Vec::new();
+        scheme.body.free_variables(&mut variables);
+
+        let constraints = variables
+            .iter()
+            .filter_map(|variable| {
+                let (_, constraint) = scheme.variables.iter().find(|(quantified, _)| quantified == variable)?;
+                match constraint {
+                    Constraint::Any => None,
+                    Constraint::Equality => Some(format!("eq {}", self.name(*variable))),
+                    Constraint::Numeric => Some(format!("num {}", self.name(*variable))),
+                }
+            })
+            .collect::<Vec<_>>();
+        let body = self.print(&scheme.body);
+        match constraints.is_empty() {
+            true => body,
+            false => format!("{} => {}", constraints.join(", "), body),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", TypePrinter::default().print(self))
+    }
+}
+
+impl fmt::Display for TypeScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", TypePrinter::default().print_scheme(self))
+    }
+}
+
+#[test]
+fn display() {
+    let function = |parameters, result| Type::Function(parameters, Box::new(result));
+
+    assert_eq!(function(vec![Type::Int], Type::Float).to_string(), "int -> float");
+    assert_eq!(function(vec![Type::Int, Type::Int], Type::Int).to_string(), "(int, int) -> int");
+    assert_eq!(function(vec![], Type::Int).to_string(), "() -> int");
+    assert_eq!(
+        function(vec![function(vec![Type::Variable(7)], Type::Variable(3))], Type::Variable(7)).to_string(),
+        "('a -> 'b) -> 'a"
+    );
+    assert_eq!(
+        function(vec![Type::Int], function(vec![Type::Int], Type::Int)).to_string(),
+        "int -> int -> int"
+    );
+
+    let scheme = TypeScheme {
+        variables: vec![(4, Constraint::Numeric), (5, Constraint::Any)],
+        body: function(vec![Type::Variable(5), Type::Variable(4)], Type::Variable(4)),
+    };
+    assert_eq!(scheme.to_string(), "num 'a => ('b, 'a) -> 'a");
+
+    let scheme = TypeScheme {
+        variables: vec![(0, Constraint::Equality), (1, Constraint::Numeric)],
+        body: function(vec![Type::Variable(0), Type::Variable(1)], Type::Bool),
+    };
+    assert_eq!(scheme.to_string(), "eq 'a, num 'b => ('a, 'b) -> bool");
+}
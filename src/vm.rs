@@ -0,0 +1,313 @@
+//! Bytecode backend: expressions are compiled into [`Chunk`]s of stack
+//! machine instructions and run by the [`Vm`], with the same results and
+//! errors as the tree walking evaluator.
+//!
+//! The VM is not the faster backend for code that runs once. Compiling an
+//! expression takes about as long as walking its tree, so a long generated
+//! expression, like the chain of 1000 operators in `benches/bytecode.rs`,
+//! takes about a third longer to compile and run than to walk. It gains on
+//! function bodies, which are compiled once and run on every call, and
+//! calls nest without growing the native stack.
+
+use std::{cell::OnceCell, rc::Rc};
+
+use crate::{
+    evaluator::{
+        apply_arithmetic, compare,
+        context::Context,
+        enter_function,
+        environment::Environment,
+        error::{EvalError, NumberKind},
+        negate, to_bool, to_float,
+        value::{Function, Value},
+    },
+    parser::syntax_tree::StatementNode,
+};
+
+use self::chunk::{Chunk, Op};
+pub use self::compiler::compile;
+
+pub mod chunk;
+mod compiler;
+
+/// A chunk being run: the instruction to run next and the environment its
+/// names are looked up in.
+struct Frame {
+    chunk: Rc<Chunk>,
+    next: usize,
+    environment: Environment,
+}
+
+/// Runs compiled chunks on a single value stack, calls push a frame instead
+/// of recursing, so the call depth is not bound by the native stack.
+/// Function bodies are compiled the first time they are called and kept
+/// with the function for later calls.
+#[derive(Default)]
+pub struct Vm {
+    stack: Vec<Value>,
+}
+
+impl Vm {
+    /// Runs the statement like [`StatementNode::execute`] does.
+    pub fn execute(
+        &mut self,
+        statement: &StatementNode,
+        context: &Context,
+        environment: &mut Environment,
+    ) -> Result<Value, EvalError> {
+        match statement {
+            StatementNode::LetNode { name, value, .. } => {
+                let value = self.run(Rc::new(compile(value, context)), context, environment)?;
+                *environment = environment.extend(name.clone(), value.clone());
+                Ok(value)
+            }
+            StatementNode::ExpressionStatementNode(exp) => self.run(Rc::new(compile(exp, context)), context, environment),
+            // nothing to compile, a function only captures the environment
+            StatementNode::FunctionNode { .. } | StatementNode::ErrorNode { .. } => {
+                statement.execute(context, environment)
+            }
+        }
+    }
+
+    /// Runs a chunk, returning the value it leaves on the stack.
+    pub fn run(&mut self, chunk: Rc<Chunk>, context: &Context, environment: &Environment) -> Result<Value, EvalError> {
+        let base = self.stack.len();
+        let mut callers = Vec::new();
+        let frame = Frame {
+            chunk,
+            next: 0,
+            environment: environment.clone(),
+        };
+        let result = self.run_frames(frame, &mut callers, context);
+        // a failed chunk leaves its operands and the calls it failed in behind
+        self.stack.truncate(base);
+        context.call_depth.set(context.call_depth.get() - callers.len());
+        result
+    }
+
+    /// Runs the frame until it returns, calls push the frame that made them
+    /// onto `callers` instead of running the function on the native stack.
+    fn run_frames(&mut self, mut frame: Frame, callers: &mut Vec<Frame>, context: &Context) -> Result<Value, EvalError> {
+        loop {
+            let (op, span) = (frame.chunk.code[frame.next], frame.chunk.spans[frame.next]);
+            frame.next += 1;
+            match op {
+                Op::Constant(constant) => self.stack.push(frame.chunk.constants[constant as usize].clone()),
+                Op::Int(value) => self.stack.push(Value::Int(value)),
+                Op::Load(name) => {
+                    let name = &frame.chunk.names[name as usize];
+                    let value = frame.environment.lookup(name).cloned().ok_or_else(|| EvalError::UndefinedVariable {
+                        name: name.clone(),
+                        span,
+                    })?;
+                    self.stack.push(value);
+                }
+                Op::Function(function) => {
+                    let template = &frame.chunk.functions[function as usize];
+                    let chunk = template.chunk.get_or_init(|| Rc::new(compile(&template.body, context)));
+                    self.stack.push(Value::Function(Rc::new(Function {
+                        name: None,
+                        parameters: vec![template.parameter.clone()],
+                        body: template.body.clone(),
+                        environment: frame.environment.clone(),
+                        chunk: OnceCell::from(chunk.clone()),
+                    })));
+                }
+                Op::Arithmetic(operator) => {
+                    let (left, right) = self.pop_pair();
+                    self.stack.push(apply_arithmetic(operator, left, right, span)?);
+                }
+                Op::Compare(operator) => {
+                    let (left, right) = self.pop_pair();
+                    self.stack.push(compare(operator, left, right, span)?);
+                }
+                Op::Negate => {
+                    let operand = self.pop();
+                    self.stack.push(negate(operand, span)?);
+                }
+                Op::ToFloat => {
+                    let operand = self.pop();
+                    self.stack.push(to_float(operand));
+                }
+                Op::Not => {
+                    let operand = self.pop();
+                    self.stack.push(Value::Bool(!to_bool(operand, span)?));
+                }
+                Op::Test => {
+                    let operand = self.pop();
+                    self.stack.push(Value::Bool(to_bool(operand, span)?));
+                }
+                Op::Jump(target) => frame.next = target as usize,
+                Op::JumpIfFalse(target) => {
+                    if !to_bool(self.pop(), span)? {
+                        frame.next = target as usize;
+                    }
+                }
+                Op::Call(count) => {
+                    let arguments = self.stack.split_off(self.stack.len() - count as usize);
+                    let callee = self.pop();
+                    let (function, environment) = enter_function(callee, arguments, span, context)?;
+                    let callee = Frame {
+                        chunk: function.chunk.get_or_init(|| Rc::new(compile(&function.body, context))).clone(),
+                        next: 0,
+                        environment,
+                    };
+                    callers.push(std::mem::replace(&mut frame, callee));
+                }
+                Op::Overflow => return Err(EvalError::Overflow { kind: NumberKind::Integer, span }),
+                Op::InvalidSyntax => return Err(EvalError::InvalidSyntax { span }),
+                // the result stays on the stack for the caller
+                Op::Return => match callers.pop() {
+                    Some(caller) => {
+                        frame = caller;
+                        context.call_depth.set(context.call_depth.get() - 1);
+                    }
+                    None => return Ok(self.pop()),
+                },
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("compiled code never pops an empty stack")
+    }
+
+    fn pop_pair(&mut self) -> (Value, Value) {
+        let right = self.pop();
+        (self.pop(), right)
+    }
+}
+
+#[cfg(test)]
+fn run_with_both_backends(source: &str, context: &Context) -> [Result<Value, EvalError>; 2] {
+    let (program, _) = crate::parser::parse_program_str(source);
+    let mut vm = Vm::default();
+    let run = |execute: &mut dyn FnMut(&StatementNode, &mut Environment) -> Result<Value, EvalError>| {
+        let mut environment = Environment::default();
+        let mut last = Ok(Value::Int(0));
+        for statement in &program.statements {
+            last = execute(statement, &mut environment);
+        }
+        last
+    };
+    [
+        run(&mut |statement, environment| statement.execute(context, environment)),
+        run(&mut |statement, environment| vm.execute(statement, context, environment)),
+    ]
+}
+
+#[test]
+fn same_results_as_the_tree_walker() {
+    use crate::evaluator::context::NumericMode;
+
+    let sources = [
+        "1 + 2 * 3 - 4 // 3 % 2",
+        "-2 ** 2 + (7 / 2) * 1.5",
+        "1 < 2 && 2.0 >= 1 || false",
+        "true == false != (3 > 4)",
+        "!true || !(1 == 1)",
+        "if 1 < 2 then 10 else 20",
+        "if false then 1 / 0 else 2",
+        "false && 1 / 0 == 0",
+        "true || 1 / 0 == 0",
+        "let k = 3; fn scale(x) = k * x; let k = 100; scale(2) + k",
+        "fn fact(n) = if n <= 1 then 1 else n * fact(n - 1)\nfact(10)",
+        "let twice = fun f -> fun x -> f(f(x)); twice(fun x -> x * 3)(2)",
+        "let add = fun a -> fun b -> a + b; let inc = add 1; inc(41)",
+        "fn fib(n) = if n < 2 then n else fib(n - 1) + fib(n - 2)\nfib(15)",
+        "2147483647 + 1",
+        "2147483648 + 1 / 0",
+        "-2147483648 + 1",
+        "-2147483649 + 1 / 0",
+        "1 / 0 + 2147483648",
+        "1 + true",
+        "-false",
+        "!1",
+        "true && 1",
+        "false || 1",
+        "if 1 then 2 else 3",
+        "x + 1",
+        "let y = 2; y(1)",
+        "fn f(a, b) = a; f(1)",
+        "fn forever(n) = forever(n + 1); forever(0)",
+        "1 + ; 2",
+        "(2 ** 40) // 3 - 7 % -2",
+        "let half = 1 / 2; half * 6 == 3 && half < 1",
+    ];
+    for mode in [NumericMode::Machine, NumericMode::BigInt, NumericMode::Rational] {
+        let context = Context {
+            numeric_mode: mode,
+            max_call_depth: 50,
+            ..Context::default()
+        };
+        for source in sources {
+            let [tree, bytecode] = run_with_both_backends(source, &context);
+            assert_eq!(bytecode, tree, "{} in {:?}", source, mode);
+            assert_eq!(context.call_depth.get(), 0, "{}", source);
+        }
+    }
+}
+
+#[test]
+fn calls_do_not_use_the_native_stack() {
+    let evaluation = std::thread::Builder::new().stack_size(2 * 1024 * 1024).spawn(|| {
+        let context = Context {
+            max_call_depth: 100_000,
+            ..Context::default()
+        };
+        let [_, bytecode] = run_with_both_backends("fn f(n) = if n == 0 then 0 else 1 + f(n - 1); f(50000)", &context);
+        (bytecode.map(|value| value.to_string()), context.call_depth.get())
+    });
+    assert_eq!(evaluation.unwrap().join().unwrap(), (Ok("50000".to_string()), 0));
+}
+
+#[test]
+fn keep_chunks_with_their_functions() {
+    let program = crate::parser::parse_valid_program("fn f(x) = x + 1; f(1); f(2)");
+    let (context, mut environment) = (Context::default(), Environment::default());
+    let mut vm = Vm::default();
+    for statement in &program.statements {
+        vm.execute(statement, &context, &mut environment).unwrap();
+    }
+    let Some(Value::Function(function)) = environment.lookup("f") else {
+        panic!("f is not a function");
+    };
+    assert!(function.chunk.get().is_some());
+
+    // nothing outlives the function, the VM keeps no reference to its body
+    let StatementNode::FunctionNode { body, .. } = &program.statements[0] else {
+        panic!("{:?} is not a function definition", program.statements[0]);
+    };
+    drop(environment);
+    assert_eq!(Rc::strong_count(body), 1);
+}
+
+#[test]
+fn disassemble() {
+    let statement = crate::parser::parse_valid_statement("if x > 1 && f(x) then 2 * x else 0.5");
+    let StatementNode::ExpressionStatementNode(expression) = statement else {
+        panic!("{:?} is not an expression", statement);
+    };
+    assert_eq!(
+        compile(&expression, &Context::default()).to_string(),
+        "\
+0000  load           0  x
+0001  int            1
+0002  greater
+0003  jump_if_false  9
+0004  load           1  f
+0005  load           0  x
+0006  call           1
+0007  test
+0008  jump          10
+0009  constant       0  false
+0010  jump_if_false 15
+0011  int            2
+0012  load           0  x
+0013  mul
+0014  jump          16
+0015  constant       1  0.5
+0016  return
+"
+    );
+}
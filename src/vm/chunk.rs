@@ -0,0 +1,155 @@
+use std::{cell::OnceCell, fmt, rc::Rc};
+
+use crate::{
+    evaluator::{value::Value, ArithmeticOperator},
+    lexer::token::Span,
+    parser::syntax_tree::{ComparisonOperator, ExpressionNode},
+};
+
+/// A single instruction of the stack machine. Operands index the pools of
+/// the chunk or point at other instructions.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Op {
+    /// Pushes a constant.
+    Constant(u32),
+    /// Pushes a machine integer, which is kept in the instruction as most
+    /// literals are small integers.
+    Int(i32),
+    /// Pushes the value bound to a name.
+    Load(u32),
+    /// Pushes a new function closing over the current environment.
+    Function(u32),
+    /// Pops the right and then the left operand, pushes the result.
+    Arithmetic(ArithmeticOperator),
+    Compare(ComparisonOperator),
+    Negate,
+    Not,
+    /// Converts the number on top of the stack to a float.
+    ToFloat,
+    /// Checks that the top of the stack is a boolean, leaving it there.
+    Test,
+    Jump(u32),
+    /// Pops a boolean and jumps if it is false.
+    JumpIfFalse(u32),
+    /// Pops the arguments and then the function, pushes the result.
+    Call(u32),
+    /// Fails with an overflow, for integer literals out of range.
+    Overflow,
+    /// Fails on a part of the source that could not be parsed.
+    InvalidSyntax,
+    /// Ends the chunk with the value on top of the stack.
+    Return,
+}
+
+/// Parameter and body of a lambda, turned into a function when it runs.
+/// The functions made from it share the chunk of the body.
+#[derive(Debug)]
+pub struct FunctionTemplate {
+    pub parameter: String,
+    pub body: Rc<ExpressionNode>,
+    pub chunk: OnceCell<Rc<Chunk>>,
+}
+
+/// Compiled expression: instructions together with the span each one
+/// reports errors at, and the pools they refer to.
+#[derive(Default, Debug)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    pub spans: Vec<Span>,
+    pub constants: Vec<Value>,
+    pub names: Vec<String>,
+    pub functions: Vec<FunctionTemplate>,
+}
+
+impl Chunk {
+    /// Appends an instruction, returning its index.
+    pub fn push(&mut self, op: Op, span: Span) -> usize {
+        self.code.push(op);
+        self.spans.push(span);
+        self.code.len() - 1
+    }
+
+    /// Index of a new constant. Constants are not shared, generated
+    /// expressions can have too many of them to look for duplicates.
+    pub fn constant(&mut self, value: Value) -> u32 {
+        self.constants.push(value);
+        (self.constants.len() - 1) as u32
+    }
+
+    /// Index of a name, every name has a single entry.
+    pub fn name(&mut self, name: &str) -> u32 {
+        let index = self.names.iter().position(|known| known == name).unwrap_or_else(|| {
+            self.names.push(name.to_string());
+            self.names.len() - 1
+        });
+        index as u32
+    }
+}
+
+/// Disassembles the chunk one instruction per line, with the pool entries
+/// the operands refer to:
+///
+/// ```text
+/// 0000  constant       0  2.5
+/// 0001  load           0  x
+/// 0002  mul
+/// 0003  return
+/// ```
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, op) in self.code.iter().enumerate() {
+            let (name, operand) = match op {
+                Op::Constant(constant) => {
+                    ("constant", Some((i64::from(*constant), self.constants[*constant as usize].to_string())))
+                }
+                Op::Int(value) => ("int", Some((i64::from(*value), String::new()))),
+                Op::Load(name) => ("load", Some((i64::from(*name), self.names[*name as usize].clone()))),
+                Op::Function(function) => {
+                    let template = &self.functions[*function as usize];
+                    ("function", Some((i64::from(*function), format!("fun {} -> ...", template.parameter))))
+                }
+                Op::Arithmetic(operator) => (arithmetic_name(*operator), None),
+                Op::Compare(operator) => (comparison_name(*operator), None),
+                Op::Negate => ("negate", None),
+                Op::Not => ("not", None),
+                Op::ToFloat => ("to_float", None),
+                Op::Test => ("test", None),
+                Op::Jump(target) => ("jump", Some((i64::from(*target), String::new()))),
+                Op::JumpIfFalse(target) => ("jump_if_false", Some((i64::from(*target), String::new()))),
+                Op::Call(arguments) => ("call", Some((i64::from(*arguments), String::new()))),
+                Op::Overflow => ("overflow", None),
+                Op::InvalidSyntax => ("invalid_syntax", None),
+                Op::Return => ("return", None),
+            };
+            let line = match operand {
+                Some((operand, comment)) => format!("{:04}  {:<13} {:>2}  {}", index, name, operand, comment),
+                None => format!("{:04}  {}", index, name),
+            };
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+fn arithmetic_name(operator: ArithmeticOperator) -> &'static str {
+    match operator {
+        ArithmeticOperator::Add => "add",
+        ArithmeticOperator::Sub => "sub",
+        ArithmeticOperator::Mul => "mul",
+        ArithmeticOperator::Div => "div",
+        ArithmeticOperator::IntDiv => "int_div",
+        ArithmeticOperator::Mod => "mod",
+        ArithmeticOperator::Pow => "pow",
+    }
+}
+
+fn comparison_name(operator: ComparisonOperator) -> &'static str {
+    match operator {
+        ComparisonOperator::Equal => "equal",
+        ComparisonOperator::NotEqual => "not_equal",
+        ComparisonOperator::Less => "less",
+        ComparisonOperator::LessEqual => "less_equal",
+        ComparisonOperator::Greater => "greater",
+        ComparisonOperator::GreaterEqual => "greater_equal",
+    }
+}
@@ -0,0 +1,227 @@
+use std::cell::OnceCell;
+
+use crate::{
+    evaluator::{context::Context, int_literal, value::Value, ArithmeticOperator},
+    lexer::token::Span,
+    numeric::bigint::BigInt,
+    parser::syntax_tree::{ExpressionNode, FactorNode, TermNode},
+};
+
+use super::chunk::{Chunk, FunctionTemplate, Op};
+
+/// Compiles an expression into a chunk that leaves its value on the stack.
+/// Integer literals are turned into values of the context's numeric mode
+/// here, so a chunk is only valid for that mode.
+pub fn compile(expression: &ExpressionNode, context: &Context) -> Chunk {
+    let mut compiler = Compiler {
+        chunk: Chunk::default(),
+        context,
+    };
+    compiler.expression(expression);
+    compiler.chunk.push(Op::Return, expression.span());
+    compiler.chunk
+}
+
+/// Operands are compiled left to right, and every instruction that can fail
+/// carries the span the tree walker reports the same error at, so both
+/// fail in the same order and at the same place.
+struct Compiler<'a> {
+    chunk: Chunk,
+    context: &'a Context,
+}
+
+impl Compiler<'_> {
+    fn expression(&mut self, expression: &ExpressionNode) {
+        match expression {
+            ExpressionNode::SingleTermNode(term) => self.term(term),
+            ExpressionNode::OrNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                self.expression(left);
+                let to_right = self.chunk.push(Op::JumpIfFalse(0), *operator_span);
+                self.constant(Value::Bool(true), *operator_span);
+                let to_end = self.chunk.push(Op::Jump(0), *operator_span);
+                self.patch(to_right);
+                self.expression(right);
+                self.chunk.push(Op::Test, *operator_span);
+                self.patch(to_end);
+            }
+            ExpressionNode::AndNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                self.expression(left);
+                let to_false = self.chunk.push(Op::JumpIfFalse(0), *operator_span);
+                self.expression(right);
+                self.chunk.push(Op::Test, *operator_span);
+                let to_end = self.chunk.push(Op::Jump(0), *operator_span);
+                self.patch(to_false);
+                self.constant(Value::Bool(false), *operator_span);
+                self.patch(to_end);
+            }
+            ExpressionNode::ComparisonNode {
+                left,
+                operator,
+                right,
+                operator_span,
+            } => {
+                self.expression(left);
+                self.expression(right);
+                self.chunk.push(Op::Compare(*operator), *operator_span);
+            }
+            ExpressionNode::AdditionTermNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                self.expression(left);
+                self.term(right);
+                self.chunk.push(Op::Arithmetic(ArithmeticOperator::Add), *operator_span);
+            }
+            ExpressionNode::SubstractionTermNode {
+                left,
+                right,
+                operator_span,
+            } => {
+                self.expression(left);
+                self.term(right);
+                self.chunk.push(Op::Arithmetic(ArithmeticOperator::Sub), *operator_span);
+            }
+        }
+    }
+
+    fn term(&mut self, term: &TermNode) {
+        let (operator, left, right, operator_span) = match term {
+            TermNode::SingleFactorNode(factor) => return self.factor(factor),
+            TermNode::MultiplicationFactorNode {
+                left,
+                right,
+                operator_span,
+            } => (ArithmeticOperator::Mul, left, right, operator_span),
+            TermNode::DivisionFactorNode {
+                left,
+                right,
+                operator_span,
+            } => (ArithmeticOperator::Div, left, right, operator_span),
+            TermNode::IntegerDivisionFactorNode {
+                left,
+                right,
+                operator_span,
+            } => (ArithmeticOperator::IntDiv, left, right, operator_span),
+            TermNode::ModuloFactorNode {
+                left,
+                right,
+                operator_span,
+            } => (ArithmeticOperator::Mod, left, right, operator_span),
+        };
+        self.term(left);
+        self.factor(right);
+        self.chunk.push(Op::Arithmetic(operator), *operator_span);
+    }
+
+    fn factor(&mut self, factor: &FactorNode) {
+        match factor {
+            FactorNode::LiteralNode { value, span } => self.int_literal(value, *span),
+            FactorNode::FloatLiteralNode { value, span } => self.constant(Value::Float(*value), *span),
+            FactorNode::BoolLiteralNode { value, span } => self.constant(Value::Bool(*value), *span),
+            FactorNode::VariableNode { name, span } => {
+                let name = self.chunk.name(name);
+                self.chunk.push(Op::Load(name), *span);
+            }
+            FactorNode::LambdaNode { parameter, body, .. } => {
+                self.chunk.functions.push(FunctionTemplate {
+                    parameter: parameter.clone(),
+                    body: body.clone(),
+                    chunk: OnceCell::new(),
+                });
+                let function = (self.chunk.functions.len() - 1) as u32;
+                self.chunk.push(Op::Function(function), factor.span());
+            }
+            FactorNode::IfNode {
+                condition,
+                consequence,
+                alternative,
+                if_span,
+                widen,
+                ..
+            } => {
+                self.expression(condition);
+                let to_alternative = self.chunk.push(Op::JumpIfFalse(0), *if_span);
+                self.expression(consequence);
+                let to_end = self.chunk.push(Op::Jump(0), *if_span);
+                self.patch(to_alternative);
+                self.expression(alternative);
+                self.patch(to_end);
+                if widen.get() {
+                    self.chunk.push(Op::ToFloat, *if_span);
+                }
+            }
+            FactorNode::CallNode {
+                callee,
+                arguments,
+                arguments_span,
+            } => {
+                self.factor(callee);
+                for argument in arguments {
+                    self.expression(argument);
+                }
+                self.chunk.push(Op::Call(arguments.len() as u32), *arguments_span);
+            }
+            FactorNode::ExpressionNode { expression, .. } => self.expression(expression),
+            FactorNode::PowerNode {
+                base,
+                exponent,
+                operator_span,
+            } => {
+                self.factor(base);
+                self.factor(exponent);
+                self.chunk.push(Op::Arithmetic(ArithmeticOperator::Pow), *operator_span);
+            }
+            FactorNode::NegativeExpressionNode { operand, operator_span } => match &**operand {
+                // folded into the literal like the tree walker does
+                FactorNode::LiteralNode { value, span } => self.int_literal(&-value, operator_span.to(*span)),
+                operand => {
+                    self.factor(operand);
+                    self.chunk.push(Op::Negate, *operator_span);
+                }
+            },
+            FactorNode::NotExpressionNode { operand, operator_span } => {
+                self.factor(operand);
+                self.chunk.push(Op::Not, *operator_span);
+            }
+            FactorNode::ErrorNode { span } => {
+                self.chunk.push(Op::InvalidSyntax, *span);
+            }
+        }
+    }
+
+    fn int_literal(&mut self, value: &BigInt, span: Span) {
+        match int_literal(value, span, self.context) {
+            Ok(Value::Int(value)) => {
+                self.chunk.push(Op::Int(value), span);
+            }
+            Ok(value) => self.constant(value, span),
+            // reported only once the literal is reached, like the tree walker does
+            Err(_) => {
+                self.chunk.push(Op::Overflow, span);
+            }
+        }
+    }
+
+    fn constant(&mut self, value: Value, span: Span) {
+        let constant = self.chunk.constant(value);
+        self.chunk.push(Op::Constant(constant), span);
+    }
+
+    /// Points the jump at `index` to the next instruction.
+    fn patch(&mut self, index: usize) {
+        let target = self.chunk.code.len() as u32;
+        match &mut self.chunk.code[index] {
+            Op::Jump(to) | Op::JumpIfFalse(to) => *to = target,
+            op => unreachable!("{:?} is not a jump", op),
+        }
+    }
+}